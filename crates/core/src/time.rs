@@ -39,6 +39,12 @@ impl Micros {
         (numer / denom as u128) as u64
     }
 
+    /// Scale by a non-negative `factor`, rounding to the nearest representable value.
+    /// Keeps sub-microsecond precision; results too large for the range saturate.
+    pub fn scale(self, factor: f64) -> Self {
+        Self((self.0 as f64 * factor).round() as u64)
+    }
+
     /// Returns the raw Q32.32 value.
     pub const fn raw(self) -> u64 {
         self.0
@@ -116,4 +122,16 @@ mod tests {
         assert_eq!((a * 2).to_ticks(60), 600); // 10 seconds
         assert_eq!((a / 5).to_ticks(60), 60); // 1 second
     }
+
+    #[test]
+    fn micros_scale() {
+        let m = Micros::from_secs(1);
+        assert_eq!(m.scale(0.5), Micros::from_millis(500));
+        assert_eq!(m.scale(2.5).to_ticks(60), 150);
+
+        // 1 s / 1.15 = 869565.2 us: the fraction is kept, not truncated
+        let scaled = m.scale(1.0 / 1.15);
+        assert!(scaled > Micros::from_micros(869_565));
+        assert!(scaled < Micros::from_micros(869_566));
+    }
 }
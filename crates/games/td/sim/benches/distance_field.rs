@@ -0,0 +1,73 @@
+//! Full recompute vs incremental repair of the distance field on generated maps.
+//!
+//! Run with `cargo bench -p sim_td --bench distance_field`.
+
+use sim_core::Game;
+use sim_td::pathing::{compute_distance_field, update_distance_field};
+use sim_td::world::CellState;
+use sim_td::{TdConfig, TdGame};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Cells toggled per measurement, cycling through the walkable cells of the map.
+const TOGGLES: usize = 200;
+
+fn time_per_toggle(mut toggle: impl FnMut(usize)) -> Duration {
+    let start = Instant::now();
+    for i in 0..TOGGLES {
+        toggle(i);
+    }
+    start.elapsed() / TOGGLES as u32
+}
+
+fn main() {
+    for maze_size in [10, 30, 60, 100] {
+        let config = TdConfig {
+            maze_size,
+            ..TdConfig::default()
+        };
+        let game = TdGame::new(config, 1);
+        let state = game.state();
+        let goals = state.config.goals.clone();
+        let cells: Vec<(u16, u16)> = (0..state.world.grid.height)
+            .flat_map(|y| (0..state.world.grid.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let grid = &state.world.grid;
+                !grid.is_blocked_idx(grid.idx(x, y)) && !goals.contains(&(x, y))
+            })
+            .step_by(7)
+            .collect();
+
+        // Each toggle blocks a cell, then unblocks it again
+        let mut grid = state.world.grid.clone();
+        let mut dist = state.dist.clone();
+        let full = time_per_toggle(|i| {
+            let (x, y) = cells[i % cells.len()];
+            grid.set(x, y, CellState::Building);
+            compute_distance_field(&grid, &goals, &mut dist);
+            grid.set(x, y, CellState::Empty);
+            compute_distance_field(&grid, &goals, &mut dist);
+            black_box(&dist);
+        });
+
+        let incremental = time_per_toggle(|i| {
+            let (x, y) = cells[i % cells.len()];
+            grid.set(x, y, CellState::Building);
+            update_distance_field(&grid, &goals, &[(x, y)], &mut dist);
+            grid.set(x, y, CellState::Empty);
+            update_distance_field(&grid, &goals, &[(x, y)], &mut dist);
+            black_box(&dist);
+        });
+        assert_eq!(dist, state.dist);
+
+        println!(
+            "maze_size {:>3} ({}x{}): full {:>10.1?}  incremental {:>10.1?}  speedup {:.1}x",
+            maze_size,
+            grid.width,
+            grid.height,
+            full,
+            incremental,
+            full.as_secs_f64() / incremental.as_secs_f64(),
+        );
+    }
+}
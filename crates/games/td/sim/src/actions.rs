@@ -1,8 +1,10 @@
-use crate::config::TowerKind;
-use crate::world::TowerId;
+use crate::config::{TowerKind, UpgradeBranch};
+use crate::world::{Targeting, TowerId};
 
 #[derive(Clone, Debug)]
 pub enum TdAction {
     PlaceTower { x: u16, y: u16, kind: TowerKind },
-    UpgradeTower { tower_id: TowerId },
+    UpgradeTower { tower_id: TowerId, branch: UpgradeBranch },
+    RepairTower { tower_id: TowerId },
+    SetTargeting { tower_id: TowerId, targeting: Targeting },
 }
@@ -0,0 +1,1160 @@
+//! Game operations shared by the MCP tools, the REST API and the agent WebSocket.
+//!
+//! Each operation pre-validates against the current observation where that gives a
+//! better error than the simulation would (e.g. building on a wall), so all transports
+//! reject the same requests with the same messages.
+
+use crate::actions::TdAction;
+use crate::config::{TdConfig, UpgradeBranch};
+use crate::map::{self, MapDef, MapError};
+use crate::mapgen;
+use crate::mcp::types::*;
+use crate::observe;
+use crate::pathing;
+use crate::placement;
+use crate::summary;
+use crate::whatif;
+use crate::TdGame;
+use sim_core::{Game, Micros};
+use sim_server::{
+    GameServer, JoinError, MatchError, MatchStatus, ObserveNextError, SessionToken, SubmitError,
+};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Error from a [`TdApi`] operation. The message is suitable for showing to the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    /// The match (or a referenced tower) does not exist.
+    NotFound(String),
+    /// Invalid session or parameters, or the action failed pre-validation.
+    Rejected(String),
+    /// The request is valid but can't be served in the match's current state.
+    Unavailable(String),
+}
+
+impl ApiError {
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::NotFound(message)
+            | ApiError::Rejected(message)
+            | ApiError::Unavailable(message) => message,
+        }
+    }
+
+    fn from_match_error(context: &str, e: MatchError) -> Self {
+        let message = format!("{}: {:?}", context, e);
+        match e {
+            MatchError::NotFound => ApiError::NotFound(message),
+            MatchError::InvalidSession => ApiError::Rejected(message),
+            MatchError::Terminated => ApiError::Unavailable(message),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+fn map_error(e: MapError) -> ApiError {
+    match e {
+        MapError::NotFound(_) => ApiError::NotFound(e.to_string()),
+        MapError::Io(_) => ApiError::Unavailable(e.to_string()),
+        MapError::InvalidName(_) | MapError::Parse(_) | MapError::Invalid(_) => {
+            ApiError::Rejected(e.to_string())
+        }
+    }
+}
+
+/// `value` if it is absent or within `range`.
+fn check_range<T: PartialOrd + fmt::Display + Copy>(
+    name: &str,
+    value: Option<T>,
+    range: RangeInclusive<T>,
+) -> Result<Option<T>, ApiError> {
+    match value {
+        Some(v) if !range.contains(&v) => Err(ApiError::Rejected(format!(
+            "Invalid {}: {} is outside {}-{}",
+            name,
+            v,
+            range.start(),
+            range.end()
+        ))),
+        _ => Ok(value),
+    }
+}
+
+/// The upgrade branch named by `branch`; damage if omitted.
+fn upgrade_branch(branch: &Option<String>) -> Result<UpgradeBranch, String> {
+    branch
+        .as_deref()
+        .map_or(Ok(UpgradeBranch::Damage), observe::string_to_branch)
+}
+
+fn secs_to_micros(secs: f32) -> Micros {
+    Micros::from_millis((secs * 1000.0).round() as u32)
+}
+
+/// The game configuration for `params`, without the named map. Matches run at 20 ticks
+/// per second unless `params` says otherwise.
+fn match_config(params: &CreateMatchParams) -> Result<TdConfig, ApiError> {
+    let mut config = TdConfig {
+        tick_hz: 20,
+        ..TdConfig::default()
+    };
+    params.difficulty.unwrap_or_default().apply(&mut config);
+
+    check_range("required_players", Some(params.required_players), 1..=8)?;
+    check_range("waves", Some(params.waves), 1..=100)?;
+    config.player_count = params.required_players;
+    config.waves_total = params.waves;
+
+    if let Some(v) = check_range("max_leaks", params.max_leaks, 0..=1000)? {
+        config.max_leaks = v;
+    }
+    if let Some(v) = params.combat_events {
+        config.combat_events = v;
+    }
+    if let Some(v) = check_range("tick_hz", params.tick_hz, 1..=120)? {
+        config.tick_hz = v;
+    }
+    let pause = params.inter_wave_pause_secs;
+    if let Some(v) = check_range("inter_wave_pause_secs", pause, 0.0..=600.0)? {
+        config.inter_wave_pause = secs_to_micros(v);
+    }
+    let interval = params.spawn_interval_secs;
+    if let Some(v) = check_range("spawn_interval_secs", interval, 0.05..=60.0)? {
+        config.spawn_interval = secs_to_micros(v);
+    }
+    if let Some(v) = check_range("build_time_secs", params.build_time_secs, 0.0..=600.0)? {
+        config.build_time = secs_to_micros(v);
+    }
+    if let Some(v) = check_range("mob_speed", params.mob_speed, 0.1..=10.0)? {
+        config.mob_speed = v;
+    }
+
+    let generator_params = params.maze_size.is_some()
+        || params.dilation_base_radius.is_some()
+        || params.dilation_amplitude.is_some();
+    if generator_params && params.map.is_some() {
+        return Err(ApiError::Rejected(
+            "maze_size and dilation_* only apply to generated maps; omit them with 'map'"
+                .to_string(),
+        ));
+    }
+    let maze_size = params.maze_size;
+    if let Some(v) = check_range("maze_size", maze_size, 3..=mapgen::MAX_MAZE_SIZE)? {
+        config.maze_size = v;
+    }
+    let radius = params.dilation_base_radius;
+    if let Some(v) = check_range("dilation_base_radius", radius, 0.0..=10.0)? {
+        config.dilation_base_radius = v;
+    }
+    let amplitude = params.dilation_amplitude;
+    if let Some(v) = check_range("dilation_amplitude", amplitude, 0.0..=10.0)? {
+        config.dilation_amplitude = v;
+    }
+
+    if let Some(tower) = &params.basic_tower {
+        let spec = &mut config.basic_spec;
+        if let Some(v) = check_range("basic_tower.cost", tower.cost, 1..=10_000)? {
+            spec.cost = v;
+        }
+        if let Some(v) = check_range("basic_tower.hp", tower.hp, 1..=100_000)? {
+            spec.hp = v;
+        }
+        if let Some(v) = check_range("basic_tower.range", tower.range, 0.5..=50.0)? {
+            spec.range = v;
+        }
+        if let Some(v) = check_range("basic_tower.damage", tower.damage, 1..=100_000)? {
+            spec.damage = v;
+        }
+        let fire_period = tower.fire_period_secs;
+        if let Some(v) = check_range("basic_tower.fire_period_secs", fire_period, 0.05..=60.0)? {
+            spec.fire_period = secs_to_micros(v);
+        }
+        if let Some(v) = check_range("basic_tower.regen", tower.regen, 0..=100_000)? {
+            spec.regen = v;
+        }
+    }
+
+    Ok(config)
+}
+
+/// Alternative routes reported by [`TdApi::get_current_path`].
+const ALTERNATIVE_ROUTES: usize = 2;
+
+/// A map fingerprint as reported by the API.
+fn fingerprint(map: &MapDef) -> String {
+    format!("{:016x}", map.fingerprint())
+}
+
+fn positions(cells: &[(u16, u16)]) -> Vec<Position> {
+    cells.iter().map(|&(x, y)| Position { x, y }).collect()
+}
+
+/// Tower Defense operations on a shared [`GameServer`].
+#[derive(Clone)]
+pub struct TdApi {
+    game_server: Arc<GameServer<TdGame>>,
+    /// Where `create_match` looks up named maps. Without one, only generated maps.
+    map_dir: Option<Arc<PathBuf>>,
+}
+
+impl TdApi {
+    pub fn new(game_server: Arc<GameServer<TdGame>>) -> Self {
+        Self {
+            game_server,
+            map_dir: None,
+        }
+    }
+
+    /// Serve named maps from `dir` (see [`map`]).
+    pub fn with_map_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.map_dir = Some(Arc::new(dir.into()));
+        self
+    }
+
+    pub fn game_server(&self) -> &Arc<GameServer<TdGame>> {
+        &self.game_server
+    }
+
+    /// Record an action rejected by pre-validation in the server metrics.
+    fn reject(&self, reason: &str, message: String) -> ApiError {
+        self.game_server.metrics().record_action_rejected(reason);
+        ApiError::Rejected(message)
+    }
+
+    pub async fn create_match(
+        &self,
+        params: CreateMatchParams,
+    ) -> Result<CreateMatchResult, ApiError> {
+        let mut game_config = match_config(&params)?;
+        let map = self.load_map(&params, &game_config).await?;
+        let map_fingerprint = fingerprint(&map);
+        game_config.map = Some(map);
+
+        let match_id = self
+            .game_server
+            .create_match_with_players(game_config, params.seed, params.required_players)
+            .await
+            .map_err(|e| ApiError::Unavailable(format!("Failed to create match: {}", e)))?;
+
+        Ok(CreateMatchResult {
+            match_id,
+            map_fingerprint,
+        })
+    }
+
+    /// The map for `params`: the named one, or the one generated from the seed. Matches
+    /// get generated maps from here rather than from `TdGame::new`, so a failure is an
+    /// error for the caller instead of a panic.
+    async fn load_map(
+        &self,
+        params: &CreateMatchParams,
+        config: &TdConfig,
+    ) -> Result<MapDef, ApiError> {
+        let named = match &params.map {
+            Some(name) => {
+                let Some(dir) = self.map_dir.clone() else {
+                    return Err(ApiError::Rejected(
+                        "This server has no map directory; omit 'map' to generate one"
+                            .to_string(),
+                    ));
+                };
+                Some((dir, name.clone()))
+            }
+            None => None,
+        };
+        let config = config.clone();
+        let seed = params.seed;
+        tokio::task::spawn_blocking(move || match named {
+            Some((dir, name)) => map::load_named(&dir, &name).map_err(map_error),
+            None => mapgen::generate_map(&config, seed)
+                .map_err(|e| ApiError::Rejected(format!("Failed to generate map: {}", e))),
+        })
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Failed to load map: {}", e)))?
+    }
+
+    /// The map `create_match` would start a match on with the same settings, without
+    /// creating one.
+    pub async fn preview_map(
+        &self,
+        params: PreviewMapParams,
+    ) -> Result<PreviewMapResult, ApiError> {
+        let params = CreateMatchParams {
+            seed: params.seed,
+            map: params.map,
+            maze_size: params.maze_size,
+            dilation_base_radius: params.dilation_base_radius,
+            dilation_amplitude: params.dilation_amplitude,
+            ..CreateMatchParams::default()
+        };
+        let mut config = match_config(&params)?;
+        let map = self.load_map(&params, &config).await?;
+        let fingerprint = fingerprint(&map);
+        config.map = Some(map);
+
+        tokio::task::spawn_blocking(move || {
+            let game = TdGame::try_new(config, params.seed)
+                .map_err(|e| ApiError::Rejected(format!("Failed to generate map: {}", e)))?;
+            let obs = game.observe(0, 0);
+            let routes = pathing::mob_routes(game.state());
+            let ascii =
+                observe::render_ascii_map(&obs, &routes, &observe::AsciiMapOptions::default());
+            Ok(PreviewMapResult {
+                map_width: obs.map_width,
+                map_height: obs.map_height,
+                spawns: obs.spawns,
+                goals: obs.goals,
+                walkable: obs.walkable,
+                path_lengths: routes
+                    .iter()
+                    .map(|r| r.as_ref().map(|r| r.length()))
+                    .collect(),
+                fingerprint,
+                ascii,
+            })
+        })
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Failed to preview map: {}", e)))?
+    }
+
+    /// Names of the maps `create_match` accepts.
+    pub async fn list_maps(&self) -> Result<ListMapsResult, ApiError> {
+        let Some(dir) = self.map_dir.clone() else {
+            return Ok(ListMapsResult { maps: Vec::new() });
+        };
+        let maps = tokio::task::spawn_blocking(move || map::list_named(&dir))
+            .await
+            .map_err(|e| ApiError::Unavailable(format!("Failed to list maps: {}", e)))?
+            .map_err(map_error)?;
+        Ok(ListMapsResult { maps })
+    }
+
+    pub async fn list_matches(&self) -> ListMatchesResult {
+        let matches = self
+            .game_server
+            .list_matches()
+            .await
+            .into_iter()
+            .map(|m| MatchInfoResult {
+                match_id: m.match_id,
+                status: status_info(m.status),
+                current_tick: m.current_tick,
+                player_count: m.player_count,
+                lag_ticks: m.lag_ticks,
+            })
+            .collect();
+
+        ListMatchesResult { matches }
+    }
+
+    pub async fn terminate_match(&self, match_id: u64) -> Result<(), ApiError> {
+        self.game_server
+            .terminate_match(match_id)
+            .await
+            .map_err(|e| match e {
+                MatchError::NotFound => {
+                    ApiError::NotFound(format!("Failed to terminate match: {}", e))
+                }
+                _ => ApiError::Unavailable(format!("Failed to terminate match: {}", e)),
+            })
+    }
+
+    pub async fn join_match(&self, match_id: u64) -> Result<JoinMatchResult, ApiError> {
+        let (session, player_id) =
+            self.game_server
+                .join_match(match_id)
+                .await
+                .map_err(|e| match e {
+                    JoinError::NotFound => {
+                        ApiError::NotFound(format!("Failed to join match: {}", e))
+                    }
+                    _ => ApiError::Unavailable(format!("Failed to join match: {}", e)),
+                })?;
+
+        Ok(JoinMatchResult {
+            session_token: session.0,
+            player_id,
+        })
+    }
+
+    pub async fn leave_match(&self, match_id: u64, session_token: u64) -> Result<(), ApiError> {
+        self.game_server
+            .leave_match(match_id, SessionToken(session_token))
+            .await
+            .map_err(|e| {
+                let message = format!("Failed to leave match: {}", e);
+                match e {
+                    MatchError::NotFound => ApiError::NotFound(message),
+                    _ => ApiError::Rejected(message),
+                }
+            })
+    }
+
+    /// Current full observation, including the `walkable` grid.
+    pub async fn observe(
+        &self,
+        match_id: u64,
+        session_token: u64,
+    ) -> Result<TdObservation, ApiError> {
+        self.game_server
+            .observe(match_id, SessionToken(session_token))
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to observe", e))
+    }
+
+    /// Current spectator view without a session, minus the `walkable` grid
+    /// (see [`get_map`](Self::get_map)).
+    pub async fn observe_spectator(&self, match_id: u64) -> Result<TdObservation, ApiError> {
+        let mut obs = self
+            .game_server
+            .observe_spectator(match_id)
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to observe", e))?;
+        obs.walkable.clear();
+        Ok(obs)
+    }
+
+    /// The static map layout. Does not require a session.
+    pub async fn get_map(&self, match_id: u64) -> Result<MatchMapResult, ApiError> {
+        let obs = self
+            .game_server
+            .observe_spectator(match_id)
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to observe", e))?;
+
+        Ok(MatchMapResult {
+            map_width: obs.map_width,
+            map_height: obs.map_height,
+            spawns: obs.spawns,
+            goals: obs.goals,
+            walkable: obs.walkable,
+        })
+    }
+
+    pub async fn place_tower(&self, params: PlaceTowerParams) -> Result<ActionResult, ApiError> {
+        // Pre-validate using current game state
+        let obs = self
+            .game_server
+            .observe(params.match_id, SessionToken(params.session_token))
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to validate", e))?;
+
+        if params.x >= obs.map_width || params.y >= obs.map_height {
+            return Err(self.reject(
+                "out_of_bounds",
+                format!(
+                    "Cannot place tower: ({},{}) is out of bounds (map is {}x{})",
+                    params.x, params.y, obs.map_width, obs.map_height
+                ),
+            ));
+        }
+        let idx = params.y as usize * obs.map_width as usize + params.x as usize;
+        if !obs.walkable.get(idx).copied().unwrap_or(false) {
+            return Err(self.reject(
+                "not_walkable",
+                format!(
+                    "Cannot place tower: ({},{}) is non-walkable terrain",
+                    params.x, params.y
+                ),
+            ));
+        }
+        if obs
+            .towers
+            .iter()
+            .any(|t| t.x == params.x && t.y == params.y)
+            || obs
+                .build_queue
+                .iter()
+                .any(|b| b.x == params.x && b.y == params.y)
+        {
+            return Err(self.reject(
+                "occupied",
+                format!(
+                    "Cannot place tower: ({},{}) is already occupied",
+                    params.x, params.y
+                ),
+            ));
+        }
+        if obs.gold < obs.tower_cost {
+            return Err(self.reject(
+                "insufficient_gold",
+                format!(
+                    "Cannot place tower: insufficient gold (need {}, have {})",
+                    obs.tower_cost, obs.gold
+                ),
+            ));
+        }
+
+        let action = TdAction::PlaceTower {
+            x: params.x,
+            y: params.y,
+            kind: observe::string_to_kind(&params.tower_type),
+        };
+
+        self.submit(
+            params.match_id,
+            params.session_token,
+            action,
+            params.intended_tick,
+            "Failed to place tower",
+        )
+        .await
+    }
+
+    pub async fn upgrade_tower(
+        &self,
+        params: UpgradeTowerParams,
+    ) -> Result<ActionResult, ApiError> {
+        let id = observe::string_to_tower_id(&params.tower_id).map_err(ApiError::Rejected)?;
+        let branch = upgrade_branch(&params.branch).map_err(ApiError::Rejected)?;
+
+        // Pre-validate using current game state
+        let obs = self
+            .game_server
+            .observe(params.match_id, SessionToken(params.session_token))
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to validate", e))?;
+
+        let Some(tower) = obs.towers.iter().find(|t| t.id == params.tower_id) else {
+            self.game_server
+                .metrics()
+                .record_action_rejected("tower_not_found");
+            return Err(ApiError::NotFound(format!(
+                "Cannot upgrade tower: tower '{}' not found",
+                params.tower_id
+            )));
+        };
+        let name = observe::branch_to_string(branch);
+        let Some(info) = tower.upgrades.iter().find(|b| b.branch == name) else {
+            return Err(self.reject(
+                "upgrade_unavailable",
+                format!("Cannot upgrade tower: no {} branch for this tower", name),
+            ));
+        };
+        if tower.upgrade_level >= tower.max_upgrade_level || info.level >= info.max_level {
+            return Err(self.reject(
+                "upgrade_maxed",
+                format!(
+                    "Cannot upgrade tower: no upgrades left (tower {}/{}, {} branch {}/{})",
+                    tower.upgrade_level, tower.max_upgrade_level, name, info.level, info.max_level
+                ),
+            ));
+        }
+        if obs.gold < tower.upgrade_cost {
+            return Err(self.reject(
+                "insufficient_gold",
+                format!(
+                    "Cannot upgrade tower: insufficient gold (need {}, have {})",
+                    tower.upgrade_cost, obs.gold
+                ),
+            ));
+        }
+
+        let action = TdAction::UpgradeTower {
+            tower_id: id,
+            branch,
+        };
+
+        self.submit(
+            params.match_id,
+            params.session_token,
+            action,
+            params.intended_tick,
+            "Failed to upgrade tower",
+        )
+        .await
+    }
+
+    pub async fn repair_tower(&self, params: RepairTowerParams) -> Result<ActionResult, ApiError> {
+        let id = observe::string_to_tower_id(&params.tower_id).map_err(ApiError::Rejected)?;
+
+        // Pre-validate using current game state
+        let obs = self
+            .game_server
+            .observe(params.match_id, SessionToken(params.session_token))
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to validate", e))?;
+
+        let Some(tower) = obs.towers.iter().find(|t| t.id == params.tower_id) else {
+            self.game_server
+                .metrics()
+                .record_action_rejected("tower_not_found");
+            return Err(ApiError::NotFound(format!(
+                "Cannot repair tower: tower '{}' not found",
+                params.tower_id
+            )));
+        };
+        if tower.hp >= tower.max_hp {
+            return Err(self.reject(
+                "tower_undamaged",
+                format!(
+                    "Cannot repair tower: tower '{}' is at full HP",
+                    params.tower_id
+                ),
+            ));
+        }
+        if obs.gold < tower.repair_cost {
+            return Err(self.reject(
+                "insufficient_gold",
+                format!(
+                    "Cannot repair tower: insufficient gold (need {}, have {})",
+                    tower.repair_cost, obs.gold
+                ),
+            ));
+        }
+
+        let action = TdAction::RepairTower { tower_id: id };
+
+        self.submit(
+            params.match_id,
+            params.session_token,
+            action,
+            params.intended_tick,
+            "Failed to repair tower",
+        )
+        .await
+    }
+
+    pub async fn set_targeting(
+        &self,
+        params: SetTargetingParams,
+    ) -> Result<ActionResult, ApiError> {
+        let id = observe::string_to_tower_id(&params.tower_id).map_err(ApiError::Rejected)?;
+        let targeting =
+            observe::string_to_targeting(&params.targeting).map_err(ApiError::Rejected)?;
+
+        // Pre-validate using current game state
+        let obs = self
+            .game_server
+            .observe(params.match_id, SessionToken(params.session_token))
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to validate", e))?;
+
+        if !obs.towers.iter().any(|t| t.id == params.tower_id) {
+            self.game_server
+                .metrics()
+                .record_action_rejected("tower_not_found");
+            return Err(ApiError::NotFound(format!(
+                "Cannot set targeting: tower '{}' not found",
+                params.tower_id
+            )));
+        }
+
+        let action = TdAction::SetTargeting {
+            tower_id: id,
+            targeting,
+        };
+
+        self.submit(
+            params.match_id,
+            params.session_token,
+            action,
+            params.intended_tick,
+            "Failed to set targeting",
+        )
+        .await
+    }
+
+    async fn submit(
+        &self,
+        match_id: u64,
+        session_token: u64,
+        action: TdAction,
+        intended_tick: u64,
+        context: &str,
+    ) -> Result<ActionResult, ApiError> {
+        let (action_id, scheduled_tick) = self
+            .game_server
+            .submit_action(match_id, SessionToken(session_token), action, intended_tick)
+            .await
+            .map_err(|e| {
+                let message = format!("{}: {}", context, e);
+                match e {
+                    SubmitError::NotFound => ApiError::NotFound(message),
+                    SubmitError::InvalidSession => ApiError::Rejected(message),
+                    SubmitError::Terminated => ApiError::Unavailable(message),
+                }
+            })?;
+
+        Ok(ActionResult {
+            action_id,
+            scheduled_tick,
+        })
+    }
+
+    pub async fn get_buildable_cells(
+        &self,
+        match_id: u64,
+        session_token: u64,
+    ) -> Result<GetBuildableCellsResult, ApiError> {
+        let obs = self.observe(match_id, session_token).await?;
+
+        let mut buildable_cells = Vec::new();
+        for y in 0..obs.map_height {
+            for x in 0..obs.map_width {
+                let idx = y as usize * obs.map_width as usize + x as usize;
+                if obs.walkable.get(idx).copied().unwrap_or(false) {
+                    buildable_cells.push(Position { x, y });
+                }
+            }
+        }
+
+        Ok(GetBuildableCellsResult {
+            map_width: obs.map_width,
+            map_height: obs.map_height,
+            buildable_cells,
+        })
+    }
+
+    /// The current mob route, with alternatives and chokepoints; see [`pathing`].
+    pub async fn get_current_path(
+        &self,
+        match_id: u64,
+        session_token: u64,
+    ) -> Result<GetCurrentPathResult, ApiError> {
+        let state = self
+            .game_server
+            .inspect(match_id, SessionToken(session_token), |game, _| {
+                game.state().clone()
+            })
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to get path", e))?;
+
+        // Chokepoints take a pathfinding pass per route cell; keep it off the async workers
+        tokio::task::spawn_blocking(move || {
+            let route_info = |r: &pathing::Route| RouteInfo {
+                path: positions(&r.cells),
+                length: r.length(),
+            };
+            let routes = pathing::mob_routes(&state);
+            let first = routes.first().and_then(Option::as_ref);
+            GetCurrentPathResult {
+                path_exists: routes.iter().all(Option::is_some),
+                path: first.map(|r| positions(&r.cells)).unwrap_or_default(),
+                path_length: first.map_or(0.0, |r| r.length()),
+                routes: routes.iter().map(|r| r.as_ref().map(route_info)).collect(),
+                alternative_routes: state
+                    .config
+                    .spawns
+                    .iter()
+                    .flat_map(|&spawn| {
+                        pathing::alternative_routes(&state, spawn, ALTERNATIVE_ROUTES)
+                    })
+                    .map(|r| route_info(&r))
+                    .collect(),
+                chokepoints: positions(&pathing::chokepoints(&state)),
+            }
+        })
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Failed to get path: {}", e)))
+    }
+
+    /// The current grid as ASCII art with a legend, optionally cropped to a region.
+    pub async fn render_map(&self, params: RenderMapParams) -> Result<String, ApiError> {
+        let (obs, routes) = self
+            .game_server
+            .inspect(
+                params.match_id,
+                SessionToken(params.session_token),
+                |game, player_id| {
+                    let state = game.state();
+                    (
+                        game.observe(state.tick, player_id),
+                        pathing::mob_routes(state),
+                    )
+                },
+            )
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to render map", e))?;
+
+        let region = if params.x.is_some()
+            || params.y.is_some()
+            || params.width.is_some()
+            || params.height.is_some()
+        {
+            let x = params.x.unwrap_or(0);
+            let y = params.y.unwrap_or(0);
+            if x >= obs.map_width || y >= obs.map_height {
+                return Err(ApiError::Rejected(format!(
+                    "Cannot render map: ({},{}) is out of bounds (map is {}x{})",
+                    x, y, obs.map_width, obs.map_height
+                )));
+            }
+            Some(observe::MapRegion {
+                x,
+                y,
+                width: params.width.unwrap_or(obs.map_width - x),
+                height: params.height.unwrap_or(obs.map_height - y),
+            })
+        } else {
+            None
+        };
+
+        let options = observe::AsciiMapOptions {
+            show_path: params.show_path,
+            rulers: params.rulers,
+            region,
+        };
+        Ok(observe::render_ascii_map(&obs, &routes, &options))
+    }
+
+    /// Concise summary of the match for agents; see [`summary::summarize`].
+    pub async fn get_summary(
+        &self,
+        match_id: u64,
+        session_token: u64,
+    ) -> Result<SummaryResult, ApiError> {
+        self.game_server
+            .inspect(match_id, SessionToken(session_token), |game, _| {
+                summary::summarize(game.state())
+            })
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to summarize", e))
+    }
+
+    /// Path coverage and path-length impact of candidate tower cells; see
+    /// [`placement::analyze_placements`].
+    pub async fn analyze_placements(
+        &self,
+        params: AnalyzePlacementsParams,
+    ) -> Result<AnalyzePlacementsResult, ApiError> {
+        let state = self
+            .game_server
+            .inspect(
+                params.match_id,
+                SessionToken(params.session_token),
+                |game, _| game.state().clone(),
+            )
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to analyze placements", e))?;
+
+        // One pathfinding pass per candidate near the path; keep it off the async workers
+        tokio::task::spawn_blocking(move || {
+            placement::analyze_placements(&state, params.candidates.as_deref(), params.limit)
+        })
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Failed to analyze placements: {}", e)))
+    }
+
+    /// Predict the outcome of hypothetical actions on a fork of the match; see
+    /// [`whatif::simulate`].
+    pub async fn what_if(&self, params: WhatIfParams) -> Result<WhatIfResult, ApiError> {
+        let actions = params
+            .actions
+            .iter()
+            .map(|action| match action {
+                ActionParams::PlaceTower { x, y, tower_type } => Ok(TdAction::PlaceTower {
+                    x: *x,
+                    y: *y,
+                    kind: observe::string_to_kind(tower_type),
+                }),
+                ActionParams::UpgradeTower { tower_id, branch } => Ok(TdAction::UpgradeTower {
+                    tower_id: observe::string_to_tower_id(tower_id)?,
+                    branch: upgrade_branch(branch)?,
+                }),
+                ActionParams::RepairTower { tower_id } => Ok(TdAction::RepairTower {
+                    tower_id: observe::string_to_tower_id(tower_id)?,
+                }),
+                ActionParams::SetTargeting {
+                    tower_id,
+                    targeting,
+                } => Ok(TdAction::SetTargeting {
+                    tower_id: observe::string_to_tower_id(tower_id)?,
+                    targeting: observe::string_to_targeting(targeting)?,
+                }),
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(ApiError::Rejected)?;
+        let horizon = match params.ticks {
+            Some(ticks) => whatif::Horizon::Ticks(ticks),
+            None => whatif::Horizon::ThroughNextWave,
+        };
+
+        let (game, player_id) = self
+            .game_server
+            .inspect(
+                params.match_id,
+                SessionToken(params.session_token),
+                |game, player_id| (game.clone(), player_id),
+            )
+            .await
+            .map_err(|e| ApiError::from_match_error("Failed to simulate", e))?;
+
+        // Up to minutes of game time; keep it off the async workers
+        tokio::task::spawn_blocking(move || whatif::simulate(game, player_id, actions, horizon))
+            .await
+            .map_err(|e| ApiError::Unavailable(format!("Failed to simulate: {}", e)))
+    }
+
+    /// Long-poll for the next decision-tick observation. The `walkable` grid is omitted
+    /// since the map layout never changes; use [`get_buildable_cells`](Self::get_buildable_cells).
+    pub async fn observe_next(
+        &self,
+        params: ObserveNextParams,
+    ) -> Result<ObserveNextResult, ApiError> {
+        let (mut obs, timed_out) = self
+            .game_server
+            .observe_next(
+                params.match_id,
+                SessionToken(params.session_token),
+                params.after_tick,
+                params.max_wait_ms,
+            )
+            .await
+            .map_err(|e| match e {
+                ObserveNextError::NotFound => ApiError::NotFound("Match not found".to_string()),
+                ObserveNextError::InvalidSession => {
+                    ApiError::Rejected("Invalid session".to_string())
+                }
+                ObserveNextError::AlreadyWaiting => ApiError::Unavailable(
+                    "Already waiting for observation. Only one observe_next allowed at a time."
+                        .to_string(),
+                ),
+                ObserveNextError::ObservationNotReady => {
+                    ApiError::Unavailable("Observation not ready yet".to_string())
+                }
+            })?;
+
+        obs.walkable.clear();
+
+        Ok(ObserveNextResult {
+            timed_out,
+            observation: obs,
+        })
+    }
+}
+
+pub fn status_info(status: MatchStatus) -> MatchStatusInfo {
+    match status {
+        MatchStatus::WaitingForPlayers { current, required } => {
+            MatchStatusInfo::WaitingForPlayers { current, required }
+        }
+        MatchStatus::Running => MatchStatusInfo::Running,
+        MatchStatus::Finished(outcome) => MatchStatusInfo::Finished {
+            outcome: format!("{:?}", outcome),
+        },
+        MatchStatus::Terminated => MatchStatusInfo::Terminated,
+    }
+}
+
+/// The complete rules and mechanics of the game.
+pub fn rules() -> RulesResult {
+    RulesResult {
+        game: "Tower Defense".to_string(),
+        objective: "Defend your base by building towers to stop waves of mobs from reaching the goal. Survive all waves to win. (NOTE: You are observing only - the simulation runs at a fixed rate regardless of your calls)".to_string(),
+        win_condition: "Complete all waves without exceeding the maximum number of leaks (mobs reaching the goal).".to_string(),
+        lose_condition: "If more than max_leaks mobs reach the goal, you lose.".to_string(),
+        map: MapRules {
+            description: "A 2D grid with procedurally generated terrain. Each cell is either walkable (path) or non-walkable (wall). Mobs only travel on walkable cells. Towers can only be placed on walkable, unoccupied cells. The terrain is generated from a maze and dilated into organic paths, unless the match was created with a named map (see list_maps), which has a fixed layout. Use preview_map to inspect a seed's map before creating a match.".to_string(),
+            default_size: "30x30 cells (maze_size=10, scale factor 3)".to_string(),
+            spawn_description: "Mobs spawn at the Start tiles determined by the map; waves alternate between spawns unless the map assigns them. Check the 'spawns' field in observations.".to_string(),
+            goal_description: "Mobs try to reach the nearest Goal tile determined by the map. Check the 'goals' field in observations. Mobs pathfind along walkable cells around towers.".to_string(),
+        },
+        towers: TowerRules {
+            placement: "Use the place_tower tool to queue a tower build. Towers can ONLY be placed on buildable cells (use get_buildable_cells to get them). Non-walkable cells are permanent terrain walls and cannot be built on. Cost scales with wave number (base_cost * 1.12^wave). Cell is blocked immediately when build starts.".to_string(),
+            attack: "Towers automatically attack one mob within range every fire_period, chosen by the tower's targeting: closest to the tower (default), first (closest to the goal along the path), last, strongest (most HP) or weakest. Change it with set_targeting. Upgrades raise damage, range, fire rate or HP, or make hits slow mobs; towers report their damage, range, fire_period_ticks and slow after upgrades.".to_string(),
+            destruction: "Mobs attack adjacent towers. When a tower's HP reaches 0, it is destroyed and the cell becomes unblocked. Repair damaged towers to max_hp with repair_tower; towers with regen also recover HP each second between waves.".to_string(),
+            tower_types: vec![
+                TowerTypeInfo {
+                    name: "Basic".to_string(),
+                    cost: 15,
+                    hp: 100,
+                    range: 4,
+                    damage: 5,
+                    description: "Standard attack tower. Base cost 15 (scales with wave). Base damage 5 (scales with upgrades). Range 4.".to_string(),
+                },
+            ],
+        },
+        mobs: MobRules {
+            movement: "Mobs spawn during waves and pathfind toward the goal, moving around towers. They take the shortest available path. If the path is completely blocked, mobs will attack towers in their way to create a path.".to_string(),
+            leaking: "When a mob reaches the goal, it 'leaks' and is removed. Each leak increments the leak counter.".to_string(),
+            combat: "Mobs attack towers that block their path. When adjacent to a blocking tower, they deal damage instead of moving.".to_string(),
+        },
+        waves: WaveRules {
+            progression: "The game consists of multiple waves with exponential scaling. Mob HP and wave size grow each wave.".to_string(),
+            pause_between: "There is an inter_wave_pause between waves (also before the first wave), giving you time to build and upgrade towers.".to_string(),
+            scaling: "Mob HP: 10 * 1.15^wave * players. Wave size: 8 * 1.08^wave * players. Both scale linearly with player count. The match's difficulty multiplies both: easy 0.75/0.85, normal 1/1, hard 1.3/1.15, nightmare 1.7/1.3.".to_string(),
+        },
+        economy: EconomyRules {
+            income: "Starting gold: 50 + 30*(players-1). Wave reward: 25 * 1.12^wave * players. Kill reward: 1 * 1.08^wave. Income scales with player count. The match's difficulty multiplies starting gold and wave rewards: easy 1.3, normal 1, hard 0.85, nightmare 0.7.".to_string(),
+            spending: "Tower build cost: base_cost * 1.12^wave. Upgrade cost: 20 * 1.20^next_level, whatever the branch. Repair cost: ceil(base_cost * missing_hp / (2 * max_hp)). Gold is deducted immediately.".to_string(),
+        },
+        actions: vec![
+            ActionRule {
+                name: "get_buildable_cells".to_string(),
+                description: "Get all buildable cell coordinates. The map never changes, so call this once after joining.".to_string(),
+                parameters: "match_id, session_token.".to_string(),
+            },
+            ActionRule {
+                name: "get_current_path".to_string(),
+                description: "Get the current shortest path mobs follow from each spawn to the nearest goal. Changes when towers are built/destroyed. Call after placing towers to see the new route. Also lists the path length, alternative routes mobs would take if the main one got longer, and chokepoints that cannot be walked around.".to_string(),
+                parameters: "match_id, session_token.".to_string(),
+            },
+            ActionRule {
+                name: "get_summary".to_string(),
+                description: "Get a concise summary: seconds until the next wave, affordable towers, leaks remaining, towers covering the mob path, mobs closest to the goal, and a threat estimate for the next wave.".to_string(),
+                parameters: "match_id, session_token.".to_string(),
+            },
+            ActionRule {
+                name: "analyze_placements".to_string(),
+                description: "For each buildable cell (or the candidates given), report how many mob path cells are within tower range and how the spawn-to-goal path length would change with a tower there. Flags placements that would fully block the path.".to_string(),
+                parameters: "match_id, session_token, candidates (optional list of {x, y}), limit (default 20).".to_string(),
+            },
+            ActionRule {
+                name: "what_if".to_string(),
+                description: "Simulate hypothetical place/upgrade actions on a copy of the match and fast-forward through the next wave (or a number of ticks). Reports predicted kills, leaks, gold and tower losses without spending anything. Exact, since the game is deterministic.".to_string(),
+                parameters: "match_id, session_token, actions (list of {type: PlaceTower, x, y, tower_type}, {type: UpgradeTower, tower_id, branch}, {type: RepairTower, tower_id} or {type: SetTargeting, tower_id, targeting}), ticks (optional).".to_string(),
+            },
+            ActionRule {
+                name: "render_map".to_string(),
+                description: "Get the current map as ASCII art: walls, open cells, the mob path, towers by upgrade level, pending builds, mobs, spawn and goal, with coordinate rulers and a legend. Optionally crop to a region.".to_string(),
+                parameters: "match_id, session_token, show_path (default true), rulers (default true), x, y, width, height (all optional).".to_string(),
+            },
+            ActionRule {
+                name: "place_tower".to_string(),
+                description: "Queue a tower to be built at the specified coordinates. Use the place_tower MCP tool directly.".to_string(),
+                parameters: "match_id, session_token, intended_tick, x, y, tower_type (default 'Basic').".to_string(),
+            },
+            ActionRule {
+                name: "upgrade_tower".to_string(),
+                description: "Upgrade one branch of a tower by a level: damage (x1.15 per level for Basic), range (+0.5 cells), fire_rate (x1.15 shots per second), hp (x1.25 max HP, healing the HP gained) or slow (hits slow mobs by 15% per level for a second). Each branch has a max level, and a tower can take at most max_upgrade_level upgrades in total; see the tower's upgrades. Cost: 20 * 1.20^(upgrade_level+1). Use the upgrade_tower MCP tool directly.".to_string(),
+                parameters: "match_id, session_token, intended_tick, tower_id (from observe response), branch (default 'damage').".to_string(),
+            },
+            ActionRule {
+                name: "repair_tower".to_string(),
+                description: "Restore a damaged tower to full HP. Cost: ceil(base_cost * missing_hp / (2 * max_hp)), the tower's repair_cost. Use the repair_tower MCP tool directly.".to_string(),
+                parameters: "match_id, session_token, intended_tick, tower_id (from observe response).".to_string(),
+            },
+            ActionRule {
+                name: "set_targeting".to_string(),
+                description: "Choose which mob in range a tower shoots: first, last, strongest, weakest or closest. Free. Use the set_targeting MCP tool directly.".to_string(),
+                parameters: "match_id, session_token, intended_tick, tower_id, targeting.".to_string(),
+            },
+        ],
+        tips: vec![
+            "*** CRITICAL: observe_next is READ-ONLY and DOES NOT CONTROL the simulation. The server ticks at a fixed rate regardless of your calls ***".to_string(),
+            "*** Calling observe_next faster/slower does NOT speed up/slow down the game. You are only polling for state ***".to_string(),
+            "RECOMMENDED START: 1) Call get_buildable_cells to learn the map layout. 2) Call get_current_path to see the mob route from spawn to goal. 3) Use this info to plan tower placements, then start building.".to_string(),
+            "Call render_map to see the map, path, towers and mobs at a glance; crop with x, y, width and height to zoom in on an area before placing towers there.".to_string(),
+            "Use observe_next to stream game state updates. Pass after_tick=0 for the first call.".to_string(),
+            "IMPORTANT: Always pass the tick value from the previous response. If you repeat the same after_tick, you WILL be forced to wait for new data - this prevents spam.".to_string(),
+            "Your actions (place_tower, upgrade_tower) are independent of observe_next - submit them with intended_tick and the server will execute them.".to_string(),
+            "The mob path can change when towers are placed or destroyed — mobs reroute around obstacles. Call get_current_path periodically (e.g. after a batch of tower placements) to see the updated route and adjust your strategy.".to_string(),
+            "Place towers strategically on walkable cells to create longer paths - mobs will pathfind around them. analyze_placements ranks cells by path coverage and shows how much each one lengthens the path.".to_string(),
+            "Upgrade existing towers for more damage rather than always building new ones. Upgraded towers are more gold-efficient.".to_string(),
+            "Tower build cost increases each wave, so building early is cheaper.".to_string(),
+            "Call get_summary between waves for a quick read on the next wave's threat and how well your towers cover the path, instead of working it out from raw observations.".to_string(),
+            "Before spending gold on a plan, try it with what_if and compare the predicted leaks against doing nothing (an empty actions list).".to_string(),
+            "Watch the wave_status in observe_next to know when the next wave starts and how many mobs it will have.".to_string(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Difficulty;
+
+    fn params() -> CreateMatchParams {
+        serde_json::from_str(r#"{"seed": 1}"#).unwrap()
+    }
+
+    #[test]
+    fn match_config_defaults() {
+        let config = match_config(&params()).unwrap();
+        let default = TdConfig::default();
+        assert_eq!(
+            (config.tick_hz, config.waves_total, config.player_count),
+            (20, 10, 1)
+        );
+        assert_eq!(config.max_leaks, default.max_leaks);
+        assert_eq!(config.mob_hp(3, 1), default.mob_hp(3, 1));
+    }
+
+    #[test]
+    fn match_config_overrides_preset() {
+        let params = CreateMatchParams {
+            difficulty: Some(Difficulty::Hard),
+            max_leaks: Some(7),
+            spawn_interval_secs: Some(0.25),
+            basic_tower: Some(TowerSpecParams {
+                damage: Some(9),
+                ..TowerSpecParams::default()
+            }),
+            ..params()
+        };
+        let config = match_config(&params).unwrap();
+
+        let mut hard = TdConfig::default();
+        Difficulty::Hard.apply(&mut hard);
+        assert_eq!(config.max_leaks, 7);
+        assert_eq!(config.inter_wave_pause, hard.inter_wave_pause);
+        assert_eq!(config.mob_hp(3, 1), hard.mob_hp(3, 1));
+        assert_eq!(config.spawn_interval, Micros::from_millis(250));
+        assert_eq!(config.basic_spec.damage, 9);
+        assert_eq!(config.basic_spec.cost, hard.basic_spec.cost);
+    }
+
+    #[test]
+    fn match_config_rejects_invalid() {
+        let invalid = |params: CreateMatchParams| match_config(&params).unwrap_err();
+
+        assert_eq!(
+            invalid(CreateMatchParams {
+                waves: 0,
+                ..params()
+            }),
+            ApiError::Rejected("Invalid waves: 0 is outside 1-100".to_string())
+        );
+        assert!(matches!(
+            invalid(CreateMatchParams {
+                mob_speed: Some(f32::NAN),
+                ..params()
+            }),
+            ApiError::Rejected(_)
+        ));
+        assert!(matches!(
+            invalid(CreateMatchParams {
+                basic_tower: Some(TowerSpecParams {
+                    range: Some(0.0),
+                    ..TowerSpecParams::default()
+                }),
+                ..params()
+            }),
+            ApiError::Rejected(_)
+        ));
+        assert_eq!(
+            invalid(CreateMatchParams {
+                maze_size: Some(mapgen::MAX_MAZE_SIZE + 1),
+                ..params()
+            }),
+            ApiError::Rejected("Invalid maze_size: 171 is outside 3-170".to_string())
+        );
+        // Generator settings contradict a named map
+        assert!(matches!(
+            invalid(CreateMatchParams {
+                map: Some("serpentine".to_string()),
+                maze_size: Some(8),
+                ..params()
+            }),
+            ApiError::Rejected(_)
+        ));
+    }
+
+    #[test]
+    fn difficulty_parses_lowercase() {
+        let params: CreateMatchParams =
+            serde_json::from_str(r#"{"seed": 1, "difficulty": "nightmare"}"#).unwrap();
+        assert_eq!(params.difficulty, Some(Difficulty::Nightmare));
+    }
+}
@@ -3,32 +3,56 @@
 //! Single binary that:
 //! - Runs the MCP server on --mcp-port (default 3000) for AI agent connections
 //! - Runs the web/SSE server on --web-port (default 8080) for browser viewers
+//!   (SSE or WebSocket), WebSocket agents (`/api/agent/ws/{match_id}`), the REST API
+//!   (`/api/matches...`, described by `/api/openapi.json`) and Prometheus scrapes
+//!   (`/metrics`)
 //! - Both share the same in-process GameServer instance (no HTTP proxy overhead)
+//!
+//! With `--stdio`, a single MCP session is served over stdin/stdout instead of HTTP so
+//! agent clients can launch the game as a subprocess; logs go to stderr and the process
+//! exits when the client closes stdin. `--no-web` disables the web server.
 
 use axum::{
     Router,
-    extract::{Path, State},
+    extract::{
+        Path, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
     http::StatusCode,
     response::{
         IntoResponse,
         sse::{Event, KeepAlive, Sse},
     },
-    routing::get,
+    routing::{get, post},
 };
 use clap::Parser;
-use rmcp::transport::streamable_http_server::{
-    session::local::LocalSessionManager, StreamableHttpService, StreamableHttpServerConfig,
+use rmcp::ServiceExt;
+use sim_server::{
+    GameServer, MatchError, MatchStatus, MatchSubscription, ServerConfig, SessionToken,
+    SubscriptionError,
 };
-use sim_server::{GameServer, MatchError, MatchStatus, ServerConfig, SessionToken};
 use sim_td::mcp::types::*;
-use sim_td::mcp::TdMcpServer;
-use sim_td::TdGame;
-use std::{collections::HashMap, convert::Infallible, path::PathBuf, sync::Arc, time::Duration};
-use std::cmp::max;
-use tokio::{net::TcpListener, sync::RwLock};
+use sim_td::mcp::{self, TdMcpServer};
+use sim_td::api::{self, TdApi};
+use sim_td::{observe, rest, TdGame};
+use td_types::{AgentMessage, AgentRequest, GameEventRecord, ObservationDelta, ViewerFrame};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    net::TcpListener,
+    sync::{RwLock, broadcast},
+};
 use tokio_stream::StreamExt;
 use tower_http::{cors::CorsLayer, services::ServeDir};
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{EnvFilter, fmt::writer::BoxMakeWriter};
 
 #[derive(Parser, Debug)]
 #[command(name = "td-server")]
@@ -38,6 +62,14 @@ struct Args {
     #[arg(long, default_value = "3000")]
     mcp_port: u16,
 
+    /// Serve MCP over stdin/stdout instead of HTTP (--mcp-port is ignored)
+    #[arg(long)]
+    stdio: bool,
+
+    /// Don't start the web server
+    #[arg(long)]
+    no_web: bool,
+
     /// Port for web/SSE server
     #[arg(long, default_value = "8080")]
     web_port: u16,
@@ -45,14 +77,53 @@ struct Args {
     /// Static files directory (WASM app)
     #[arg(long, default_value = "crates/games/td/viewer/dist")]
     static_dir: PathBuf,
+
+    /// Max extra ticks a lagging match may run per wake-up to catch up with
+    /// wall-clock time (0 = skip missed ticks)
+    #[arg(long, default_value = "0")]
+    max_catch_up_ticks: u32,
+
+    /// Worker threads stepping matches (0 = one per available CPU)
+    #[arg(long, default_value = "0")]
+    worker_threads: usize,
+
+    /// Decision ticks per second, i.e. how often agents receive observations
+    #[arg(long, default_value = "1")]
+    interaction_rate: u32,
+
+    /// Directory of named maps (`<name>.txt` or `<name>.json`) for create_match
+    #[arg(long, default_value = "crates/games/td/maps")]
+    map_dir: PathBuf,
 }
 
-/// Tracks a per-match broadcast channel for SSE fan-out.
+/// How often an idle match stream checks whether it still has subscribers.
+const SUBSCRIBER_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Ticks between periodic keyframes on a match stream.
+const KEYFRAME_INTERVAL_TICKS: u64 = 200;
+
+/// Tracks a per-match broadcast channel for SSE/WebSocket fan-out.
+///
+/// Frames are [`ViewerFrame`]s: a keyframe followed by per-tick deltas, serialized once
+/// and shared by all subscribers.
 struct MatchStream {
-    tx: tokio::sync::broadcast::Sender<String>,
+    tx: broadcast::Sender<String>,
+    /// Most recent observation. New subscribers get it as a keyframe, and the next delta
+    /// is computed against it.
+    latest: Arc<std::sync::Mutex<Option<TdObservation>>>,
+    /// Set by a resync request; the next frame is sent as a keyframe.
+    keyframe_requested: Arc<AtomicBool>,
     _task: tokio::task::JoinHandle<()>,
 }
 
+/// A subscriber's view of a [`MatchStream`].
+struct MatchStreamSubscription {
+    /// Keyframe of the latest observation, if one has been received yet.
+    keyframe: Option<String>,
+    rx: broadcast::Receiver<String>,
+    keyframe_requested: Arc<AtomicBool>,
+}
+
 /// Tracks the match-list broadcast channel for SSE fan-out.
 struct MatchListStream {
     tx: tokio::sync::broadcast::Sender<String>,
@@ -61,7 +132,8 @@ struct MatchListStream {
 
 struct AppState {
     game_server: Arc<GameServer<TdGame>>,
-    /// Active match streams: match_id -> broadcast sender + poll task.
+    api: TdApi,
+    /// Active match streams: match_id -> broadcast sender + forwarding task.
     streams: Arc<RwLock<HashMap<u64, MatchStream>>>,
     /// Active match-list stream: created on first subscriber, cleared when all disconnect.
     match_list_stream: Arc<RwLock<Option<MatchListStream>>>,
@@ -69,38 +141,81 @@ struct AppState {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    // In stdio mode stdout carries the MCP protocol
+    let log_writer = if args.stdio {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with_writer(log_writer)
         .init();
 
-    let args = Args::parse();
-
     // Shared game server
     let config = ServerConfig {
         simulation_rate: 20,
-        interaction_rate: 1,
+        interaction_rate: args.interaction_rate,
         max_matches: 100,
         event_buffer_capacity: 1024,
+        max_catch_up_ticks: args.max_catch_up_ticks,
+        worker_threads: args.worker_threads,
+        ..ServerConfig::default()
     };
     let game_server = Arc::new(GameServer::<TdGame>::new(config));
+    let api = TdApi::new(game_server.clone()).with_map_dir(&args.map_dir);
 
-    // --- MCP server ---
-    let mcp_service = StreamableHttpService::new(
-        {
-            let gs = game_server.clone();
-            move || Ok(TdMcpServer::new(gs.clone()))
-        },
-        Arc::new(LocalSessionManager::default()),
-        StreamableHttpServerConfig {
-            stateful_mode: false,
-            ..Default::default()
-        },
-    );
-    let mcp_app = Router::new().nest_service("/mcp", mcp_service);
+    let web = async {
+        if args.no_web {
+            std::future::pending::<()>().await;
+        }
+        serve_web(&args, game_server.clone(), api.clone()).await
+    };
+
+    if args.stdio {
+        // Exit when the MCP client goes away, even though the web server never finishes
+        tokio::select! {
+            result = serve_mcp_stdio(api.clone()) => result?,
+            result = web => result?,
+        }
+    } else {
+        tokio::try_join!(serve_mcp_http(args.mcp_port, api.clone()), web)?;
+    }
+
+    Ok(())
+}
+
+/// Serve MCP over streamable HTTP at `/mcp`.
+async fn serve_mcp_http(port: u16, api: TdApi) -> std::io::Result<()> {
+    let mcp_app = Router::new().nest_service("/mcp", mcp::http_service(api));
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    tracing::info!("MCP server: http://127.0.0.1:{}/mcp", port);
+    axum::serve(listener, mcp_app).await
+}
+
+/// Serve a single MCP session over stdin/stdout until the client disconnects.
+async fn serve_mcp_stdio(api: TdApi) -> Result<(), Box<dyn std::error::Error>> {
+    tracing::info!("MCP server: stdio");
+    let service = TdMcpServer::with_api(api)
+        .serve(rmcp::transport::stdio())
+        .await?;
+    let reason = service.waiting().await?;
+    tracing::info!("MCP stdio session ended: {:?}", reason);
+    Ok(())
+}
 
-    // --- Web/SSE server ---
+/// Serve the viewer, streams, agent WebSocket, REST API and metrics.
+async fn serve_web(
+    args: &Args,
+    game_server: Arc<GameServer<TdGame>>,
+    api: TdApi,
+) -> std::io::Result<()> {
     let web_state = Arc::new(AppState {
-        game_server: game_server.clone(),
+        game_server,
+        api: api.clone(),
         streams: Arc::new(RwLock::new(HashMap::new())),
         match_list_stream: Arc::new(RwLock::new(None)),
     });
@@ -113,26 +228,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let web_app = Router::new()
+        .route("/metrics", get(metrics))
         .route("/api/stream/matches", get(stream_matches))
         .route("/api/stream/{match_id}", get(stream_match))
+        .route("/api/stream/{match_id}/keyframe", post(request_keyframe))
+        .route("/api/ws/{match_id}", get(ws_match))
+        .route("/api/agent/ws/{match_id}", get(ws_agent))
+        .merge(rest::router(api))
         .fallback_service(ServeDir::new(&args.static_dir).append_index_html_on_directories(true))
         .layer(CorsLayer::permissive())
         .with_state(web_state);
 
-    // --- Spawn both servers ---
-    let mcp_listener = TcpListener::bind(("127.0.0.1", args.mcp_port)).await?;
-    let web_listener = TcpListener::bind(("0.0.0.0", args.web_port)).await?;
-
-    tracing::info!("MCP server: http://127.0.0.1:{}/mcp", args.mcp_port);
+    let listener = TcpListener::bind(("0.0.0.0", args.web_port)).await?;
     tracing::info!("Web server: http://0.0.0.0:{}", args.web_port);
     tracing::info!("Serving static files from {:?}", args.static_dir);
+    axum::serve(listener, web_app).await
+}
+
+// ---------------------------------------------------------------------------
+// Metrics endpoint
+// ---------------------------------------------------------------------------
 
-    tokio::try_join!(
-        axum::serve(mcp_listener, mcp_app),
-        axum::serve(web_listener, web_app),
-    )?;
+/// Prometheus scrape endpoint: game server metrics plus stream subscriber counts.
+async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    use std::fmt::Write;
 
-    Ok(())
+    let mut body = state.game_server.render_metrics().await;
+
+    let _ = writeln!(
+        body,
+        "# HELP td_stream_subscribers Connected viewers per stream, SSE and WebSocket."
+    );
+    let _ = writeln!(body, "# TYPE td_stream_subscribers gauge");
+    let match_list_subscribers = state
+        .match_list_stream
+        .read()
+        .await
+        .as_ref()
+        .map(|entry| entry.tx.receiver_count())
+        .unwrap_or(0);
+    let _ = writeln!(
+        body,
+        "td_stream_subscribers{{stream=\"matches\"}} {}",
+        match_list_subscribers
+    );
+    for (match_id, entry) in state.streams.read().await.iter() {
+        let _ = writeln!(
+            body,
+            "td_stream_subscribers{{stream=\"match\",match_id=\"{}\"}} {}",
+            match_id,
+            entry.tx.receiver_count()
+        );
+    }
+
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
 }
 
 // ---------------------------------------------------------------------------
@@ -163,10 +318,10 @@ async fn stream_matches(
             let (tx, rx) = tokio::sync::broadcast::channel::<String>(16);
 
             let poll_tx = tx.clone();
-            let gs = state.game_server.clone();
+            let api = state.api.clone();
             let mls = state.match_list_stream.clone();
             let task = tokio::spawn(async move {
-                poll_match_list_loop(gs, mls, poll_tx).await;
+                poll_match_list_loop(api, mls, poll_tx).await;
             });
 
             *lock = Some(MatchListStream {
@@ -189,68 +344,422 @@ async fn stream_matches(
         .into_response()
 }
 
+/// Subscribe to the shared observation stream for a match, starting it on first use.
+async fn subscribe_match_stream(
+    state: &AppState,
+    match_id: u64,
+) -> Result<MatchStreamSubscription, MatchError> {
+    let mut streams = state.streams.write().await;
+
+    if let Some(entry) = streams.get(&match_id) {
+        tracing::info!(
+            "Match {} stream: new subscriber (receivers: {})",
+            match_id,
+            entry.tx.receiver_count() + 1
+        );
+        // The forwarder updates `latest` and sends the frame under this lock, so the
+        // keyframe is exactly the base of the first delta the receiver gets
+        let latest = entry.latest.lock().unwrap();
+        let rx = entry.tx.subscribe();
+        let keyframe = latest
+            .clone()
+            .map(|obs| serde_json::to_string(&ViewerFrame::Keyframe(obs)).unwrap());
+        drop(latest);
+        return Ok(MatchStreamSubscription {
+            keyframe,
+            rx,
+            keyframe_requested: entry.keyframe_requested.clone(),
+        });
+    }
+
+    // First subscriber — create spectator session and subscribe to pushed ticks
+    let session_token = state.game_server.spectate_match(match_id).await?;
+    let subscription = match state
+        .game_server
+        .subscribe(match_id, session_token, false)
+        .await
+    {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            let _ = state.game_server.leave_match(match_id, session_token).await;
+            return Err(e);
+        }
+    };
+
+    let (tx, rx) = broadcast::channel::<String>(16);
+    let latest = Arc::new(std::sync::Mutex::new(None));
+    let keyframe_requested = Arc::new(AtomicBool::new(false));
+
+    let task = tokio::spawn(forward_observations_loop(
+        state.game_server.clone(),
+        state.streams.clone(),
+        match_id,
+        session_token,
+        subscription,
+        tx.clone(),
+        latest.clone(),
+        keyframe_requested.clone(),
+    ));
+
+    streams.insert(match_id, MatchStream {
+        tx,
+        latest,
+        keyframe_requested: keyframe_requested.clone(),
+        _task: task,
+    });
+
+    tracing::info!("Match {} stream: first subscriber, subscribed to ticks", match_id);
+    Ok(MatchStreamSubscription {
+        keyframe: None,
+        rx,
+        keyframe_requested,
+    })
+}
+
 /// SSE endpoint: streams game state for a match to all connected viewers.
 async fn stream_match(
     State(state): State<Arc<AppState>>,
     Path(match_id): Path<u64>,
 ) -> impl IntoResponse {
-    let rx = {
-        let mut streams = state.streams.write().await;
+    let subscription = match subscribe_match_stream(&state, match_id).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            tracing::error!("Failed to create spectator session for match {}: {}", match_id, e);
+            return (StatusCode::BAD_GATEWAY, format!("Failed to spectate match: {}", e))
+                .into_response();
+        }
+    };
 
-        if let Some(entry) = streams.get(&match_id) {
-            tracing::info!(
-                "Match {} SSE: new subscriber (receivers: {})",
-                match_id,
-                entry.tx.receiver_count() + 1
-            );
-            entry.tx.subscribe()
-        } else {
-            // First subscriber — create spectator session and start polling
-            let (tx, rx) = tokio::sync::broadcast::channel::<String>(16);
+    let updates = tokio_stream::wrappers::BroadcastStream::new(subscription.rx).map(|result| {
+        match result {
+            Ok(json) => json,
+            Err(_) => "{\"error\": \"stream lagged\"}".to_string(),
+        }
+    });
+    let stream = tokio_stream::iter(subscription.keyframe)
+        .chain(updates)
+        .map(|json| Ok::<_, Infallible>(Event::default().data(json)));
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Resync endpoint: the next frame on the match stream is sent as a keyframe.
+/// Viewers call this when a delta doesn't apply to their state.
+async fn request_keyframe(
+    State(state): State<Arc<AppState>>,
+    Path(match_id): Path<u64>,
+) -> StatusCode {
+    match state.streams.read().await.get(&match_id) {
+        Some(entry) => {
+            entry.keyframe_requested.store(true, Ordering::Relaxed);
+            StatusCode::ACCEPTED
+        }
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+/// WebSocket endpoint: same frames as the SSE stream, one text message per tick.
+/// Sending the text message `keyframe` requests a resync.
+async fn ws_match(
+    State(state): State<Arc<AppState>>,
+    Path(match_id): Path<u64>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let subscription = match subscribe_match_stream(&state, match_id).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            tracing::error!("Failed to create spectator session for match {}: {}", match_id, e);
+            return (StatusCode::BAD_GATEWAY, format!("Failed to spectate match: {}", e))
+                .into_response();
+        }
+    };
+
+    ws.on_upgrade(move |socket| forward_to_websocket(socket, subscription))
+}
+
+/// Push frames to a spectator WebSocket until either side closes.
+async fn forward_to_websocket(mut socket: WebSocket, subscription: MatchStreamSubscription) {
+    let MatchStreamSubscription {
+        keyframe,
+        mut rx,
+        keyframe_requested,
+    } = subscription;
+
+    if let Some(json) = keyframe {
+        if socket.send(Message::text(json)).await.is_err() {
+            return;
+        }
+    }
 
-            let session_token = match state.game_server.spectate_match(match_id).await {
-                Ok(token) => token,
-                Err(e) => {
-                    tracing::error!("Failed to create spectator session for match {}: {}", match_id, e);
-                    return (StatusCode::BAD_GATEWAY, format!("Failed to spectate match: {}", e))
-                        .into_response();
+    loop {
+        tokio::select! {
+            frame = rx.recv() => {
+                let json = match frame {
+                    Ok(json) => json,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        "{\"error\": \"stream lagged\"}".to_string()
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if socket.send(Message::text(json)).await.is_err() {
+                    return;
                 }
-            };
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) if text.as_str() == "keyframe" => {
+                    keyframe_requested.store(true, Ordering::Relaxed);
+                }
+                // Stop when the client goes away
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => return,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
 
-            let poll_tx = tx.clone();
-            let gs = state.game_server.clone();
-            let streams_ref = state.streams.clone();
-            let task = tokio::spawn(async move {
-                poll_observe_loop(gs, streams_ref, match_id, session_token, poll_tx).await;
-            });
+    let _ = socket.send(Message::Close(None)).await;
+}
 
-            streams.insert(match_id, MatchStream {
-                tx: tx.clone(),
-                _task: task,
-            });
+// ---------------------------------------------------------------------------
+// Agent WebSocket endpoint
+// ---------------------------------------------------------------------------
 
-            tracing::info!("Match {} SSE: first subscriber, started polling", match_id);
-            rx
+/// WebSocket endpoint for agents: joins the match as a new player, pushes decision-tick
+/// observations and accepts actions on the same connection. See [`td_types::AgentMessage`].
+async fn ws_agent(
+    State(state): State<Arc<AppState>>,
+    Path(match_id): Path<u64>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| run_agent_socket(socket, state.api.clone(), match_id))
+}
+
+async fn send_agent_message(socket: &mut WebSocket, message: &AgentMessage) -> bool {
+    let json = serde_json::to_string(message).unwrap();
+    socket.send(Message::text(json)).await.is_ok()
+}
+
+/// Serve one agent connection. The player leaves the match when the connection closes.
+async fn run_agent_socket(mut socket: WebSocket, api: TdApi, match_id: u64) {
+    let game_server = api.game_server();
+    let (session, player_id) = match game_server.join_match(match_id).await {
+        Ok(joined) => joined,
+        Err(e) => {
+            let message = AgentMessage::Error {
+                message: format!("Failed to join match: {}", e),
+            };
+            let _ = send_agent_message(&mut socket, &message).await;
+            let _ = socket.send(Message::Close(None)).await;
+            return;
         }
     };
+    tracing::info!("Match {}: agent joined as player {}", match_id, player_id);
 
-    let stream = tokio_stream::wrappers::BroadcastStream::new(rx).map(|result| match result {
-        Ok(json) => Ok::<_, Infallible>(Event::default().data(json)),
-        Err(_) => Ok(Event::default().data("{\"error\": \"stream lagged\"}")),
-    });
+    let mut subscription = match game_server.subscribe(match_id, session, true).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            let message = AgentMessage::Error {
+                message: format!("Failed to subscribe: {}", e),
+            };
+            let _ = send_agent_message(&mut socket, &message).await;
+            let _ = game_server.leave_match(match_id, session).await;
+            return;
+        }
+    };
 
-    Sse::new(stream)
-        .keep_alive(KeepAlive::default())
-        .into_response()
+    let joined = AgentMessage::Joined {
+        match_id,
+        session_token: session.0,
+        player_id,
+    };
+    if send_agent_message(&mut socket, &joined).await {
+        serve_agent(&mut socket, &api, match_id, session, &mut subscription).await;
+    }
+
+    let _ = game_server.leave_match(match_id, session).await;
+    let _ = socket.send(Message::Close(None)).await;
+    tracing::info!("Match {}: agent player {} disconnected", match_id, player_id);
+}
+
+/// Relay observations and actions until the match ends or either side disconnects.
+async fn serve_agent(
+    socket: &mut WebSocket,
+    api: &TdApi,
+    match_id: u64,
+    session: SessionToken,
+    subscription: &mut MatchSubscription<TdGame>,
+) {
+    let mut status = MatchStatus::Running;
+    let mut sent_walkable = false;
+
+    loop {
+        tokio::select! {
+            update = subscription.recv() => match update {
+                Ok(update) => {
+                    status = update.status;
+                    let mut observation = update.observation;
+                    // Terrain never changes; only the first observation carries it
+                    if sent_walkable {
+                        observation.walkable.clear();
+                    }
+                    sent_walkable = true;
+                    let events = update
+                        .events
+                        .iter()
+                        .map(|e| GameEventRecord {
+                            sequence: e.sequence,
+                            tick: e.tick,
+                            event: observe::event_to_info(&e.event),
+                        })
+                        .collect();
+                    let message = AgentMessage::Observation {
+                        observation: Box::new(observation),
+                        events,
+                    };
+                    if !send_agent_message(socket, &message).await {
+                        return;
+                    }
+                }
+                // The next update carries a fresh observation
+                Err(SubscriptionError::Lagged(n)) => {
+                    tracing::warn!("Match {}: agent fell behind by {} updates", match_id, n);
+                }
+                Err(SubscriptionError::Closed) => {
+                    let message = match status {
+                        MatchStatus::Finished(_) | MatchStatus::Terminated => {
+                            AgentMessage::MatchEnded { status: api::status_info(status) }
+                        }
+                        _ => AgentMessage::Error {
+                            message: "session left the match".to_string(),
+                        },
+                    };
+                    let _ = send_agent_message(socket, &message).await;
+                    return;
+                }
+            },
+            incoming = socket.recv() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    None | Some(Err(_)) | Some(Ok(Message::Close(_))) => return,
+                    Some(Ok(_)) => continue,
+                };
+                let request = match serde_json::from_str::<AgentRequest>(text.as_str()) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        let message = AgentMessage::Error {
+                            message: format!("Invalid request: {}", e),
+                        };
+                        if !send_agent_message(socket, &message).await {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+                let reply = handle_agent_request(api, match_id, session, request).await;
+                let Some(reply) = reply else {
+                    return;
+                };
+                if !send_agent_message(socket, &reply).await {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Submit an agent's action and build the acknowledgement. Returns `None` for `Leave`.
+/// Actions go through the same validation as the MCP tools.
+async fn handle_agent_request(
+    api: &TdApi,
+    match_id: u64,
+    session: SessionToken,
+    request: AgentRequest,
+) -> Option<AgentMessage> {
+    let (request_id, result) = match request {
+        AgentRequest::PlaceTower {
+            request_id,
+            intended_tick,
+            x,
+            y,
+            tower_type,
+        } => {
+            let params = PlaceTowerParams {
+                match_id,
+                session_token: session.0,
+                intended_tick,
+                x,
+                y,
+                tower_type,
+            };
+            (request_id, api.place_tower(params).await)
+        }
+        AgentRequest::UpgradeTower {
+            request_id,
+            intended_tick,
+            tower_id,
+            branch,
+        } => {
+            let params = UpgradeTowerParams {
+                match_id,
+                session_token: session.0,
+                intended_tick,
+                tower_id,
+                branch,
+            };
+            (request_id, api.upgrade_tower(params).await)
+        }
+        AgentRequest::RepairTower {
+            request_id,
+            intended_tick,
+            tower_id,
+        } => {
+            let params = RepairTowerParams {
+                match_id,
+                session_token: session.0,
+                intended_tick,
+                tower_id,
+            };
+            (request_id, api.repair_tower(params).await)
+        }
+        AgentRequest::SetTargeting {
+            request_id,
+            intended_tick,
+            tower_id,
+            targeting,
+        } => {
+            let params = SetTargetingParams {
+                match_id,
+                session_token: session.0,
+                intended_tick,
+                tower_id,
+                targeting,
+            };
+            (request_id, api.set_targeting(params).await)
+        }
+        AgentRequest::Leave => return None,
+    };
+
+    Some(match result {
+        Ok(result) => AgentMessage::ActionAck {
+            request_id,
+            action_id: result.action_id,
+            scheduled_tick: result.scheduled_tick,
+        },
+        Err(e) => AgentMessage::ActionRejected {
+            request_id,
+            reason: e.to_string(),
+        },
+    })
 }
 
 // ---------------------------------------------------------------------------
-// Background poll loops (direct GameServer calls, no HTTP)
+// Background loops (direct GameServer calls, no HTTP)
 // ---------------------------------------------------------------------------
 
 /// Polls `list_matches` every 2s and broadcasts to all SSE subscribers.
 async fn poll_match_list_loop(
-    game_server: Arc<GameServer<TdGame>>,
+    api: TdApi,
     match_list_stream: Arc<RwLock<Option<MatchListStream>>>,
     tx: tokio::sync::broadcast::Sender<String>,
 ) {
@@ -264,8 +773,7 @@ async fn poll_match_list_loop(
             break;
         }
 
-        let matches = game_server.list_matches().await;
-        let result = transform_match_list(matches);
+        let result = api.list_matches().await;
         let json = serde_json::to_string(&result).unwrap();
         let _ = tx.send(json);
     }
@@ -274,71 +782,79 @@ async fn poll_match_list_loop(
     tracing::info!("Match list SSE: cleaned up stream entry");
 }
 
-/// Polls `observe` every 100ms for a match and broadcasts to all SSE subscribers.
-async fn poll_observe_loop(
+/// Forwards observations pushed by the match on every tick to all subscribers,
+/// as a keyframe followed by deltas.
+#[allow(clippy::too_many_arguments)]
+async fn forward_observations_loop(
     game_server: Arc<GameServer<TdGame>>,
     streams: Arc<RwLock<HashMap<u64, MatchStream>>>,
     match_id: u64,
     session_token: SessionToken,
-    tx: tokio::sync::broadcast::Sender<String>,
+    mut subscription: MatchSubscription<TdGame>,
+    tx: broadcast::Sender<String>,
+    latest: Arc<std::sync::Mutex<Option<TdObservation>>>,
+    keyframe_requested: Arc<AtomicBool>,
 ) {
-    // visualize at rate of simulation but don't exceed 10Hz
-    let mut interval = tokio::time::interval(Duration::from_millis(max(1000/game_server.config.simulation_rate as u64,100)));
+    let mut subscriber_check = tokio::time::interval(SUBSCRIBER_CHECK_INTERVAL);
+    let mut match_over = false;
+    let mut last_keyframe_tick = 0;
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            update = subscription.recv(), if !match_over => match update {
+                Ok(update) => {
+                    let obs = update.observation;
+                    let keyframe_due = keyframe_requested.swap(false, Ordering::Relaxed)
+                        || obs.tick >= last_keyframe_tick + KEYFRAME_INTERVAL_TICKS;
+
+                    // Send under the lock so a new subscriber sees either the previous
+                    // observation and this frame, or this observation and none of it
+                    {
+                        let mut latest = latest.lock().unwrap();
+                        let frame = match latest.as_ref() {
+                            Some(prev) if !keyframe_due => {
+                                let mut delta = ObservationDelta::between(prev, &obs);
+                                delta.events = update
+                                    .events
+                                    .iter()
+                                    .map(|e| observe::event_to_info(&e.event))
+                                    .collect();
+                                ViewerFrame::Delta(delta)
+                            }
+                            _ => {
+                                last_keyframe_tick = obs.tick;
+                                ViewerFrame::Keyframe(obs.clone())
+                            }
+                        };
+                        *latest = Some(obs);
+                        let _ = tx.send(serde_json::to_string(&frame).unwrap());
+                    }
+                }
+                Err(SubscriptionError::Lagged(n)) => {
+                    tracing::warn!("Match {} stream: dropped {} ticks", match_id, n);
+                }
+                Err(SubscriptionError::Closed) => {
+                    if let Err(e @ MatchError::NotFound) = game_server.current_tick(match_id).await {
+                        tracing::info!("Match {} stream: match no longer exists, stopping", match_id);
+                        let _ = tx.send(format!(r#"{{"error": "{}"}}"#, e));
+                        break;
+                    }
+                    // Finished: keep serving the final frame until viewers leave
+                    match_over = true;
+                }
+            },
+            _ = subscriber_check.tick() => {}
+        }
 
         if tx.receiver_count() == 0 {
-            tracing::info!("Match {} SSE: no subscribers, stopping poll loop", match_id);
+            tracing::info!("Match {} stream: no subscribers, stopping", match_id);
             break;
         }
-
-        match game_server.observe(match_id, session_token).await {
-            Ok(obs) => {
-                let json = serde_json::to_string(&obs).unwrap();
-                let _ = tx.send(json);
-            }
-            Err(e) => {
-                tracing::warn!("Match {} SSE: observe failed: {}", match_id, e);
-                let _ = tx.send(format!(r#"{{"error": "{}"}}"#, e));
-                if matches!(e, MatchError::NotFound) {
-                    tracing::info!("Match {} SSE: match no longer exists, stopping", match_id);
-                    break;
-                }
-            }
-        }
     }
 
     // Cleanup: remove from streams map and leave spectator session
     streams.write().await.remove(&match_id);
-    tracing::info!("Match {} SSE: cleaned up stream entry", match_id);
+    tracing::info!("Match {} stream: cleaned up stream entry", match_id);
 
     let _ = game_server.leave_match(match_id, session_token).await;
 }
-
-// ---------------------------------------------------------------------------
-// Transformation helpers (GameServer types → JSON-compatible types)
-// ---------------------------------------------------------------------------
-
-fn transform_match_list(matches: Vec<sim_server::MatchInfo>) -> ListMatchesResult {
-    ListMatchesResult {
-        matches: matches
-            .into_iter()
-            .map(|m| MatchInfoResult {
-                match_id: m.match_id,
-                status: match m.status {
-                    MatchStatus::WaitingForPlayers { current, required } => {
-                        MatchStatusInfo::WaitingForPlayers { current, required }
-                    }
-                    MatchStatus::Running => MatchStatusInfo::Running,
-                    MatchStatus::Finished(outcome) => MatchStatusInfo::Finished {
-                        outcome: format!("{:?}", outcome),
-                    },
-                    MatchStatus::Terminated => MatchStatusInfo::Terminated,
-                },
-                current_tick: m.current_tick,
-                player_count: m.player_count,
-            })
-            .collect(),
-    }
-}
@@ -1,3 +1,6 @@
+use crate::map::MapDef;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use sim_core::Micros;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -12,19 +15,158 @@ pub struct TowerSpec {
     pub range: f32,
     pub damage: i32,
     pub fire_period: Micros,
+    /// HP restored per second between waves, up to full; 0 disables regeneration.
+    pub regen: i32,
+    pub upgrades: UpgradeTree,
+}
+
+/// What each level of an upgrade branch improves, by the branch's `bonus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UpgradeBranch {
+    /// Damage × (1 + bonus) per level.
+    Damage,
+    /// Range + bonus cells per level.
+    Range,
+    /// Fire period ÷ (1 + bonus) per level.
+    FireRate,
+    /// Max HP × (1 + bonus) per level. Upgrading heals the HP gained.
+    Hp,
+    /// Hits slow mobs by bonus × level of their speed (at most [`MAX_SLOW`]) for
+    /// [`SLOW_DURATION`].
+    Slow,
+}
+
+impl UpgradeBranch {
+    pub const ALL: [UpgradeBranch; 5] = [
+        UpgradeBranch::Damage,
+        UpgradeBranch::Range,
+        UpgradeBranch::FireRate,
+        UpgradeBranch::Hp,
+        UpgradeBranch::Slow,
+    ];
+}
+
+/// A tower's level in each branch, indexed by `UpgradeBranch as usize`.
+pub type BranchLevels = [u8; UpgradeBranch::ALL.len()];
+
+/// Largest fraction of its speed a slowed mob loses.
+pub const MAX_SLOW: f32 = 0.9;
+
+/// How long a hit from a slowing tower lasts.
+pub const SLOW_DURATION: Micros = Micros::from_secs(1);
+
+/// One branch of an [`UpgradeTree`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchSpec {
+    pub branch: UpgradeBranch,
+    pub max_level: u8,
+    pub bonus: f64,
+}
+
+/// The upgrades a tower kind can take. Each upgrade raises one branch by a level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpgradeTree {
+    /// Upgrades a tower can take in total, across branches.
+    pub max_level: u8,
+    /// Branches the kind can take; the rest stay at level 0.
+    pub branches: Vec<BranchSpec>,
+}
+
+impl UpgradeTree {
+    pub fn branch(&self, branch: UpgradeBranch) -> Option<&BranchSpec> {
+        self.branches.iter().find(|b| b.branch == branch)
+    }
+
+    fn bonus(&self, branch: UpgradeBranch) -> f64 {
+        self.branch(branch).map_or(0.0, |b| b.bonus)
+    }
+}
+
+/// A tower's stats after upgrades.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TowerStats {
+    pub damage: i32,
+    pub range: f32,
+    pub fire_period: Micros,
+    pub max_hp: i32,
+    /// Fraction of their speed that mobs hit by the tower lose.
+    pub slow: f32,
+}
+
+/// Which spawn each mob of a wave enters from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SpawnPlan {
+    /// Mobs take turns across all spawns.
+    #[default]
+    RoundRobin,
+    /// Wave `w` uses entry `(w - 1) % len`: the spawn indices its mobs enter from, in
+    /// equal consecutive groups. `[[0], [1], [0, 1]]` sends wave 1 from spawn 0, wave 2
+    /// from spawn 1, and the first half of wave 3 from spawn 0, the second from spawn 1.
+    Groups(Vec<Vec<usize>>),
+}
+
+/// Named presets for the wave and economy curves; see [`Difficulty::apply`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Nightmare,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Nightmare => "nightmare",
+        }
+    }
+
+    /// Set the curve scales, `max_leaks` and `inter_wave_pause` of `config`.
+    pub fn apply(self, config: &mut TdConfig) {
+        // (mob HP, wave size, gold, max leaks, seconds between waves)
+        let (mob_hp, wave_size, gold, max_leaks, pause) = match self {
+            Difficulty::Easy => (0.75, 0.85, 1.3, 20, 15),
+            Difficulty::Normal => (1.0, 1.0, 1.0, 10, 10),
+            Difficulty::Hard => (1.3, 1.15, 0.85, 5, 8),
+            Difficulty::Nightmare => (1.7, 1.3, 0.7, 3, 6),
+        };
+        config.mob_hp_scale = mob_hp;
+        config.wave_size_scale = wave_size;
+        config.gold_scale = gold;
+        config.max_leaks = max_leaks;
+        config.inter_wave_pause = Micros::from_secs(pause);
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct TdConfig {
     pub width: u16,
     pub height: u16,
-    pub spawn: (u16, u16),
-    pub goal: (u16, u16),
+    /// Where mobs enter. At least one.
+    pub spawns: Vec<(u16, u16)>,
+    /// Where mobs leak; each mob heads for the nearest. At least one.
+    pub goals: Vec<(u16, u16)>,
+    pub spawn_plan: SpawnPlan,
     pub tick_hz: u32,
     pub waves_total: u8,
     pub inter_wave_pause: Micros,
     pub spawn_interval: Micros,
     pub max_leaks: u16,
+    /// Emit `TowerFired` and `MobDamaged` for every shot. Off saves an event pair per
+    /// shot in busy matches.
+    pub combat_events: bool,
 
     // Build pacing
     pub build_time: Micros,
@@ -32,10 +174,22 @@ pub struct TdConfig {
     // Tower specs
     pub basic_spec: TowerSpec,
 
+    // Mobs, in cells per second
+    pub mob_speed: f32,
+
     // Player count (set at match creation)
     pub player_count: u8,
 
+    // Curve scales set by `Difficulty`; 1.0 is normal
+    pub mob_hp_scale: f64,
+    pub wave_size_scale: f64,
+    /// Scales starting gold and wave rewards, not kill rewards.
+    pub gold_scale: f64,
+
     // Map generation
+    /// Fixed map used instead of generating one; width, height, spawn and goal are
+    /// taken from it.
+    pub map: Option<MapDef>,
     pub maze_size: i32,
     pub dilation_base_radius: f64,
     pub dilation_amplitude: f64,
@@ -52,25 +206,86 @@ impl TdConfig {
         d.to_ticks(self.tick_hz)
     }
 
-    /// Mob HP: `floor(10 * 1.15^w * p)`
+    /// Spawn of mob `index` (from 0) of wave `wave`, which has `wave_size` mobs.
+    /// Spawn indices out of range wrap around.
+    pub fn spawn_for(&self, wave: u8, index: u16, wave_size: u16) -> (u16, u16) {
+        let n = self.spawns.len();
+        let i = match &self.spawn_plan {
+            SpawnPlan::RoundRobin => index as usize,
+            SpawnPlan::Groups(waves) if !waves.is_empty() => {
+                let groups = &waves[(wave.max(1) as usize - 1) % waves.len()];
+                if groups.is_empty() {
+                    0
+                } else {
+                    let group = index as usize * groups.len() / wave_size.max(1) as usize;
+                    groups[group.min(groups.len() - 1)]
+                }
+            }
+            SpawnPlan::Groups(_) => 0,
+        };
+        self.spawns[i % n]
+    }
+
+    /// Mob HP: `floor(10 * 1.15^w * p * mob_hp_scale)`
     pub fn mob_hp(&self, wave: u8, player_count: u8) -> i32 {
         let w = wave as f64;
         let p = player_count as f64;
-        (10.0 * 1.15_f64.powf(w) * p).floor() as i32
+        (10.0 * 1.15_f64.powf(w) * p * self.mob_hp_scale).floor() as i32
     }
 
-    /// Wave size: `floor(8 * 1.08^w * p)`
+    /// Wave size: `floor(8 * 1.08^w * p * wave_size_scale)`, at least 1
     pub fn wave_size(&self, wave: u8, player_count: u8) -> u16 {
         let w = wave as f64;
         let p = player_count as f64;
-        (8.0 * 1.08_f64.powf(w) * p).floor() as u16
+        ((8.0 * 1.08_f64.powf(w) * p * self.wave_size_scale).floor() as u16).max(1)
     }
 
-    /// Tower damage based on base spec damage and upgrade level: `floor(base * 1.15^level)`
-    pub fn tower_damage(&self, kind: TowerKind, upgrade_level: u8) -> i32 {
-        let base = self.spec(kind).damage as f64;
-        let level = upgrade_level as f64;
-        (base * 1.15_f64.powf(level)).floor() as i32
+    /// Tower damage at a damage branch level: `floor(base * (1 + bonus)^level)`
+    pub fn tower_damage(&self, kind: TowerKind, damage_level: u8) -> i32 {
+        let spec = self.spec(kind);
+        let factor = 1.0 + spec.upgrades.bonus(UpgradeBranch::Damage);
+        (spec.damage as f64 * factor.powi(damage_level as i32)).floor() as i32
+    }
+
+    /// Stats of a `kind` tower with the given branch levels; see [`UpgradeBranch`].
+    pub fn tower_stats(&self, kind: TowerKind, levels: &BranchLevels) -> TowerStats {
+        let spec = self.spec(kind);
+        let bonus = |branch: UpgradeBranch| spec.upgrades.bonus(branch);
+        let level = |branch: UpgradeBranch| levels[branch as usize] as i32;
+
+        let range = bonus(UpgradeBranch::Range) * level(UpgradeBranch::Range) as f64;
+        let fire_rate = (1.0 + bonus(UpgradeBranch::FireRate)).powi(level(UpgradeBranch::FireRate));
+        let hp = (1.0 + bonus(UpgradeBranch::Hp)).powi(level(UpgradeBranch::Hp));
+        let slow = bonus(UpgradeBranch::Slow) * level(UpgradeBranch::Slow) as f64;
+        TowerStats {
+            damage: self.tower_damage(kind, levels[UpgradeBranch::Damage as usize]),
+            range: spec.range + range as f32,
+            fire_period: spec.fire_period.scale(1.0 / fire_rate),
+            max_hp: (spec.hp as f64 * hp).floor() as i32,
+            slow: (slow as f32).min(MAX_SLOW),
+        }
+    }
+
+    /// Why a `kind` tower with `levels` (`total` upgrades) cannot take an upgrade in
+    /// `branch`, if it cannot.
+    pub fn upgrade_blocked(
+        &self,
+        kind: TowerKind,
+        levels: &BranchLevels,
+        total: u8,
+        branch: UpgradeBranch,
+    ) -> Option<&'static str> {
+        let tree = &self.spec(kind).upgrades;
+        let Some(spec) = tree.branch(branch) else {
+            return Some("branch not available for this tower");
+        };
+        if total >= tree.max_level {
+            Some("tower is at max upgrade level")
+        } else if levels[branch as usize] >= spec.max_level {
+            Some("branch is at max level")
+        } else {
+            None
+        }
     }
 
     /// Build cost scaling with wave: `floor(base_cost * 1.12^w)`
@@ -86,16 +301,25 @@ impl TdConfig {
         (20.0 * 1.20_f64.powf(next)).floor() as u32
     }
 
-    /// Starting gold: `50 + 30*(p-1)`
+    /// Repair cost: `ceil(base_cost * missing_hp / (2 * max_hp))`, so a full repair costs
+    /// half the unscaled build cost whatever the tower's Hp upgrades
+    pub fn repair_cost(&self, kind: TowerKind, missing_hp: i32, max_hp: i32) -> u32 {
+        let spec = self.spec(kind);
+        let missing = missing_hp.max(0) as f64;
+        (spec.cost as f64 * missing / (2.0 * max_hp.max(1) as f64)).ceil() as u32
+    }
+
+    /// Starting gold: `floor((50 + 30*(p-1)) * gold_scale)`
     pub fn gold_start(&self, player_count: u8) -> u32 {
-        50 + 30 * (player_count as u32 - 1)
+        let base = 50 + 30 * (player_count as u32 - 1);
+        (base as f64 * self.gold_scale).floor() as u32
     }
 
-    /// Gold per wave completion: `floor(25 * 1.12^w * p)`
+    /// Gold per wave completion: `floor(25 * 1.12^w * p * gold_scale)`
     pub fn gold_per_wave(&self, wave: u8, player_count: u8) -> u32 {
         let w = wave as f64;
         let p = player_count as f64;
-        (25.0 * 1.12_f64.powf(w) * p).floor() as u32
+        (25.0 * 1.12_f64.powf(w) * p * self.gold_scale).floor() as u32
     }
 
     /// Gold per mob kill: `floor(1 * 1.08^w)`
@@ -112,13 +336,15 @@ impl Default for TdConfig {
         Self {
             width: grid_size,
             height: grid_size,
-            spawn: (0, 0),
-            goal: (grid_size - 1, grid_size - 1),
+            spawns: vec![(0, 0)],
+            goals: vec![(grid_size - 1, grid_size - 1)],
+            spawn_plan: SpawnPlan::RoundRobin,
             tick_hz: 60,
             waves_total: 10,
             inter_wave_pause: Micros::from_secs(10),
             spawn_interval: Micros::from_millis(500),
             max_leaks: 10,
+            combat_events: true,
 
             build_time: Micros::from_secs(2),
 
@@ -128,10 +354,48 @@ impl Default for TdConfig {
                 range: 4.0,
                 damage: 5,
                 fire_period: Micros::from_secs(1),
+                regen: 0,
+                upgrades: UpgradeTree {
+                    max_level: 10,
+                    branches: vec![
+                        BranchSpec {
+                            branch: UpgradeBranch::Damage,
+                            max_level: 10,
+                            bonus: 0.15,
+                        },
+                        BranchSpec {
+                            branch: UpgradeBranch::Range,
+                            max_level: 4,
+                            bonus: 0.5,
+                        },
+                        BranchSpec {
+                            branch: UpgradeBranch::FireRate,
+                            max_level: 5,
+                            bonus: 0.15,
+                        },
+                        BranchSpec {
+                            branch: UpgradeBranch::Hp,
+                            max_level: 5,
+                            bonus: 0.25,
+                        },
+                        BranchSpec {
+                            branch: UpgradeBranch::Slow,
+                            max_level: 3,
+                            bonus: 0.15,
+                        },
+                    ],
+                },
             },
 
+            mob_speed: 2.0,
+
             player_count: 1,
 
+            mob_hp_scale: 1.0,
+            wave_size_scale: 1.0,
+            gold_scale: 1.0,
+
+            map: None,
             maze_size,
             dilation_base_radius: 3.0,
             dilation_amplitude: 2.0,
@@ -143,6 +407,58 @@ impl Default for TdConfig {
 mod tests {
     use super::*;
 
+    #[test]
+    fn difficulty_presets() {
+        let mut normal = TdConfig::default();
+        Difficulty::Normal.apply(&mut normal);
+        let default = TdConfig::default();
+        assert_eq!(normal.max_leaks, default.max_leaks);
+        assert_eq!(normal.inter_wave_pause, default.inter_wave_pause);
+        assert_eq!(normal.mob_hp(5, 1), default.mob_hp(5, 1));
+
+        // Each preset is harder than the one before on every curve
+        let configs: Vec<TdConfig> = Difficulty::ALL
+            .iter()
+            .map(|d| {
+                let mut config = TdConfig::default();
+                d.apply(&mut config);
+                config
+            })
+            .collect();
+        for pair in configs.windows(2) {
+            let (easier, harder) = (&pair[0], &pair[1]);
+            assert!(harder.mob_hp(5, 1) > easier.mob_hp(5, 1));
+            assert!(harder.wave_size(5, 1) > easier.wave_size(5, 1));
+            assert!(harder.gold_per_wave(5, 1) < easier.gold_per_wave(5, 1));
+            assert!(harder.gold_start(1) < easier.gold_start(1));
+            assert!(harder.max_leaks < easier.max_leaks);
+        }
+    }
+
+    #[test]
+    fn spawn_plans() {
+        let mut config = TdConfig {
+            spawns: vec![(0, 0), (1, 0), (2, 0)],
+            ..TdConfig::default()
+        };
+        let spawns = |config: &TdConfig, wave| -> Vec<(u16, u16)> {
+            (0..6).map(|i| config.spawn_for(wave, i, 6)).collect()
+        };
+        assert_eq!(
+            spawns(&config, 1),
+            vec![(0, 0), (1, 0), (2, 0), (0, 0), (1, 0), (2, 0)]
+        );
+
+        config.spawn_plan = SpawnPlan::Groups(vec![vec![2], vec![0, 1]]);
+        assert_eq!(spawns(&config, 1), vec![(2, 0); 6]);
+        assert_eq!(
+            spawns(&config, 2),
+            vec![(0, 0), (0, 0), (0, 0), (1, 0), (1, 0), (1, 0)]
+        );
+        // Waves past the list start over
+        assert_eq!(spawns(&config, 3), spawns(&config, 1));
+    }
+
     #[test]
     fn mob_hp_wave_1() {
         let config = TdConfig::default();
@@ -185,6 +501,79 @@ mod tests {
         assert_eq!(config.upgrade_cost(1), 28);
     }
 
+    #[test]
+    fn tower_stats_follow_branch_levels() {
+        let config = TdConfig::default();
+        let base = config.tower_stats(TowerKind::Basic, &BranchLevels::default());
+        assert_eq!(
+            base,
+            TowerStats {
+                damage: 5,
+                range: 4.0,
+                fire_period: Micros::from_secs(1),
+                max_hp: 100,
+                slow: 0.0,
+            }
+        );
+
+        // damage, range, fire_rate, hp, slow
+        let stats = config.tower_stats(TowerKind::Basic, &[0, 2, 1, 2, 3]);
+        assert_eq!(stats.damage, 5);
+        assert_eq!(stats.range, 5.0);
+        // 1s / 1.15 ≈ 869565.2us
+        assert_eq!(stats.fire_period, Micros::from_secs(1).scale(1.0 / 1.15));
+        assert_eq!(stats.fire_period.to_ticks(1_000_000), 869_565);
+        // 100 * 1.25^2 = 156.25
+        assert_eq!(stats.max_hp, 156);
+        assert!((stats.slow - 0.45).abs() < 1e-6);
+    }
+
+    #[test]
+    fn upgrade_limits() {
+        let mut config = TdConfig::default();
+        let kind = TowerKind::Basic;
+        assert_eq!(
+            config.upgrade_blocked(kind, &[0; 5], 0, UpgradeBranch::Slow),
+            None
+        );
+        assert_eq!(
+            config.upgrade_blocked(kind, &[0, 0, 0, 0, 3], 3, UpgradeBranch::Slow),
+            Some("branch is at max level")
+        );
+        assert_eq!(
+            config.upgrade_blocked(kind, &[6, 4, 0, 0, 0], 10, UpgradeBranch::Hp),
+            Some("tower is at max upgrade level")
+        );
+
+        config
+            .basic_spec
+            .upgrades
+            .branches
+            .retain(|b| b.branch != UpgradeBranch::Slow);
+        assert_eq!(
+            config.upgrade_blocked(kind, &[0; 5], 0, UpgradeBranch::Slow),
+            Some("branch not available for this tower")
+        );
+    }
+
+    #[test]
+    fn repair_cost_scaling() {
+        let config = TdConfig::default();
+        // Full repair: 15 * 100 / 200 = 7.5 → 8
+        assert_eq!(config.repair_cost(TowerKind::Basic, 100, 100), 8);
+        // 15 * 10 / 200 = 0.75 → 1
+        assert_eq!(config.repair_cost(TowerKind::Basic, 10, 100), 1);
+        assert_eq!(config.repair_cost(TowerKind::Basic, 0, 100), 0);
+        // Hp level 2: max HP 156, so a full repair still costs 15 * 156 / 312 = 7.5 → 8
+        let max_hp = config
+            .tower_stats(TowerKind::Basic, &[0, 0, 0, 2, 0])
+            .max_hp;
+        assert_eq!(max_hp, 156);
+        assert_eq!(config.repair_cost(TowerKind::Basic, max_hp, max_hp), 8);
+        // 15 * 78 / 312 = 3.75 → 4
+        assert_eq!(config.repair_cost(TowerKind::Basic, 78, max_hp), 4);
+    }
+
     #[test]
     fn wave_size_scaling() {
         let config = TdConfig::default();
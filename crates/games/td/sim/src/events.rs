@@ -1,5 +1,5 @@
-use crate::config::TowerKind;
-use crate::world::{MobId, TowerId};
+use crate::config::{TowerKind, UpgradeBranch};
+use crate::world::{MobId, Targeting, TowerId};
 
 #[derive(Clone, Debug)]
 pub enum TdEvent {
@@ -39,8 +39,42 @@ pub enum TdEvent {
     },
     TowerUpgraded {
         id: TowerId,
+        branch: UpgradeBranch,
         new_level: u8,
     },
+    UpgradeRejected {
+        id: TowerId,
+        branch: UpgradeBranch,
+        reason: String,
+    },
+    TowerRepaired {
+        id: TowerId,
+        hp: i32,
+        cost: u32,
+    },
+    RepairRejected {
+        id: TowerId,
+        reason: String,
+    },
+    TargetingChanged {
+        id: TowerId,
+        targeting: Targeting,
+    },
+    TargetingRejected {
+        id: TowerId,
+        targeting: Targeting,
+        reason: String,
+    },
+    TowerFired {
+        tower_id: TowerId,
+        mob_id: MobId,
+        damage: i32,
+    },
+    MobDamaged {
+        id: MobId,
+        damage: i32,
+        hp: i32,
+    },
     BuildRejected {
         x: u16,
         y: u16,
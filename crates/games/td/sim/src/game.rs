@@ -1,18 +1,13 @@
 use crate::actions::TdAction;
-use crate::config::TdConfig;
+use crate::config::{SpawnPlan, TdConfig};
 use crate::events::TdEvent;
-use crate::pathing::compute_distance_field;
+use crate::mapgen::{self, GenerateError};
+use crate::pathing::{self, compute_distance_field};
 use crate::systems;
 use crate::world::{TdState, WavePhase};
-use maze_generator::prelude::{Coordinates, Generator};
-use maze_generator::recursive_backtracking::RbGenerator;
 use sim_core::{ActionEnvelope, Game, PlayerId, TerminalOutcome, Tick};
-use td_map_generator::dilate::{dilate_path, DilationParams};
-use td_map_generator::grid::Tile;
-use td_map_generator::noise::ValueNoise1D;
-use td_map_generator::upscale::upscale_path;
-use td_map_generator::{create_seed, solve_maze_bfs};
 
+#[derive(Clone)]
 pub struct TdGame {
     state: TdState,
     #[allow(dead_code)]
@@ -20,6 +15,31 @@ pub struct TdGame {
 }
 
 impl TdGame {
+    /// Start a game on `config.map`, or on a map generated from `seed` if unset.
+    pub fn try_new(mut config: TdConfig, seed: u64) -> Result<Self, GenerateError> {
+        let map = match &config.map {
+            Some(map) => {
+                // The fields are public, so a caller's map may never have been checked
+                map.validate()
+                    .map_err(|e| GenerateError::Invalid(e.to_string()))?;
+                map.clone()
+            }
+            None => mapgen::generate_map(&config, seed)?,
+        };
+        config.width = map.width;
+        config.height = map.height;
+        config.spawns = map.spawns.clone();
+        config.goals = map.goals.clone();
+        if !map.spawn_groups.is_empty() {
+            config.spawn_plan = SpawnPlan::Groups(map.spawn_groups.clone());
+        }
+        let walkable = map.walkable();
+
+        let mut state = TdState::with_terrain(config, walkable);
+        compute_distance_field(&state.world.grid, &state.config.goals, &mut state.dist);
+        Ok(Self { state, seed })
+    }
+
     pub fn state(&self) -> &TdState {
         &self.state
     }
@@ -31,71 +51,11 @@ impl Game for TdGame {
     type Observation = td_types::TdObservation;
     type Event = TdEvent;
 
-    fn new(mut config: Self::Config, seed: u64) -> Self {
-        let maze_size = config.maze_size;
-
-        // 1. Generate maze
-        let mut generator = RbGenerator::new(Some(create_seed(seed)));
-        let mut maze = generator.generate(maze_size, maze_size).unwrap();
-        maze.goal = Coordinates::new(maze_size - 1, maze_size - 1);
-
-        // 2. Solve maze
-        let path = solve_maze_bfs(&maze).expect("No maze solution found");
-
-        // 3. Upscale path to tile grid (scale factor 3)
-        let (mut tile_grid, spine) =
-            upscale_path(&path, maze_size as usize, maze_size as usize);
-
-        // 4. Compute distance field for dilation
-        let dist_field = td_map_generator::distance::compute_distance_field(
-            tile_grid.width,
-            tile_grid.height,
-            &spine,
-        );
-
-        // 5. Create noise and dilate path
-        let noise = ValueNoise1D::new(seed, 256, 30.0);
-        let params = DilationParams {
-            base_radius: config.dilation_base_radius,
-            amplitude: config.dilation_amplitude,
-        };
-        dilate_path(&mut tile_grid, &dist_field, &noise, &params);
-
-        // 6. Convert TileGrid to walkable mask and extract spawn/goal
-        let grid_w = tile_grid.width as u16;
-        let grid_h = tile_grid.height as u16;
-        let mut walkable = vec![false; tile_grid.width * tile_grid.height];
-        let mut spawn = (0u16, 0u16);
-        let mut goal = (grid_w - 1, grid_h - 1);
-
-        for y in 0..tile_grid.height {
-            for x in 0..tile_grid.width {
-                let tile = tile_grid.get(x, y);
-                let idx = y * tile_grid.width + x;
-                match tile {
-                    Tile::Path => walkable[idx] = true,
-                    Tile::Start => {
-                        walkable[idx] = true;
-                        spawn = (x as u16, y as u16);
-                    }
-                    Tile::Goal => {
-                        walkable[idx] = true;
-                        goal = (x as u16, y as u16);
-                    }
-                    Tile::Wall => {}
-                }
-            }
-        }
-
-        // 7. Update config with generated map dimensions and positions
-        config.width = grid_w;
-        config.height = grid_h;
-        config.spawn = spawn;
-        config.goal = goal;
-
-        let mut state = TdState::with_terrain(config, walkable);
-        compute_distance_field(&state.world.grid, state.config.goal, &mut state.dist);
-        Self { state, seed }
+    /// Panics if the map cannot be generated. `TdApi::create_match` generates the map
+    /// up front and sets `config.map`, so matches never take this path; use
+    /// [`TdGame::try_new`] to handle the failure.
+    fn new(config: Self::Config, seed: u64) -> Self {
+        Self::try_new(config, seed).unwrap_or_else(|e| panic!("seed {}: {}", seed, e))
     }
 
     fn step(
@@ -120,8 +80,17 @@ impl Game for TdGame {
                         out_events,
                     );
                 }
-                TdAction::UpgradeTower { tower_id } => {
-                    systems::try_upgrade_tower(&mut self.state, *tower_id, out_events);
+                TdAction::UpgradeTower { tower_id, branch } => {
+                    systems::try_upgrade_tower(&mut self.state, *tower_id, *branch, out_events);
+                }
+                TdAction::RepairTower { tower_id } => {
+                    systems::try_repair_tower(&mut self.state, *tower_id, out_events);
+                }
+                TdAction::SetTargeting {
+                    tower_id,
+                    targeting,
+                } => {
+                    systems::set_targeting(&mut self.state, *tower_id, *targeting, out_events);
                 }
             }
         }
@@ -129,17 +98,15 @@ impl Game for TdGame {
         // 2. Process completed builds → place towers
         let towers_placed = systems::process_builds(&mut self.state, tick, out_events);
 
-        // 3. Recompute distance field if towers were placed
+        // 3. Update distance field if towers were placed
         if towers_placed {
-            compute_distance_field(
-                &self.state.world.grid,
-                self.state.config.goal,
-                &mut self.state.dist,
-            );
+            pathing::refresh_distance_field(&mut self.state);
         }
 
-        // 4. Update wave phase (may spawn mobs, award gold on wave completion)
+        // 4. Update wave phase (may spawn mobs, award gold on wave completion), then
+        //    regenerate towers between waves
         systems::update_wave(&mut self.state, tick, out_events);
+        systems::regen_towers(&mut self.state, tick);
 
         // 5. Move mobs (mobs attack towers)
         systems::move_mobs(&mut self.state, tick, out_events);
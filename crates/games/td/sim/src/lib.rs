@@ -1,16 +1,26 @@
 pub mod actions;
+pub mod api;
 pub mod config;
 pub mod events;
 pub mod game;
+pub mod map;
+pub mod mapgen;
 pub mod mcp;
 pub mod observe;
 pub mod pathing;
+pub mod placement;
+pub mod rest;
+pub mod summary;
 pub mod systems;
+pub mod whatif;
 pub mod world;
 
 pub use actions::TdAction;
-pub use config::{TdConfig, TowerKind, TowerSpec};
+pub use config::{
+    BranchSpec, Difficulty, SpawnPlan, TdConfig, TowerKind, TowerSpec, TowerStats, UpgradeBranch,
+    UpgradeTree,
+};
 pub use events::TdEvent;
 pub use game::TdGame;
 pub use td_types::TdObservation;
-pub use world::{Grid, Mob, MobId, TdState, Tower, TowerId, WavePhase, World};
+pub use world::{Grid, Mob, MobId, Targeting, TdState, Tower, TowerId, WavePhase, World};
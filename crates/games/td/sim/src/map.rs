@@ -0,0 +1,515 @@
+//! Hand-authored maps that replace procedural generation, written as ASCII art or JSON,
+//! and the map directory that `create_match` picks named maps from.
+//!
+//! ASCII maps use the symbols of the rendered map: `#` wall, `.` open, `S` spawn,
+//! `G` goal, plus `o` for an obstacle. One row per line, all rows the same width.
+//! Spawns are numbered in reading order.
+//!
+//! JSON maps list the blocked cells of an otherwise open grid:
+//!
+//! ```json
+//! { "width": 8, "height": 3, "spawns": [[0, 1]], "goals": [[7, 0], [7, 2]],
+//!   "walls": [[3, 0], [3, 2]], "obstacles": [[5, 1]] }
+//! ```
+//!
+//! Only JSON maps can assign waves to spawns, with `spawn_groups`; otherwise mobs take
+//! turns across spawns.
+
+use crate::pathing::compute_distance_field;
+use crate::world::Grid;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Largest accepted width or height.
+pub const MAX_MAP_SIZE: u16 = 512;
+
+/// A fixed map: terrain, spawns and goals.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapDef {
+    pub width: u16,
+    pub height: u16,
+    pub spawns: Vec<(u16, u16)>,
+    pub goals: Vec<(u16, u16)>,
+    /// Cells outside the playing field.
+    #[serde(default)]
+    pub walls: Vec<(u16, u16)>,
+    /// Pre-placed blockers on the playing field. Like walls, they can't be walked
+    /// through, built on or destroyed.
+    #[serde(default)]
+    pub obstacles: Vec<(u16, u16)>,
+    /// Spawn indices per wave, as in
+    /// [`SpawnPlan::Groups`](crate::config::SpawnPlan::Groups); empty alternates spawns.
+    #[serde(default)]
+    pub spawn_groups: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapError {
+    /// The map name is not a plain file name.
+    InvalidName(String),
+    /// No `<name>.txt` or `<name>.json` in the map directory.
+    NotFound(String),
+    Io(String),
+    /// The map text doesn't parse.
+    Parse(String),
+    /// The map parses but can't be played.
+    Invalid(String),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::InvalidName(name) => write!(
+                f,
+                "invalid map name '{}' (use letters, digits, '-' and '_')",
+                name
+            ),
+            MapError::NotFound(name) => write!(f, "map '{}' not found", name),
+            MapError::Io(e) => write!(f, "failed to read map: {}", e),
+            MapError::Parse(e) => write!(f, "failed to parse map: {}", e),
+            MapError::Invalid(e) => write!(f, "invalid map: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl MapDef {
+    /// Parse and validate an ASCII map.
+    pub fn from_ascii(text: &str) -> Result<Self, MapError> {
+        let rows: Vec<&str> = text
+            .lines()
+            .map(str::trim_end)
+            .filter(|row| !row.is_empty())
+            .collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if rows.len() > MAX_MAP_SIZE as usize || width > MAX_MAP_SIZE as usize {
+            return Err(MapError::Invalid(format!(
+                "larger than {0}x{0}",
+                MAX_MAP_SIZE
+            )));
+        }
+
+        let mut map = MapDef {
+            width: width as u16,
+            height: rows.len() as u16,
+            spawns: Vec::new(),
+            goals: Vec::new(),
+            walls: Vec::new(),
+            obstacles: Vec::new(),
+            spawn_groups: Vec::new(),
+        };
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(MapError::Parse(format!(
+                    "row {} is {} cells wide, expected {}",
+                    y,
+                    row.chars().count(),
+                    width
+                )));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let cell = (x as u16, y as u16);
+                match c {
+                    '#' => map.walls.push(cell),
+                    'o' => map.obstacles.push(cell),
+                    '.' => {}
+                    'S' => map.spawns.push(cell),
+                    'G' => map.goals.push(cell),
+                    _ => {
+                        return Err(MapError::Parse(format!(
+                            "unknown symbol '{}' at ({},{})",
+                            c, x, y
+                        )))
+                    }
+                }
+            }
+        }
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// Parse and validate a JSON map.
+    pub fn from_json(text: &str) -> Result<Self, MapError> {
+        let map: MapDef = serde_json::from_str(text).map_err(|e| MapError::Parse(e.to_string()))?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// Walkable mask, row-major. Walls and obstacles are not walkable.
+    pub fn walkable(&self) -> Vec<bool> {
+        let width = self.width as usize;
+        let mut walkable = vec![true; width * self.height as usize];
+        for &(x, y) in self.walls.iter().chain(&self.obstacles) {
+            walkable[y as usize * width + x as usize] = false;
+        }
+        walkable
+    }
+
+    /// Stable hash of everything that affects play: size, spawns, goals, spawn groups and
+    /// the walkable mask (walls and obstacles are not told apart). 64-bit FNV-1a, so it
+    /// is the same across platforms and releases.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut write = |bytes: &[u8]| {
+            for &b in bytes {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        let mut write_cells = |cells: &[(u16, u16)]| {
+            write(&(cells.len() as u32).to_le_bytes());
+            for &(x, y) in cells {
+                write(&x.to_le_bytes());
+                write(&y.to_le_bytes());
+            }
+        };
+
+        write_cells(&[(self.width, self.height)]);
+        write_cells(&self.spawns);
+        write_cells(&self.goals);
+        write(&(self.spawn_groups.len() as u32).to_le_bytes());
+        for group in &self.spawn_groups {
+            write(&(group.len() as u32).to_le_bytes());
+            for &i in group {
+                write(&(i as u32).to_le_bytes());
+            }
+        }
+        for chunk in self.walkable().chunks(8) {
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, &open)| acc | (open as u8) << i);
+            write(&[bits]);
+        }
+        hash
+    }
+
+    /// Check the map can be played: there is at least one spawn and one goal, they are
+    /// distinct open cells, and mobs can walk from every spawn to a goal.
+    pub fn validate(&self) -> Result<(), MapError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(MapError::Invalid("empty map".to_string()));
+        }
+        if self.width > MAX_MAP_SIZE || self.height > MAX_MAP_SIZE {
+            return Err(MapError::Invalid(format!(
+                "larger than {0}x{0}",
+                MAX_MAP_SIZE
+            )));
+        }
+        if self.spawns.is_empty() || self.goals.is_empty() {
+            return Err(MapError::Invalid(
+                "needs at least one spawn and one goal".to_string(),
+            ));
+        }
+        let in_bounds = |&(x, y): &(u16, u16)| x < self.width && y < self.height;
+        let named: Vec<(&str, &(u16, u16))> = self
+            .spawns
+            .iter()
+            .map(|cell| ("spawn", cell))
+            .chain(self.goals.iter().map(|cell| ("goal", cell)))
+            .collect();
+        let listed = self.walls.iter().chain(&self.obstacles);
+        if let Some((name, &(x, y))) = named
+            .iter()
+            .copied()
+            .chain(listed.map(|cell| ("blocked cell", cell)))
+            .find(|(_, cell)| !in_bounds(cell))
+        {
+            return Err(MapError::Invalid(format!(
+                "{} ({},{}) is outside the {}x{} map",
+                name, x, y, self.width, self.height
+            )));
+        }
+        if let Some(&(x, y)) = self.spawns.iter().find(|cell| self.goals.contains(cell)) {
+            return Err(MapError::Invalid(format!(
+                "({},{}) is both a spawn and a goal",
+                x, y
+            )));
+        }
+
+        if let Some(&i) = self
+            .spawn_groups
+            .iter()
+            .flatten()
+            .find(|&&i| i >= self.spawns.len())
+        {
+            return Err(MapError::Invalid(format!(
+                "spawn group refers to spawn {}, but there are {}",
+                i,
+                self.spawns.len()
+            )));
+        }
+
+        let grid = Grid::from_terrain(self.width, self.height, self.walkable());
+        for &(name, &(x, y)) in &named {
+            if grid.is_blocked_idx(grid.idx(x, y)) {
+                return Err(MapError::Invalid(format!(
+                    "{} ({},{}) is blocked",
+                    name, x, y
+                )));
+            }
+        }
+        let mut dist = vec![u32::MAX; grid.walkable.len()];
+        compute_distance_field(&grid, &self.goals, &mut dist);
+        if let Some(&(x, y)) = self
+            .spawns
+            .iter()
+            .find(|&&(x, y)| dist[grid.idx(x, y)] == u32::MAX)
+        {
+            return Err(MapError::Invalid(format!(
+                "no goal is reachable from spawn ({},{})",
+                x, y
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Load map `name` from `dir`: `<name>.txt` as ASCII, else `<name>.json`.
+pub fn load_named(dir: &Path, name: &str) -> Result<MapDef, MapError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(MapError::InvalidName(name.to_string()));
+    }
+
+    let read = |extension: &str| match std::fs::read_to_string(
+        dir.join(format!("{}.{}", name, extension)),
+    ) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(MapError::Io(e.to_string())),
+    };
+    if let Some(text) = read("txt")? {
+        return MapDef::from_ascii(&text);
+    }
+    if let Some(text) = read("json")? {
+        return MapDef::from_json(&text);
+    }
+    Err(MapError::NotFound(name.to_string()))
+}
+
+/// Names of the maps in `dir`, sorted. Empty if the directory doesn't exist.
+pub fn list_named(dir: &Path) -> Result<Vec<String>, MapError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(MapError::Io(e.to_string())),
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| MapError::Io(e.to_string()))?.path();
+        let is_map = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("txt" | "json")
+        );
+        if let (true, Some(name)) = (is_map, path.file_stem().and_then(|s| s.to_str())) {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SpawnPlan, TdConfig};
+    use crate::pathing;
+    use crate::TdGame;
+    use sim_core::Game;
+
+    const CORRIDOR: &str = "\
+##########
+#S...o..G#
+#........#
+##########
+";
+
+    #[test]
+    fn parse_ascii() {
+        let map = MapDef::from_ascii(CORRIDOR).unwrap();
+        assert_eq!((map.width, map.height), (10, 4));
+        assert_eq!(
+            (&map.spawns[..], &map.goals[..]),
+            (&[(1, 1)][..], &[(8, 1)][..])
+        );
+        assert_eq!(map.obstacles, vec![(5, 1)]);
+        assert_eq!(map.walls.len(), 10 + 10 + 4);
+
+        let walkable = map.walkable();
+        assert!(walkable[10 + 2]);
+        assert!(!walkable[10 + 5]);
+        assert!(walkable[20 + 5]);
+    }
+
+    #[test]
+    fn parse_json_matches_ascii() {
+        let ascii = MapDef::from_ascii(CORRIDOR).unwrap();
+        let json = serde_json::to_string(&ascii).unwrap();
+        assert_eq!(MapDef::from_json(&json).unwrap(), ascii);
+
+        // Walls and obstacles are optional
+        let open = MapDef::from_json(r#"{"width":3,"height":1,"spawns":[[0,0]],"goals":[[2,0]]}"#);
+        assert_eq!(open.unwrap().walkable(), vec![true; 3]);
+    }
+
+    #[test]
+    fn reject_invalid_maps() {
+        let invalid = |text: &str| MapDef::from_ascii(text).unwrap_err();
+
+        assert!(matches!(invalid("S.G\n..\n"), MapError::Parse(_)));
+        assert!(matches!(invalid("S.x.G\n"), MapError::Parse(_)));
+        assert!(matches!(invalid("S...\n"), MapError::Invalid(_)));
+        assert_eq!(
+            invalid("S.#.G\n"),
+            MapError::Invalid("no goal is reachable from spawn (0,0)".to_string())
+        );
+        // Every spawn needs a way out
+        assert!(matches!(invalid("S.G#S\n"), MapError::Invalid(_)));
+        // Diagonal steps can't cut between two blocked corners
+        assert!(matches!(invalid("S#\n#G\n"), MapError::Invalid(_)));
+
+        let json = r#"{"width":3,"height":1,"spawns":[[0,0]],"goals":[[3,0]]}"#;
+        assert!(matches!(
+            MapDef::from_json(json).unwrap_err(),
+            MapError::Invalid(_)
+        ));
+        let json = r#"{"width":3,"height":1,"spawns":[[0,0]],"goals":[[2,0]],
+            "spawn_groups":[[0],[1]]}"#;
+        assert!(matches!(
+            MapDef::from_json(json).unwrap_err(),
+            MapError::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn fingerprint_tracks_layout() {
+        let map = MapDef::from_ascii(CORRIDOR).unwrap();
+        // Pinned: fingerprints must not change between releases
+        assert_eq!(map.fingerprint(), 0xb9cd_7b2b_16d5_b57b);
+
+        let mut moved = map.clone();
+        moved.obstacles = vec![(6, 1)];
+        assert_ne!(moved.fingerprint(), map.fingerprint());
+
+        // Walls and obstacles play the same
+        let mut as_wall = map.clone();
+        as_wall.walls.push(as_wall.obstacles.pop().unwrap());
+        assert_eq!(as_wall.fingerprint(), map.fingerprint());
+    }
+
+    #[test]
+    fn game_uses_map() {
+        let map = MapDef::from_ascii(CORRIDOR).unwrap();
+        let config = TdConfig {
+            map: Some(map.clone()),
+            ..TdConfig::default()
+        };
+        let game = TdGame::new(config, 1);
+        let state = game.state();
+
+        assert_eq!((state.config.width, state.config.height), (10, 4));
+        assert_eq!(
+            (&state.config.spawns, &state.config.goals),
+            (&map.spawns, &map.goals)
+        );
+        assert_eq!(state.world.grid.walkable, map.walkable());
+        let route = pathing::route_from(state, map.spawns[0]).unwrap();
+        assert!(!route.cells.contains(&(5, 1)));
+    }
+
+    #[test]
+    fn game_uses_every_spawn_and_goal() {
+        let map = MapDef::from_ascii(
+            "\
+##########
+#S......G#
+##########
+#S......G#
+##########
+",
+        )
+        .unwrap();
+        let mut game = TdGame::new(
+            TdConfig {
+                map: Some(map),
+                ..TdConfig::default()
+            },
+            1,
+        );
+        let routes = pathing::mob_routes(game.state());
+        assert_eq!(routes[0].as_ref().unwrap().cells.last(), Some(&(8, 1)));
+        assert_eq!(routes[1].as_ref().unwrap().cells.last(), Some(&(8, 3)));
+
+        // Mobs alternate between spawns and each leaks at the goal of its lane
+        let mut events = Vec::new();
+        let mut lanes = std::collections::HashSet::new();
+        for tick in 1..10_000 {
+            events.clear();
+            game.step(tick, &[], &mut events);
+            lanes.extend(game.state().world.mobs.values().map(|m| m.y.floor() as u16));
+            if events
+                .iter()
+                .any(|e| matches!(e, crate::TdEvent::WaveEnded { .. }))
+            {
+                break;
+            }
+        }
+        assert_eq!(lanes, [1, 3].into());
+        let state = game.state();
+        assert_eq!(state.leaks, state.config.wave_size(1, 1));
+    }
+
+    #[test]
+    fn bundled_maps_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../maps");
+        let names = list_named(&dir).unwrap();
+        assert!(!names.is_empty());
+        for name in names {
+            load_named(&dir, &name).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        }
+
+        let map = load_named(&dir, "open-field").unwrap();
+        let game = TdGame::new(
+            TdConfig {
+                map: Some(map.clone()),
+                ..TdConfig::default()
+            },
+            1,
+        );
+        assert_eq!(
+            game.state().config.spawn_plan,
+            SpawnPlan::Groups(map.spawn_groups)
+        );
+    }
+
+    #[test]
+    fn load_from_directory() {
+        let dir = std::env::temp_dir().join(format!("td-maps-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("corridor.txt"), CORRIDOR).unwrap();
+        let json = serde_json::to_string(&MapDef::from_ascii(CORRIDOR).unwrap()).unwrap();
+        std::fs::write(dir.join("open.json"), json).unwrap();
+        std::fs::write(dir.join("notes.md"), "not a map").unwrap();
+
+        assert_eq!(list_named(&dir).unwrap(), vec!["corridor", "open"]);
+        assert_eq!(load_named(&dir, "corridor").unwrap().goals, vec![(8, 1)]);
+        assert_eq!(load_named(&dir, "open").unwrap().goals, vec![(8, 1)]);
+        assert_eq!(
+            load_named(&dir, "missing"),
+            Err(MapError::NotFound("missing".to_string()))
+        );
+        assert_eq!(
+            load_named(&dir, "../corridor"),
+            Err(MapError::InvalidName("../corridor".to_string()))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Procedural map generation: a maze from the seed, upscaled and dilated into organic
+//! paths. Produces a [`MapDef`], so generated and hand-authored maps are played, checked
+//! and fingerprinted the same way.
+
+use crate::config::TdConfig;
+use crate::map::{MapDef, MAX_MAP_SIZE};
+use maze_generator::prelude::{Coordinates, Generator};
+use maze_generator::recursive_backtracking::RbGenerator;
+use std::fmt;
+use td_map_generator::dilate::{dilate_path, DilationParams};
+use td_map_generator::grid::Tile;
+use td_map_generator::noise::ValueNoise1D;
+use td_map_generator::upscale::upscale_path;
+use td_map_generator::{create_seed, solve_maze_bfs};
+
+/// Tiles per maze cell.
+const SCALE: i32 = 3;
+
+/// Largest accepted `maze_size`.
+pub const MAX_MAZE_SIZE: i32 = MAX_MAP_SIZE as i32 / SCALE;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerateError {
+    /// `maze_size` is below 1 or above [`MAX_MAZE_SIZE`].
+    InvalidSize(i32),
+    /// The maze generator failed.
+    Maze(String),
+    /// The maze has no path from start to goal.
+    Unsolvable,
+    /// The dilated grid lacks a spawn or goal tile.
+    MissingEndpoint(&'static str),
+    /// The result is not a playable map.
+    Invalid(String),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::InvalidSize(size) => {
+                write!(f, "maze_size {} is outside 1-{}", size, MAX_MAZE_SIZE)
+            }
+            GenerateError::Maze(msg) => write!(f, "maze generation failed: {}", msg),
+            GenerateError::Unsolvable => write!(f, "generated maze has no solution"),
+            GenerateError::MissingEndpoint(name) => {
+                write!(f, "generated map has no {} tile", name)
+            }
+            GenerateError::Invalid(msg) => write!(f, "generated map is invalid: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// Generate the map for `seed` from `config.maze_size` and the dilation settings, with
+/// one spawn and one goal. Deterministic: the same inputs give the same map.
+pub fn generate_map(config: &TdConfig, seed: u64) -> Result<MapDef, GenerateError> {
+    let maze_size = config.maze_size;
+    if !(1..=MAX_MAZE_SIZE).contains(&maze_size) {
+        return Err(GenerateError::InvalidSize(maze_size));
+    }
+
+    // 1. Generate maze
+    let mut generator = RbGenerator::new(Some(create_seed(seed)));
+    let mut maze = generator
+        .generate(maze_size, maze_size)
+        .map_err(|e| GenerateError::Maze(e.to_string()))?;
+    maze.goal = Coordinates::new(maze_size - 1, maze_size - 1);
+
+    // 2. Solve maze
+    let path = solve_maze_bfs(&maze).ok_or(GenerateError::Unsolvable)?;
+
+    // 3. Upscale path to tile grid (scale factor 3)
+    let (mut tile_grid, spine) = upscale_path(&path, maze_size as usize, maze_size as usize);
+
+    // 4. Compute distance field for dilation
+    let dist_field = td_map_generator::distance::compute_distance_field(
+        tile_grid.width,
+        tile_grid.height,
+        &spine,
+    );
+
+    // 5. Create noise and dilate path
+    let noise = ValueNoise1D::new(seed, 256, 30.0);
+    let params = DilationParams {
+        base_radius: config.dilation_base_radius,
+        amplitude: config.dilation_amplitude,
+    };
+    dilate_path(&mut tile_grid, &dist_field, &noise, &params);
+
+    // 6. Collect walls and the spawn and goal tiles
+    let mut walls = Vec::new();
+    let mut spawn = None;
+    let mut goal = None;
+    for y in 0..tile_grid.height {
+        for x in 0..tile_grid.width {
+            let cell = (x as u16, y as u16);
+            match tile_grid.get(x, y) {
+                Tile::Path => {}
+                Tile::Start => spawn = Some(cell),
+                Tile::Goal => goal = Some(cell),
+                Tile::Wall => walls.push(cell),
+            }
+        }
+    }
+
+    let map = MapDef {
+        width: tile_grid.width as u16,
+        height: tile_grid.height as u16,
+        spawns: vec![spawn.ok_or(GenerateError::MissingEndpoint("spawn"))?],
+        goals: vec![goal.ok_or(GenerateError::MissingEndpoint("goal"))?],
+        walls,
+        obstacles: Vec::new(),
+        spawn_groups: Vec::new(),
+    };
+    map.validate()
+        .map_err(|e| GenerateError::Invalid(e.to_string()))?;
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TdGame;
+
+    #[test]
+    fn generation_is_deterministic() {
+        let config = TdConfig::default();
+        let a = generate_map(&config, 5).unwrap();
+        let b = generate_map(&config, 5).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_eq!((a.width, a.height), (30, 30));
+    }
+
+    #[test]
+    fn invalid_size_is_an_error() {
+        for maze_size in [0, -3, MAX_MAZE_SIZE + 1] {
+            let config = TdConfig {
+                maze_size,
+                ..TdConfig::default()
+            };
+            assert_eq!(
+                generate_map(&config, 1),
+                Err(GenerateError::InvalidSize(maze_size))
+            );
+            assert!(matches!(
+                TdGame::try_new(config, 1),
+                Err(GenerateError::InvalidSize(size)) if size == maze_size
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_map_is_an_error() {
+        let mut map = generate_map(&TdConfig::default(), 1).unwrap();
+        map.spawns.clear();
+        let config = TdConfig {
+            map: Some(map),
+            ..TdConfig::default()
+        };
+        assert!(matches!(
+            TdGame::try_new(config, 1),
+            Err(GenerateError::Invalid(_))
+        ));
+    }
+}
@@ -1,4 +1,127 @@
+pub mod prompts;
+pub mod resources;
 pub mod server;
 pub mod types;
 
 pub use server::TdMcpServer;
+
+use crate::api::TdApi;
+use rmcp::transport::streamable_http_server::{
+    session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+};
+use std::sync::Arc;
+
+/// MCP over streamable HTTP. Sessions are stateful: each client keeps one
+/// [`TdMcpServer`] (and its resource subscriptions) and a stream for notifications.
+pub fn http_service(api: TdApi) -> StreamableHttpService<TdMcpServer, LocalSessionManager> {
+    StreamableHttpService::new(
+        move || Ok(TdMcpServer::with_api(api.clone())),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig {
+            stateful_mode: true,
+            ..Default::default()
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TdGame;
+    use axum::body::Body;
+    use axum::http::{header, Request, Response};
+    use sim_server::{GameServer, ServerConfig};
+    use std::time::Duration;
+    use tokio_stream::StreamExt;
+    use tower::ServiceExt;
+
+    const SESSION_ID: &str = "mcp-session-id";
+
+    fn post(session: Option<&str>, body: serde_json::Value) -> Request<Body> {
+        let mut request = Request::post("/")
+            .header(header::ACCEPT, "application/json, text/event-stream")
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(session) = session {
+            request = request.header(SESSION_ID, session);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn send(
+        service: &StreamableHttpService<TdMcpServer, LocalSessionManager>,
+        request: Request<Body>,
+    ) -> Response<Body> {
+        service
+            .clone()
+            .oneshot(request)
+            .await
+            .unwrap()
+            .map(Body::new)
+    }
+
+    /// Collect the body and check that it answers with a result, not an error.
+    async fn expect_result(response: Response<Body>) {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("\"result\""), "{body}");
+    }
+
+    #[tokio::test]
+    async fn http_subscription_notifies_on_decision_ticks() {
+        let config = ServerConfig {
+            simulation_rate: 20,
+            interaction_rate: 4,
+            ..ServerConfig::default()
+        };
+        let api = TdApi::new(Arc::new(GameServer::<TdGame>::new(config)));
+        let params = serde_json::from_str(r#"{"seed": 1}"#).unwrap();
+        let match_id = api.create_match(params).await.unwrap().match_id;
+        api.join_match(match_id).await.unwrap();
+        let service = http_service(api);
+
+        let initialize = serde_json::json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0"},
+            },
+        });
+        let response = send(&service, post(None, initialize)).await;
+        let session = response.headers()[SESSION_ID].to_str().unwrap().to_string();
+        expect_result(response).await;
+        let initialized =
+            serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        send(&service, post(Some(&session), initialized)).await;
+
+        // Notifications arrive on the session's standalone stream
+        let get = Request::get("/")
+            .header(header::ACCEPT, "text/event-stream")
+            .header(SESSION_ID, &session)
+            .body(Body::empty())
+            .unwrap();
+        let mut stream = send(&service, get).await.into_body().into_data_stream();
+
+        let uri = format!("td://match/{}/observation", match_id);
+        let subscribe = serde_json::json!({
+            "jsonrpc": "2.0", "id": 2, "method": "resources/subscribe", "params": {"uri": uri},
+        });
+        expect_result(send(&service, post(Some(&session), subscribe)).await).await;
+
+        let updated = tokio::time::timeout(Duration::from_secs(10), async {
+            let mut received = String::new();
+            while let Some(chunk) = stream.next().await {
+                received.push_str(&String::from_utf8_lossy(&chunk.unwrap()));
+                if received.contains("notifications/resources/updated") {
+                    return received;
+                }
+            }
+            panic!("stream ended without an update: {received}");
+        })
+        .await
+        .expect("no resources/updated notification within 10s");
+        assert!(updated.contains(&uri), "{updated}");
+    }
+}
@@ -0,0 +1,149 @@
+//! MCP prompts: parameterized strategy starters.
+
+use super::resources::{self, TdResource};
+use crate::api::TdApi;
+use rmcp::model::{
+    GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageContent,
+    PromptMessageRole, RawEmbeddedResource, AnnotateAble,
+};
+use rmcp::ErrorData as McpError;
+
+const STRATEGIES: [(&str, &str); 3] = [
+    (
+        "maze",
+        "Build a maze: place towers on the current mob path so mobs must detour, \
+         lengthening the route through your towers' range. Never fully block the path, \
+         or mobs will attack towers instead of walking.",
+    ),
+    (
+        "killzone",
+        "Build a kill zone: find a stretch of path near the goal that mobs must pass, \
+         cluster towers around it so their ranges overlap, and upgrade those towers \
+         before building elsewhere.",
+    ),
+    (
+        "economy",
+        "Play for economy: build the fewest towers that hold the early waves, since \
+         build costs rise every wave, then spend wave rewards on upgrades, which scale \
+         damage more cheaply than new towers later on.",
+    ),
+];
+
+fn argument(name: &str, description: &str, required: bool) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        required: Some(required),
+    }
+}
+
+pub fn list() -> Vec<Prompt> {
+    let strategies: Vec<&str> = STRATEGIES.iter().map(|(name, _)| *name).collect();
+    vec![
+        Prompt::new(
+            "play_match",
+            Some("Step-by-step plan for joining and playing a match with a chosen strategy"),
+            Some(vec![
+                argument("match_id", "Match to play", true),
+                argument(
+                    "strategy",
+                    &format!("One of: {} (default: maze)", strategies.join(", ")),
+                    false,
+                ),
+            ]),
+        ),
+        Prompt::new(
+            "review_position",
+            Some("Analyze the current state of a match and suggest the next actions"),
+            Some(vec![argument("match_id", "Match to review", true)]),
+        ),
+    ]
+}
+
+/// Prompt arguments arrive as strings, but accept JSON numbers too.
+fn match_id_argument(arguments: &JsonObject) -> Result<u64, McpError> {
+    let value = arguments
+        .get("match_id")
+        .ok_or_else(|| McpError::invalid_params("Missing argument: match_id", None))?;
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+        .ok_or_else(|| McpError::invalid_params(format!("Invalid match_id: {}", value), None))
+}
+
+pub async fn get(
+    api: &TdApi,
+    name: &str,
+    arguments: Option<JsonObject>,
+) -> Result<GetPromptResult, McpError> {
+    let arguments = arguments.unwrap_or_default();
+    match name {
+        "play_match" => {
+            let match_id = match_id_argument(&arguments)?;
+            let strategy = arguments
+                .get("strategy")
+                .and_then(|v| v.as_str())
+                .unwrap_or("maze");
+            let (strategy, advice) = STRATEGIES
+                .iter()
+                .find(|(name, _)| *name == strategy)
+                .ok_or_else(|| {
+                    McpError::invalid_params(format!("Unknown strategy: {}", strategy), None)
+                })?;
+
+            let text = format!(
+                "You are playing Tower Defense match {match_id} using the {strategy} strategy.\n\n\
+                 1. Read the {rules} resource to learn the rules.\n\
+                 2. Call join_match with match_id={match_id} and keep the session_token.\n\
+                 3. Read {map} for the layout, then call get_current_path to see the mob route.\n\
+                 4. Strategy: {advice}\n\
+                 5. Loop: call observe_next with the last tick you saw, then place_tower or \
+                 upgrade_tower as gold allows. The simulation runs in real time whether or \
+                 not you act, so keep each decision short.\n\
+                 6. Stop when the match finishes and summarize what worked.",
+                rules = TdResource::Rules.uri(),
+                map = TdResource::Map { match_id }.uri(),
+            );
+
+            Ok(GetPromptResult {
+                description: Some(format!("Play match {} ({})", match_id, strategy)),
+                messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+            })
+        }
+        "review_position" => {
+            let match_id = match_id_argument(&arguments)?;
+            let uri = TdResource::Observation { match_id }.uri();
+            let observation = resources::read(api, &uri).await?;
+
+            let text = format!(
+                "Here is the current state of Tower Defense match {}. Assess the threat of \
+                 the current and next wave against the towers' damage and coverage of the \
+                 mob path, then list the next placements or upgrades you would make with \
+                 the available gold, most important first.",
+                match_id
+            );
+
+            Ok(GetPromptResult {
+                description: Some(format!("Review match {}", match_id)),
+                messages: vec![
+                    PromptMessage::new_text(PromptMessageRole::User, text),
+                    PromptMessage {
+                        role: PromptMessageRole::User,
+                        content: PromptMessageContent::Resource {
+                            resource: RawEmbeddedResource {
+                                meta: None,
+                                resource: observation,
+                            }
+                            .no_annotation(),
+                        },
+                    },
+                ],
+            })
+        }
+        _ => Err(McpError::invalid_params(
+            format!("Unknown prompt: {}", name),
+            None,
+        )),
+    }
+}
@@ -0,0 +1,166 @@
+//! MCP resources: game rules and per-match state, readable without a session.
+//!
+//! - `td://rules`: the game rules (same as the `rules` tool)
+//! - `td://match/{match_id}/observation`: current spectator observation, without the
+//!   `walkable` grid. Subscribable; updated on every decision tick.
+//! - `td://match/{match_id}/map`: static map layout
+
+use crate::api::{self, ApiError, TdApi};
+use rmcp::model::{
+    AnnotateAble, RawResource, RawResourceTemplate, Resource, ResourceContents,
+    ResourceTemplate,
+};
+use rmcp::ErrorData as McpError;
+
+pub const RULES_URI: &str = "td://rules";
+
+const JSON_MIME_TYPE: &str = "application/json";
+
+/// A resource URI, parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TdResource {
+    Rules,
+    Observation { match_id: u64 },
+    Map { match_id: u64 },
+}
+
+impl TdResource {
+    pub fn parse(uri: &str) -> Option<Self> {
+        if uri == RULES_URI {
+            return Some(TdResource::Rules);
+        }
+        let rest = uri.strip_prefix("td://match/")?;
+        let (match_id, kind) = rest.split_once('/')?;
+        let match_id = match_id.parse().ok()?;
+        match kind {
+            "observation" => Some(TdResource::Observation { match_id }),
+            "map" => Some(TdResource::Map { match_id }),
+            _ => None,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        match self {
+            TdResource::Rules => RULES_URI.to_string(),
+            TdResource::Observation { match_id } => {
+                format!("td://match/{}/observation", match_id)
+            }
+            TdResource::Map { match_id } => format!("td://match/{}/map", match_id),
+        }
+    }
+
+    fn describe(&self) -> Resource {
+        let (name, description) = match self {
+            TdResource::Rules => (
+                "rules".to_string(),
+                "Complete rules and mechanics of the game".to_string(),
+            ),
+            TdResource::Observation { match_id } => (
+                format!("match-{}-observation", match_id),
+                format!("Current state of match {}", match_id),
+            ),
+            TdResource::Map { match_id } => (
+                format!("match-{}-map", match_id),
+                format!("Map layout of match {}", match_id),
+            ),
+        };
+        RawResource {
+            description: Some(description),
+            mime_type: Some(JSON_MIME_TYPE.to_string()),
+            ..RawResource::new(self.uri(), name)
+        }
+        .no_annotation()
+    }
+}
+
+pub fn api_error(e: ApiError) -> McpError {
+    match e {
+        ApiError::NotFound(message) => McpError::resource_not_found(message, None),
+        ApiError::Rejected(message) | ApiError::Unavailable(message) => {
+            McpError::invalid_params(message, None)
+        }
+    }
+}
+
+/// The rules plus the observation and map of every active match.
+pub async fn list(api: &TdApi) -> Vec<Resource> {
+    let mut resources = vec![TdResource::Rules.describe()];
+    for info in api.list_matches().await.matches {
+        let match_id = info.match_id;
+        resources.push(TdResource::Observation { match_id }.describe());
+        resources.push(TdResource::Map { match_id }.describe());
+    }
+    resources
+}
+
+pub fn templates() -> Vec<ResourceTemplate> {
+    [
+        (
+            "td://match/{match_id}/observation",
+            "match-observation",
+            "Current state of a match at the latest tick. Subscribe to be notified on every decision tick.",
+        ),
+        (
+            "td://match/{match_id}/map",
+            "match-map",
+            "Static map layout of a match: size, spawn, goal and walkable cells.",
+        ),
+    ]
+    .into_iter()
+    .map(|(uri_template, name, description)| {
+        RawResourceTemplate {
+            uri_template: uri_template.to_string(),
+            name: name.to_string(),
+            title: None,
+            description: Some(description.to_string()),
+            mime_type: Some(JSON_MIME_TYPE.to_string()),
+            icons: None,
+        }
+        .no_annotation()
+    })
+    .collect()
+}
+
+/// Read a resource as JSON text contents.
+pub async fn read(api: &TdApi, uri: &str) -> Result<ResourceContents, McpError> {
+    let resource = TdResource::parse(uri)
+        .ok_or_else(|| McpError::resource_not_found(format!("Unknown resource: {}", uri), None))?;
+
+    let json = match resource {
+        TdResource::Rules => serde_json::to_string(&api::rules()).unwrap(),
+        TdResource::Observation { match_id } => {
+            let obs = api.observe_spectator(match_id).await.map_err(api_error)?;
+            serde_json::to_string(&obs).unwrap()
+        }
+        TdResource::Map { match_id } => {
+            let map = api.get_map(match_id).await.map_err(api_error)?;
+            serde_json::to_string(&map).unwrap()
+        }
+    };
+
+    Ok(ResourceContents::TextResourceContents {
+        uri: uri.to_string(),
+        mime_type: Some(JSON_MIME_TYPE.to_string()),
+        text: json,
+        meta: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_round_trip() {
+        for resource in [
+            TdResource::Rules,
+            TdResource::Observation { match_id: 7 },
+            TdResource::Map { match_id: 42 },
+        ] {
+            assert_eq!(TdResource::parse(&resource.uri()), Some(resource));
+        }
+        assert_eq!(TdResource::parse("td://match/x/map"), None);
+        assert_eq!(TdResource::parse("td://match/1/towers"), None);
+        assert_eq!(TdResource::parse("td://match/1"), None);
+    }
+}
@@ -1,30 +1,46 @@
+use super::resources::{self, TdResource};
 use super::types::*;
-use crate::actions::TdAction;
-use crate::config::TdConfig;
-use crate::observe;
+use super::prompts;
+use crate::api::{self, TdApi};
 use crate::TdGame;
 use rmcp::{
-    ServerHandler,
+    ErrorData as McpError, Peer, RoleServer, ServerHandler,
     handler::server::{tool::ToolRouter, wrapper::Parameters},
-    model::{CallToolResult, ServerCapabilities, ServerInfo},
+    model::{
+        CallToolResult, GetPromptRequestParams, GetPromptResult, ListPromptsResult,
+        ListResourceTemplatesResult, ListResourcesResult, PaginatedRequestParams,
+        ReadResourceRequestParams, ReadResourceResult, ResourceUpdatedNotificationParam,
+        ServerCapabilities, ServerInfo, SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+    service::RequestContext,
     tool, tool_router,
 };
-use sim_server::{GameServer, MatchStatus, ObserveNextError, ServerConfig, SessionToken};
-use std::cmp::Reverse;
-use std::collections::BinaryHeap;
-use std::sync::Arc;
+use sim_server::{
+    GameServer, MatchSubscription, ServerConfig, SessionToken, SubscriptionError,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
 
 /// MCP Server for the Tower Defense game.
 pub struct TdMcpServer {
-    game_server: Arc<GameServer<TdGame>>,
+    api: TdApi,
     tool_router: ToolRouter<Self>,
+    /// Resource subscriptions of this client: URI -> cancels the notification task.
+    /// Dropping the server cancels them all.
+    resource_subscriptions: Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>,
 }
 
 impl TdMcpServer {
     pub fn new(game_server: Arc<GameServer<TdGame>>) -> Self {
+        Self::with_api(TdApi::new(game_server))
+    }
+
+    pub fn with_api(api: TdApi) -> Self {
         Self {
-            game_server,
+            api,
             tool_router: Self::tool_router(),
+            resource_subscriptions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -34,286 +50,87 @@ impl TdMcpServer {
             interaction_rate: 4,
             max_matches: 100,
             event_buffer_capacity: 1024,
+            ..ServerConfig::default()
         };
         let game_server = Arc::new(GameServer::<TdGame>::new(config));
         Self::new(game_server)
     }
 }
 
-const NEIGHBORS: [(i32, i32); 8] = [
-    (0, -1),  // N
-    (1, -1),  // NE
-    (1, 0),   // E
-    (1, 1),   // SE
-    (0, 1),   // S
-    (-1, 1),  // SW
-    (-1, 0),  // W
-    (-1, -1), // NW
-];
-
-/// Compute the current mob path from spawn to goal using the same Dijkstra +
-/// gradient descent algorithm as the game engine.
-fn compute_mob_path(obs: &TdObservation) -> Vec<Position> {
-    let w = obs.map_width as usize;
-    let h = obs.map_height as usize;
-    let size = w * h;
-
-    // Build blocked grid: non-walkable terrain OR tower OR pending build.
-    let mut blocked = vec![false; size];
-    for (i, &walkable) in obs.walkable.iter().enumerate() {
-        if !walkable {
-            blocked[i] = true;
-        }
-    }
-    for t in &obs.towers {
-        blocked[t.y as usize * w + t.x as usize] = true;
-    }
-    for b in &obs.build_queue {
-        blocked[b.y as usize * w + b.x as usize] = true;
-    }
-
-    // Dijkstra from goal (matching pathing.rs exactly).
-    let goal_idx = obs.goal.y as usize * w + obs.goal.x as usize;
-    let spawn_idx = obs.spawn.y as usize * w + obs.spawn.x as usize;
-
-    let mut dist = vec![u32::MAX; size];
-    if blocked[goal_idx] {
-        return Vec::new();
-    }
-    dist[goal_idx] = 0;
-
-    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::new();
-    heap.push(Reverse((0, goal_idx)));
-
-    while let Some(Reverse((d, idx))) = heap.pop() {
-        if d > dist[idx] {
-            continue;
-        }
-        // Early exit once we've settled the spawn cell.
-        if idx == spawn_idx {
-            break;
-        }
-
-        let x = (idx % w) as i32;
-        let y = (idx / w) as i32;
-
-        for (i, &(dx, dy)) in NEIGHBORS.iter().enumerate() {
-            let nx = x + dx;
-            let ny = y + dy;
-            if nx < 0 || ny < 0 || nx >= w as i32 || ny >= h as i32 {
-                continue;
-            }
-            let nidx = ny as usize * w + nx as usize;
-            if blocked[nidx] {
-                continue;
-            }
-            // Diagonal: both adjacent cardinal cells must be unblocked.
-            let is_diag = i % 2 == 1;
-            if is_diag {
-                let cx1 = (x + dx) as usize + y as usize * w;
-                let cx2 = x as usize + (y + dy) as usize * w;
-                if blocked[cx1] || blocked[cx2] {
-                    continue;
-                }
-            }
-            let cost = if is_diag { 14u32 } else { 10u32 };
-            let new_dist = d.saturating_add(cost);
-            if new_dist < dist[nidx] {
-                dist[nidx] = new_dist;
-                heap.push(Reverse((new_dist, nidx)));
-            }
-        }
-    }
-
-    if dist[spawn_idx] == u32::MAX {
-        return Vec::new();
-    }
-
-    // Gradient descent from spawn to goal.
-    let mut path = vec![Position {
-        x: obs.spawn.x,
-        y: obs.spawn.y,
-    }];
-    let mut cx = obs.spawn.x;
-    let mut cy = obs.spawn.y;
+/// Notify the client that `uri` changed after every decision tick, until cancelled, the
+/// match ends or the client goes away. Owns the spectator `session` and leaves with it.
+async fn notify_decision_ticks(
+    game_server: Arc<GameServer<TdGame>>,
+    match_id: u64,
+    session: SessionToken,
+    mut subscription: MatchSubscription<TdGame>,
+    uri: String,
+    peer: Peer<RoleServer>,
+    mut cancel: oneshot::Receiver<()>,
+) {
+    // The first update is the state at subscription time, not a new decision tick
+    let _ = subscription.recv().await;
 
     loop {
-        if cx == obs.goal.x && cy == obs.goal.y {
-            break;
-        }
-        let cur_dist = dist[cy as usize * w + cx as usize];
-        let mut best: Option<(u16, u16)> = None;
-        let mut best_dist = cur_dist;
-
-        for (i, &(dx, dy)) in NEIGHBORS.iter().enumerate() {
-            let nx = cx as i32 + dx;
-            let ny = cy as i32 + dy;
-            if nx < 0 || ny < 0 || nx >= w as i32 || ny >= h as i32 {
-                continue;
-            }
-            let nidx = ny as usize * w + nx as usize;
-            if blocked[nidx] {
-                continue;
-            }
-            let is_diag = i % 2 == 1;
-            if is_diag {
-                let cx1 = (cx as i32 + dx) as usize + cy as usize * w;
-                let cx2 = cx as usize + (cy as i32 + dy) as usize * w;
-                if blocked[cx1] || blocked[cx2] {
-                    continue;
+        tokio::select! {
+            _ = &mut cancel => break,
+            update = subscription.recv() => match update {
+                Ok(_) | Err(SubscriptionError::Lagged(_)) => {
+                    let param = ResourceUpdatedNotificationParam { uri: uri.clone() };
+                    if peer.notify_resource_updated(param).await.is_err() {
+                        break;
+                    }
                 }
-            }
-            if dist[nidx] < best_dist {
-                best_dist = dist[nidx];
-                best = Some((nx as u16, ny as u16));
-            }
-        }
-
-        match best {
-            Some((nx, ny)) => {
-                path.push(Position { x: nx, y: ny });
-                cx = nx;
-                cy = ny;
-            }
-            None => break, // Stuck (shouldn't happen if dist[spawn] != MAX)
+                Err(SubscriptionError::Closed) => break,
+            },
         }
     }
 
-    path
+    let _ = game_server.leave_match(match_id, session).await;
+}
+
+fn to_json<T: serde::Serialize>(result: Result<T, api::ApiError>) -> Result<String, String> {
+    result
+        .map(|value| serde_json::to_string(&value).unwrap())
+        .map_err(|e| e.to_string())
 }
 
 #[tool_router]
 impl TdMcpServer {
     /// Create a new Tower Defense match.
-    #[tool(description = "Create a new Tower Defense match with the specified seed and player count")]
+    #[tool(description = "Create a new Tower Defense match with the specified seed and player count. Pass 'map' to play a named map (see list_maps) instead of one generated from the seed. 'difficulty' (easy, normal, hard, nightmare) scales mob HP, wave size, gold, max_leaks and the pause between waves; other optional fields override single settings (timing, mob speed, map generation, Basic tower stats). Out-of-range values are rejected.")]
     async fn create_match(
         &self,
         Parameters(params): Parameters<CreateMatchParams>,
     ) -> Result<String, String> {
-        let game_config = TdConfig {
-            tick_hz: 20,
-            waves_total: params.waves,
-            player_count: params.required_players,
-            ..TdConfig::default()
-        };
+        to_json(self.api.create_match(params).await)
+    }
 
-        let match_id = self
-            .game_server
-            .create_match_with_players(game_config, params.seed, params.required_players)
-            .await
-            .map_err(|e| format!("Failed to create match: {}", e))?;
+    /// List the named maps.
+    #[tool(description = "List the named maps available to create_match's 'map' parameter. Named maps are fixed layouts, independent of the seed.")]
+    async fn list_maps(&self) -> Result<String, String> {
+        to_json(self.api.list_maps().await)
+    }
 
-        Ok(serde_json::to_string(&CreateMatchResult { match_id }).unwrap())
+    /// Preview the map of a seed or named map.
+    #[tool(description = "Preview the map create_match would use for a seed and map generation settings (or a named map), without creating a match: size, spawns, goals, walkable grid, spawn-to-goal path lengths, ASCII rendering and a fingerprint. create_match returns the same fingerprint, so equal fingerprints mean the same map.")]
+    async fn preview_map(
+        &self,
+        Parameters(params): Parameters<PreviewMapParams>,
+    ) -> Result<String, String> {
+        to_json(self.api.preview_map(params).await)
     }
 
     /// List all active matches.
     #[tool(description = "List all active Tower Defense matches")]
     async fn list_matches(&self) -> Result<String, String> {
-        let matches = self.game_server.list_matches().await;
-
-        let matches: Vec<_> = matches
-            .into_iter()
-            .map(|m| MatchInfoResult {
-                match_id: m.match_id,
-                status: match m.status {
-                    MatchStatus::WaitingForPlayers { current, required } => {
-                        MatchStatusInfo::WaitingForPlayers { current, required }
-                    }
-                    MatchStatus::Running => MatchStatusInfo::Running,
-                    MatchStatus::Finished(outcome) => MatchStatusInfo::Finished {
-                        outcome: format!("{:?}", outcome),
-                    },
-                    MatchStatus::Terminated => MatchStatusInfo::Terminated,
-                },
-                current_tick: m.current_tick,
-                player_count: m.player_count,
-            })
-            .collect();
-
-        Ok(serde_json::to_string(&ListMatchesResult { matches }).unwrap())
+        Ok(serde_json::to_string(&self.api.list_matches().await).unwrap())
     }
 
     /// Get the game rules and mechanics.
     #[tool(description = "Get the complete rules and mechanics of the Tower Defense game. Call this first to understand how to play.")]
     async fn rules(&self) -> Result<String, String> {
-        let rules = RulesResult {
-            game: "Tower Defense".to_string(),
-            objective: "Defend your base by building towers to stop waves of mobs from reaching the goal. Survive all waves to win. (NOTE: You are observing only - the simulation runs at a fixed rate regardless of your calls)".to_string(),
-            win_condition: "Complete all waves without exceeding the maximum number of leaks (mobs reaching the goal).".to_string(),
-            lose_condition: "If more than max_leaks mobs reach the goal, you lose.".to_string(),
-            map: MapRules {
-                description: "A 2D grid with procedurally generated terrain. Each cell is either walkable (path) or non-walkable (wall). Mobs only travel on walkable cells. Towers can only be placed on walkable, unoccupied cells. The terrain is generated from a maze and dilated into organic paths.".to_string(),
-                default_size: "30x30 cells (maze_size=10, scale factor 3)".to_string(),
-                spawn_description: "Mobs spawn at the Start tile determined by map generation. Check the 'spawn' field in observations.".to_string(),
-                goal_description: "Mobs try to reach the Goal tile determined by map generation. Check the 'goal' field in observations. Mobs pathfind along walkable cells around towers.".to_string(),
-            },
-            towers: TowerRules {
-                placement: "Use the place_tower tool to queue a tower build. Towers can ONLY be placed on buildable cells (use get_buildable_cells to get them). Non-walkable cells are permanent terrain walls and cannot be built on. Cost scales with wave number (base_cost * 1.12^wave). Cell is blocked immediately when build starts.".to_string(),
-                attack: "Towers automatically attack the nearest mob within range every fire_period. Damage scales with upgrade level (base_dmg * 1.15^level).".to_string(),
-                destruction: "Mobs attack adjacent towers. When a tower's HP reaches 0, it is destroyed and the cell becomes unblocked.".to_string(),
-                tower_types: vec![
-                    TowerTypeInfo {
-                        name: "Basic".to_string(),
-                        cost: 15,
-                        hp: 100,
-                        range: 4,
-                        damage: 5,
-                        description: "Standard attack tower. Base cost 15 (scales with wave). Base damage 5 (scales with upgrades). Range 4.".to_string(),
-                    },
-                ],
-            },
-            mobs: MobRules {
-                movement: "Mobs spawn during waves and pathfind toward the goal, moving around towers. They take the shortest available path. If the path is completely blocked, mobs will attack towers in their way to create a path.".to_string(),
-                leaking: "When a mob reaches the goal, it 'leaks' and is removed. Each leak increments the leak counter.".to_string(),
-                combat: "Mobs attack towers that block their path. When adjacent to a blocking tower, they deal damage instead of moving.".to_string(),
-            },
-            waves: WaveRules {
-                progression: "The game consists of multiple waves with exponential scaling. Mob HP and wave size grow each wave.".to_string(),
-                pause_between: "There is an inter_wave_pause between waves (also before the first wave), giving you time to build and upgrade towers.".to_string(),
-                scaling: "Mob HP: 10 * 1.15^wave * players. Wave size: 8 * 1.08^wave * players. Both scale linearly with player count.".to_string(),
-            },
-            economy: EconomyRules {
-                income: "Starting gold: 50 + 30*(players-1). Wave reward: 25 * 1.12^wave * players. Kill reward: 1 * 1.08^wave. Income scales with player count.".to_string(),
-                spending: "Tower build cost: base_cost * 1.12^wave. Upgrade cost: 20 * 1.20^next_level. Gold is deducted immediately.".to_string(),
-            },
-            actions: vec![
-                ActionRule {
-                    name: "get_buildable_cells".to_string(),
-                    description: "Get all buildable cell coordinates. The map never changes, so call this once after joining.".to_string(),
-                    parameters: "match_id, session_token.".to_string(),
-                },
-                ActionRule {
-                    name: "get_current_path".to_string(),
-                    description: "Get the current shortest path mobs follow from spawn to goal. Changes when towers are built/destroyed. Call after placing towers to see the new route.".to_string(),
-                    parameters: "match_id, session_token.".to_string(),
-                },
-                ActionRule {
-                    name: "place_tower".to_string(),
-                    description: "Queue a tower to be built at the specified coordinates. Use the place_tower MCP tool directly.".to_string(),
-                    parameters: "match_id, session_token, intended_tick, x, y, tower_type (default 'Basic').".to_string(),
-                },
-                ActionRule {
-                    name: "upgrade_tower".to_string(),
-                    description: "Upgrade a tower to increase its damage. Cost: 20 * 1.20^(current_level+1). Use the upgrade_tower MCP tool directly.".to_string(),
-                    parameters: "match_id, session_token, intended_tick, tower_id (from observe response).".to_string(),
-                },
-            ],
-            tips: vec![
-                "*** CRITICAL: observe_next is READ-ONLY and DOES NOT CONTROL the simulation. The server ticks at a fixed rate regardless of your calls ***".to_string(),
-                "*** Calling observe_next faster/slower does NOT speed up/slow down the game. You are only polling for state ***".to_string(),
-                "RECOMMENDED START: 1) Call get_buildable_cells to learn the map layout. 2) Call get_current_path to see the mob route from spawn to goal. 3) Use this info to plan tower placements, then start building.".to_string(),
-                "Use observe_next to stream game state updates. Pass after_tick=0 for the first call.".to_string(),
-                "IMPORTANT: Always pass the tick value from the previous response. If you repeat the same after_tick, you WILL be forced to wait for new data - this prevents spam.".to_string(),
-                "Your actions (place_tower, upgrade_tower) are independent of observe_next - submit them with intended_tick and the server will execute them.".to_string(),
-                "The mob path can change when towers are placed or destroyed — mobs reroute around obstacles. Call get_current_path periodically (e.g. after a batch of tower placements) to see the updated route and adjust your strategy.".to_string(),
-                "Place towers strategically on walkable cells to create longer paths - mobs will pathfind around them.".to_string(),
-                "Upgrade existing towers for more damage rather than always building new ones. Upgraded towers are more gold-efficient.".to_string(),
-                "Tower build cost increases each wave, so building early is cheaper.".to_string(),
-                "Watch the wave_status in observe_next to know when the next wave starts and how many mobs it will have.".to_string(),
-            ],
-        };
-
-        Ok(serde_json::to_string(&rules).unwrap())
+        Ok(serde_json::to_string(&api::rules()).unwrap())
     }
 
     /// Terminate a match.
@@ -322,10 +139,10 @@ impl TdMcpServer {
         &self,
         Parameters(params): Parameters<TerminateMatchParams>,
     ) -> Result<String, String> {
-        self.game_server
+        self.api
             .terminate_match(params.match_id)
             .await
-            .map_err(|e| format!("Failed to terminate match: {}", e))?;
+            .map_err(|e| e.to_string())?;
 
         Ok("Match terminated".to_string())
     }
@@ -336,17 +153,7 @@ impl TdMcpServer {
         &self,
         Parameters(params): Parameters<JoinMatchParams>,
     ) -> Result<String, String> {
-        let (session, player_id) = self
-            .game_server
-            .join_match(params.match_id)
-            .await
-            .map_err(|e| format!("Failed to join match: {}", e))?;
-
-        Ok(serde_json::to_string(&JoinMatchResult {
-            session_token: session.0,
-            player_id,
-        })
-        .unwrap())
+        to_json(self.api.join_match(params.match_id).await)
     }
 
     /// Leave a match.
@@ -355,10 +162,10 @@ impl TdMcpServer {
         &self,
         Parameters(params): Parameters<LeaveMatchParams>,
     ) -> Result<String, String> {
-        self.game_server
-            .leave_match(params.match_id, SessionToken(params.session_token))
+        self.api
+            .leave_match(params.match_id, params.session_token)
             .await
-            .map_err(|e| format!("Failed to leave match: {}", e))?;
+            .map_err(|e| e.to_string())?;
 
         Ok("Left match".to_string())
     }
@@ -369,118 +176,34 @@ impl TdMcpServer {
         &self,
         Parameters(params): Parameters<PlaceTowerParams>,
     ) -> Result<String, String> {
-        // Pre-validate using current game state
-        let obs = self
-            .game_server
-            .observe(params.match_id, SessionToken(params.session_token))
-            .await
-            .map_err(|e| format!("Failed to validate: {:?}", e))?;
-
-        if params.x >= obs.map_width || params.y >= obs.map_height {
-            return Err(format!(
-                "Cannot place tower: ({},{}) is out of bounds (map is {}x{})",
-                params.x, params.y, obs.map_width, obs.map_height
-            ));
-        }
-        let idx = params.y as usize * obs.map_width as usize + params.x as usize;
-        if !obs.walkable.get(idx).copied().unwrap_or(false) {
-            return Err(format!(
-                "Cannot place tower: ({},{}) is non-walkable terrain",
-                params.x, params.y
-            ));
-        }
-        if obs.towers.iter().any(|t| t.x == params.x && t.y == params.y)
-            || obs
-                .build_queue
-                .iter()
-                .any(|b| b.x == params.x && b.y == params.y)
-        {
-            return Err(format!(
-                "Cannot place tower: ({},{}) is already occupied",
-                params.x, params.y
-            ));
-        }
-        if obs.gold < obs.tower_cost {
-            return Err(format!(
-                "Cannot place tower: insufficient gold (need {}, have {})",
-                obs.tower_cost, obs.gold
-            ));
-        }
-
-        let action = TdAction::PlaceTower {
-            x: params.x,
-            y: params.y,
-            kind: observe::string_to_kind(&params.tower_type),
-        };
-
-        let (action_id, scheduled_tick) = self
-            .game_server
-            .submit_action(
-                params.match_id,
-                SessionToken(params.session_token),
-                action,
-                params.intended_tick,
-            )
-            .await
-            .map_err(|e| format!("Failed to place tower: {}", e))?;
-
-        Ok(serde_json::to_string(&ActionResult {
-            action_id,
-            scheduled_tick,
-        })
-        .unwrap())
+        to_json(self.api.place_tower(params).await)
     }
 
-    /// Upgrade a tower to increase its damage.
-    #[tool(description = "Upgrade a tower to increase its damage. Cost: 20 * 1.20^(current_level+1). The tower_id is from the observe response.")]
+    /// Upgrade one branch of a tower.
+    #[tool(description = "Upgrade one branch of a tower by a level: 'damage' (default), 'range', 'fire_rate', 'hp' (also heals the HP gained) or 'slow' (hits slow mobs). Branches have max levels and a tower has a max_upgrade_level in total; the tower's upgrades in the observe response list its branches and levels, and damage, range, fire_period_ticks and slow show the result. Cost: 20 * 1.20^(upgrade_level+1). The tower_id is from the observe response.")]
     async fn upgrade_tower(
         &self,
         Parameters(params): Parameters<UpgradeTowerParams>,
     ) -> Result<String, String> {
-        let id = observe::string_to_tower_id(&params.tower_id)?;
-
-        // Pre-validate using current game state
-        let obs = self
-            .game_server
-            .observe(params.match_id, SessionToken(params.session_token))
-            .await
-            .map_err(|e| format!("Failed to validate: {:?}", e))?;
-
-        let tower = obs
-            .towers
-            .iter()
-            .find(|t| t.id == params.tower_id)
-            .ok_or_else(|| {
-                format!(
-                    "Cannot upgrade tower: tower '{}' not found",
-                    params.tower_id
-                )
-            })?;
-        if obs.gold < tower.upgrade_cost {
-            return Err(format!(
-                "Cannot upgrade tower: insufficient gold (need {}, have {})",
-                tower.upgrade_cost, obs.gold
-            ));
-        }
-
-        let action = TdAction::UpgradeTower { tower_id: id };
+        to_json(self.api.upgrade_tower(params).await)
+    }
 
-        let (action_id, scheduled_tick) = self
-            .game_server
-            .submit_action(
-                params.match_id,
-                SessionToken(params.session_token),
-                action,
-                params.intended_tick,
-            )
-            .await
-            .map_err(|e| format!("Failed to upgrade tower: {}", e))?;
+    /// Restore a damaged tower to full HP.
+    #[tool(description = "Repair a damaged tower to full HP. Cost: ceil(base_cost * missing_hp / (2 * max_hp)), shown as repair_cost in the observe response. Mobs that reach a tower damage it, so towers that block the path need repairs to hold. The tower_id is from the observe response.")]
+    async fn repair_tower(
+        &self,
+        Parameters(params): Parameters<RepairTowerParams>,
+    ) -> Result<String, String> {
+        to_json(self.api.repair_tower(params).await)
+    }
 
-        Ok(serde_json::to_string(&ActionResult {
-            action_id,
-            scheduled_tick,
-        })
-        .unwrap())
+    /// Choose which mob in range a tower shoots.
+    #[tool(description = "Set which mob in range a tower shoots: 'first' (closest to the goal along the path), 'last' (furthest from the goal), 'strongest' (most HP), 'weakest' (least HP) or 'closest' (to the tower, the default). Free. The tower_id is from the observe response.")]
+    async fn set_targeting(
+        &self,
+        Parameters(params): Parameters<SetTargetingParams>,
+    ) -> Result<String, String> {
+        to_json(self.api.set_targeting(params).await)
     }
 
     /// Get all buildable cells on the map (static — does not change during a match).
@@ -489,46 +212,64 @@ impl TdMcpServer {
         &self,
         Parameters(params): Parameters<GetBuildableCellsParams>,
     ) -> Result<String, String> {
-        let obs = self
-            .game_server
-            .observe(params.match_id, SessionToken(params.session_token))
-            .await
-            .map_err(|e| format!("Failed to observe: {:?}", e))?;
-
-        let mut buildable_cells = Vec::new();
-        for y in 0..obs.map_height {
-            for x in 0..obs.map_width {
-                let idx = y as usize * obs.map_width as usize + x as usize;
-                if obs.walkable.get(idx).copied().unwrap_or(false) {
-                    buildable_cells.push(Position { x, y });
-                }
-            }
-        }
-
-        Ok(serde_json::to_string(&GetBuildableCellsResult {
-            map_width: obs.map_width,
-            map_height: obs.map_height,
-            buildable_cells,
-        })
-        .unwrap())
+        to_json(
+            self.api
+                .get_buildable_cells(params.match_id, params.session_token)
+                .await,
+        )
     }
 
     /// Get the current mob path from spawn to goal.
-    #[tool(description = "Get the current shortest path mobs will follow from spawn to goal, accounting for placed towers. The path changes when towers are built or destroyed. Returns path_exists=false if the path is fully blocked (mobs will attack towers).")]
+    #[tool(description = "Get the current shortest path mobs will follow from spawn to goal, accounting for placed towers. The path changes when towers are built or destroyed. On maps with several spawns, `routes` has one route per spawn. Also returns the path length in cells, the next-best alternative routes, and chokepoints: path cells whose blocking would cut a spawn off from every goal. Returns path_exists=false if a path is fully blocked (mobs will attack towers).")]
     async fn get_current_path(
         &self,
         Parameters(params): Parameters<GetCurrentPathParams>,
     ) -> Result<String, String> {
-        let obs = self
-            .game_server
-            .observe(params.match_id, SessionToken(params.session_token))
-            .await
-            .map_err(|e| format!("Failed to observe: {:?}", e))?;
+        to_json(
+            self.api
+                .get_current_path(params.match_id, params.session_token)
+                .await,
+        )
+    }
 
-        let path = compute_mob_path(&obs);
-        let path_exists = !path.is_empty();
+    /// Get a concise summary of the match state.
+    #[tool(description = "Get a concise summary of the match: seconds until the next wave, how many towers you can afford, leaks remaining before you lose, towers covering the mob path, the mobs closest to the goal, and a threat estimate (low/medium/high) for the next wave. Complements observe_next.")]
+    async fn get_summary(
+        &self,
+        Parameters(params): Parameters<GetSummaryParams>,
+    ) -> Result<String, String> {
+        to_json(
+            self.api
+                .get_summary(params.match_id, params.session_token)
+                .await,
+        )
+    }
+
+    /// Analyze candidate tower placements.
+    #[tool(description = "Rank tower placements: for each buildable cell (or the given candidates), how many cells of the current mob path are within tower range, and how the spawn-to-goal path length would change if a tower were built there. Flags cells where a tower would fully block the path (mobs then attack towers). Returns the best `limit` cells, most coverage first.")]
+    async fn analyze_placements(
+        &self,
+        Parameters(params): Parameters<AnalyzePlacementsParams>,
+    ) -> Result<String, String> {
+        to_json(self.api.analyze_placements(params).await)
+    }
 
-        Ok(serde_json::to_string(&GetCurrentPathResult { path_exists, path }).unwrap())
+    /// Preview the outcome of a plan.
+    #[tool(description = "Simulate a plan without committing it: applies hypothetical actions (list of {type: PlaceTower, x, y, tower_type}, {type: UpgradeTower, tower_id, branch}, {type: RepairTower, tower_id} or {type: SetTargeting, tower_id, targeting}) to a copy of the match on the next tick and fast-forwards through the end of the next wave, or a number of ticks. Returns predicted kills, leaks, gold and towers built/lost, plus any actions the game would reject. Pass an empty actions list for a baseline.")]
+    async fn what_if(
+        &self,
+        Parameters(params): Parameters<WhatIfParams>,
+    ) -> Result<String, String> {
+        to_json(self.api.what_if(params).await)
+    }
+
+    /// Render the current map as ASCII art.
+    #[tool(description = "Render the current map as ASCII art with coordinate rulers and a legend: # wall, . open, * mob path, S spawn, G goal, 0-9 tower by upgrade level, b pending build, m/M mobs. Pass x, y, width and/or height to crop to a region. Easier to reason about than the walkable grid.")]
+    async fn render_map(
+        &self,
+        Parameters(params): Parameters<RenderMapParams>,
+    ) -> Result<String, String> {
+        self.api.render_map(params).await.map_err(|e| e.to_string())
     }
 
     /// Wait for the next game state update (long-poll).
@@ -537,47 +278,20 @@ impl TdMcpServer {
         &self,
         Parameters(params): Parameters<ObserveNextParams>,
     ) -> Result<String, String> {
-        let (mut obs, timed_out) = self
-            .game_server
-            .observe_next(
-                params.match_id,
-                SessionToken(params.session_token),
-                params.after_tick,
-                params.max_wait_ms,
-            )
-            .await
-            .map_err(|e| match e {
-                ObserveNextError::NotFound => "Match not found".to_string(),
-                ObserveNextError::InvalidSession => "Invalid session".to_string(),
-                ObserveNextError::AlreadyWaiting => {
-                    "Already waiting for observation. Only one observe_next allowed at a time."
-                        .to_string()
-                }
-                ObserveNextError::ObservationNotReady => {
-                    "Observation not ready yet".to_string()
-                }
-            })?;
-
-        // Strip walkable array — map layout never changes, agents should use get_buildable_cells instead.
-        obs.walkable.clear();
-
-        let result = ObserveNextResult {
-            timed_out,
-            observation: obs,
-        };
-
-        Ok(serde_json::to_string(&result).unwrap())
+        to_json(self.api.observe_next(params).await)
     }
-
 }
 
 impl ServerHandler for TdMcpServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
-                "Tower Defense MCP Server. Create matches, join as players, place towers, upgrade them, and defend against waves of mobs!".into()
+                "Tower Defense MCP Server. Create matches, join as players, place towers, upgrade them, and defend against waves of mobs! Read td://rules for the rules, subscribe to td://match/{match_id}/observation for decision-tick updates, and use the play_match or review_position prompts to get started.".into()
             ),
             capabilities: ServerCapabilities::builder()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
                 .enable_tools()
                 .build(),
             ..Default::default()
@@ -598,19 +312,122 @@ impl ServerHandler for TdMcpServer {
         }))
     }
 
-    fn call_tool(
+    async fn call_tool(
         &self,
         request: rmcp::model::CallToolRequestParams,
         context: rmcp::service::RequestContext<rmcp::RoleServer>,
-    ) -> impl std::future::Future<Output = Result<CallToolResult, rmcp::ErrorData>> + Send + '_
-    {
-        async move {
-            let tool_context = rmcp::handler::server::tool::ToolCallContext::new(
-                self,
-                request,
-                context,
-            );
-            self.tool_router.call(tool_context).await
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let tool_context =
+            rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        self.tool_router.call(tool_context).await
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(ListResourcesResult {
+            resources: resources::list(&self.api).await,
+            ..Default::default()
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult {
+            resource_templates: resources::templates(),
+            ..Default::default()
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let contents = resources::read(&self.api, &request.uri).await?;
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let Some(TdResource::Observation { match_id }) = TdResource::parse(&request.uri) else {
+            return Err(McpError::invalid_params(
+                format!("Resource does not support subscriptions: {}", request.uri),
+                None,
+            ));
+        };
+
+        let game_server = self.api.game_server().clone();
+        let session = game_server
+            .spectate_match(match_id)
+            .await
+            .map_err(|e| McpError::resource_not_found(format!("Failed to subscribe: {}", e), None))?;
+        let subscription = match game_server.subscribe(match_id, session, true).await {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                let _ = game_server.leave_match(match_id, session).await;
+                return Err(McpError::resource_not_found(
+                    format!("Failed to subscribe: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        // Replacing an existing subscription cancels it
+        self.resource_subscriptions
+            .lock()
+            .unwrap()
+            .insert(request.uri.clone(), cancel_tx);
+        tokio::spawn(notify_decision_ticks(
+            game_server,
+            match_id,
+            session,
+            subscription,
+            request.uri,
+            context.peer,
+            cancel_rx,
+        ));
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if let Some(cancel) = self.resource_subscriptions.lock().unwrap().remove(&request.uri) {
+            let _ = cancel.send(());
         }
+        Ok(())
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult {
+            prompts: prompts::list(),
+            ..Default::default()
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        prompts::get(&self.api, &request.name, request.arguments).await
     }
 }
@@ -1,3 +1,4 @@
+use crate::config::Difficulty;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -7,21 +8,166 @@ pub use td_types::{
     PendingBuildInfo, Position, TdObservation, TowerInfo, WaveStatus,
 };
 
-/// Parameters for creating a match.
+/// Parameters for creating a match. Everything but `seed` is optional; settings left
+/// out come from `difficulty`, then from the defaults.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateMatchParams {
     /// Random seed for deterministic gameplay.
     pub seed: u64,
-    /// Number of players required to start the match.
+    /// Number of players required to start the match, 1-8 (default 1).
+    #[serde(default = "default_required_players")]
     pub required_players: u8,
-    /// Number of waves.
+    /// Number of waves, 1-100 (default 10).
+    #[serde(default = "default_waves")]
     pub waves: u8,
+    /// Name of a map from the server's map directory (see list_maps). Omit to generate
+    /// a map from the seed.
+    #[serde(default)]
+    pub map: Option<String>,
+    /// Preset for mob HP, wave size, gold, max_leaks and the pause between waves
+    /// (default normal).
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// Leaks the players can absorb; one more loses the match, 0-1000.
+    #[serde(default)]
+    pub max_leaks: Option<u16>,
+    /// Emit a TowerFired and a MobDamaged event for every shot (default true).
+    #[serde(default)]
+    pub combat_events: Option<bool>,
+    /// Simulation ticks per second, 1-120 (default 20).
+    #[serde(default)]
+    pub tick_hz: Option<u32>,
+    /// Seconds before the first wave and between waves, 0-600.
+    #[serde(default)]
+    pub inter_wave_pause_secs: Option<f32>,
+    /// Seconds between two mobs of a wave, 0.05-60 (default 0.5).
+    #[serde(default)]
+    pub spawn_interval_secs: Option<f32>,
+    /// Seconds a tower takes to build, 0-600 (default 2).
+    #[serde(default)]
+    pub build_time_secs: Option<f32>,
+    /// Mob speed in cells per second, 0.1-10 (default 2).
+    #[serde(default)]
+    pub mob_speed: Option<f32>,
+    /// Maze cells per side of a generated map, 3-170 (default 10); the map is 3 times
+    /// as wide. Not allowed with `map`.
+    #[serde(default)]
+    pub maze_size: Option<i32>,
+    /// Base half-width of generated paths in cells, 0-10 (default 3). Not allowed with
+    /// `map`.
+    #[serde(default)]
+    pub dilation_base_radius: Option<f64>,
+    /// How much generated path width varies, 0-10 (default 2). Not allowed with `map`.
+    #[serde(default)]
+    pub dilation_amplitude: Option<f64>,
+    /// Overrides for the Basic tower.
+    #[serde(default)]
+    pub basic_tower: Option<TowerSpecParams>,
+}
+
+fn default_required_players() -> u8 {
+    1
+}
+
+fn default_waves() -> u8 {
+    10
+}
+
+impl Default for CreateMatchParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            required_players: default_required_players(),
+            waves: default_waves(),
+            map: None,
+            difficulty: None,
+            max_leaks: None,
+            combat_events: None,
+            tick_hz: None,
+            inter_wave_pause_secs: None,
+            spawn_interval_secs: None,
+            build_time_secs: None,
+            mob_speed: None,
+            maze_size: None,
+            dilation_base_radius: None,
+            dilation_amplitude: None,
+            basic_tower: None,
+        }
+    }
+}
+
+/// Tower settings for [`CreateMatchParams`]; omitted fields keep their defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TowerSpecParams {
+    /// Build cost before wave scaling, 1-10000 (default 15).
+    #[serde(default)]
+    pub cost: Option<u32>,
+    /// 1-100000 (default 100).
+    #[serde(default)]
+    pub hp: Option<i32>,
+    /// Range in cells, 0.5-50 (default 4).
+    #[serde(default)]
+    pub range: Option<f32>,
+    /// Damage per shot before upgrades, 1-100000 (default 5).
+    #[serde(default)]
+    pub damage: Option<i32>,
+    /// Seconds between shots, 0.05-60 (default 1).
+    #[serde(default)]
+    pub fire_period_secs: Option<f32>,
+    /// HP regained per second between waves, 0-100000 (default 0).
+    #[serde(default)]
+    pub regen: Option<i32>,
+}
+
+/// Result of listing the named maps.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListMapsResult {
+    pub maps: Vec<String>,
 }
 
 /// Result of creating a match.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateMatchResult {
     pub match_id: u64,
+    /// Fingerprint of the match's map, as reported by preview_map.
+    pub map_fingerprint: String,
+}
+
+/// Parameters for previewing a map: the map settings of [`CreateMatchParams`].
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PreviewMapParams {
+    /// Seed of the generated map.
+    pub seed: u64,
+    /// Name of a map from the server's map directory (see list_maps) instead.
+    #[serde(default)]
+    pub map: Option<String>,
+    /// Maze cells per side, 3-170 (default 10); the map is 3 times as wide.
+    #[serde(default)]
+    pub maze_size: Option<i32>,
+    /// Base half-width of generated paths in cells, 0-10 (default 3).
+    #[serde(default)]
+    pub dilation_base_radius: Option<f64>,
+    /// How much generated path width varies, 0-10 (default 2).
+    #[serde(default)]
+    pub dilation_amplitude: Option<f64>,
+}
+
+/// A map as a match created with the same settings would start.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PreviewMapResult {
+    pub map_width: u16,
+    pub map_height: u16,
+    pub spawns: Vec<Position>,
+    pub goals: Vec<Position>,
+    /// Row-major walkable flags; index is `y * map_width + x`.
+    pub walkable: Vec<bool>,
+    /// Walking distance from each spawn to the nearest goal in cells (diagonal steps
+    /// count 1.4), in the order of `spawns`. null for a spawn that can't reach a goal.
+    pub path_lengths: Vec<Option<f32>>,
+    /// Stable hash of the layout: equal fingerprints mean the same map.
+    pub fingerprint: String,
+    /// The map as ASCII art, as render_map draws it.
+    pub ascii: String,
 }
 
 /// Parameters for joining a match.
@@ -79,6 +225,34 @@ pub struct UpgradeTowerParams {
     pub intended_tick: u64,
     /// ID of the tower to upgrade (from observe response).
     pub tower_id: String,
+    /// Branch to upgrade: damage (default), range, fire_rate, hp or slow. The tower's
+    /// `upgrades` list the branches it can take.
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+/// Parameters for repairing a tower.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RepairTowerParams {
+    pub match_id: u64,
+    pub session_token: u64,
+    /// The tick at which this action should be executed. Use 0 to execute immediately.
+    pub intended_tick: u64,
+    /// ID of the tower to repair (from observe response).
+    pub tower_id: String,
+}
+
+/// Parameters for setting a tower's targeting.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SetTargetingParams {
+    pub match_id: u64,
+    pub session_token: u64,
+    /// The tick at which this action should be executed. Use 0 to execute immediately.
+    pub intended_tick: u64,
+    /// ID of the tower (from observe response).
+    pub tower_id: String,
+    /// first, last, strongest, weakest or closest.
+    pub targeting: String,
 }
 
 /// Result of submitting an action.
@@ -89,6 +263,30 @@ pub struct ActionResult {
     pub scheduled_tick: u64,
 }
 
+/// A place, upgrade, repair or targeting action, tagged by `type`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum ActionParams {
+    PlaceTower {
+        x: u16,
+        y: u16,
+        #[serde(default = "default_tower_type")]
+        tower_type: String,
+    },
+    UpgradeTower {
+        tower_id: String,
+        #[serde(default)]
+        branch: Option<String>,
+    },
+    RepairTower {
+        tower_id: String,
+    },
+    SetTargeting {
+        tower_id: String,
+        targeting: String,
+    },
+}
+
 /// Parameters for observe_next (long-poll observation).
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ObserveNextParams {
@@ -120,6 +318,17 @@ pub struct GetBuildableCellsResult {
     pub buildable_cells: Vec<Position>,
 }
 
+/// Static layout of a match's map.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MatchMapResult {
+    pub map_width: u16,
+    pub map_height: u16,
+    pub spawns: Vec<Position>,
+    pub goals: Vec<Position>,
+    /// Row-major walkable flags; index is `y * map_width + x`.
+    pub walkable: Vec<bool>,
+}
+
 /// Parameters for getting the current mob path.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GetCurrentPathParams {
@@ -130,11 +339,214 @@ pub struct GetCurrentPathParams {
 /// Result of getting the current mob path.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GetCurrentPathResult {
-    /// Whether a path from spawn to goal currently exists.
+    /// Whether every spawn currently has a path to a goal.
     pub path_exists: bool,
-    /// The sequence of cells mobs will traverse from spawn to goal.
+    /// The sequence of cells mobs will traverse from the first spawn to a goal.
     /// Empty if path is blocked (mobs will attack towers to create a path).
     pub path: Vec<Position>,
+    /// Walking distance along `path` in cells (diagonal steps count 1.4); 0 if blocked.
+    pub path_length: f32,
+    /// The route from each spawn, in the order of the map's spawns; null if that spawn
+    /// is cut off from every goal.
+    pub routes: Vec<Option<RouteInfo>>,
+    /// Routes mobs would switch to if towers blocked the current ones: per spawn in
+    /// order, shortest first.
+    pub alternative_routes: Vec<RouteInfo>,
+    /// Cells every route passes through: a tower on one blocks the path completely.
+    pub chokepoints: Vec<Position>,
+}
+
+/// A spawn-to-goal route.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RouteInfo {
+    pub path: Vec<Position>,
+    /// Walking distance in cells (diagonal steps count 1.4).
+    pub length: f32,
+}
+
+/// Parameters for rendering the map as text.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RenderMapParams {
+    pub match_id: u64,
+    pub session_token: u64,
+    /// Mark the current mob path with '*' (default: true).
+    #[serde(default = "default_true")]
+    pub show_path: bool,
+    /// Add coordinate rulers above and left of the grid (default: true).
+    #[serde(default = "default_true")]
+    pub rulers: bool,
+    /// Left edge of the region to render (default: 0).
+    #[serde(default)]
+    pub x: Option<u16>,
+    /// Top edge of the region to render (default: 0).
+    #[serde(default)]
+    pub y: Option<u16>,
+    /// Width of the region to render (default: to the right edge of the map).
+    #[serde(default)]
+    pub width: Option<u16>,
+    /// Height of the region to render (default: to the bottom edge of the map).
+    #[serde(default)]
+    pub height: Option<u16>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Parameters for getting a match summary.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetSummaryParams {
+    pub match_id: u64,
+    pub session_token: u64,
+}
+
+/// Concise, precomputed view of the current match state.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SummaryResult {
+    pub tick: u64,
+    /// Last wave started (0 before the first wave).
+    pub current_wave: u8,
+    pub waves_total: u8,
+    /// Whether a wave is currently spawning.
+    pub in_wave: bool,
+    /// Seconds until the next wave starts. Absent during a wave and after the last one.
+    pub seconds_until_next_wave: Option<f32>,
+    pub gold: u32,
+    pub tower_cost: u32,
+    /// How many towers the current gold buys at the current cost.
+    pub affordable_towers: u32,
+    pub leaks: u16,
+    pub max_leaks: u16,
+    /// Leaks that can still be absorbed; one more than this loses the match.
+    pub leaks_remaining: u16,
+    pub tower_count: u32,
+    /// Cells on the current mob paths from all spawns; 0 if every path is blocked.
+    pub path_length: u32,
+    /// Towers that can shoot at the mob path, most path cells in range first.
+    pub towers_near_path: Vec<TowerCoverage>,
+    /// Up to 5 mobs nearest the goal, closest first.
+    pub mobs_closest_to_goal: Vec<MobProgress>,
+    /// Estimate for the next wave. Absent after the last wave has started.
+    pub next_wave: Option<WaveThreat>,
+}
+
+/// A tower and how much of the mob path it covers.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TowerCoverage {
+    pub tower_id: String,
+    pub x: u16,
+    pub y: u16,
+    pub upgrade_level: u8,
+    pub damage: i32,
+    /// Cells of the current mob path within the tower's range.
+    pub path_cells_in_range: u32,
+}
+
+/// A mob and its distance to the goal.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MobProgress {
+    pub mob_id: String,
+    pub x: f32,
+    pub y: f32,
+    pub hp: i32,
+    pub max_hp: i32,
+    /// Walking distance to the goal in cells. Absent if the mob is cut off from the goal.
+    pub cells_to_goal: Option<f32>,
+}
+
+/// Threat estimate for a wave against the current towers and path.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WaveThreat {
+    pub wave: u8,
+    pub mob_count: u16,
+    pub mob_hp: i32,
+    pub total_hp: u64,
+    /// Damage the towers deal to a single mob walking the whole path alone.
+    pub damage_per_mob: f32,
+    /// Damage the towers deal while the whole wave walks past, if they always have a target.
+    pub wave_damage_capacity: f32,
+    /// "high": a lone mob survives the path. "medium": single mobs die but the wave
+    /// outlasts the towers. "low": the towers can kill the whole wave.
+    pub threat: String,
+}
+
+/// Parameters for a what-if simulation.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WhatIfParams {
+    pub match_id: u64,
+    pub session_token: u64,
+    /// Hypothetical actions, applied in order on the next tick.
+    #[serde(default)]
+    pub actions: Vec<ActionParams>,
+    /// Ticks to simulate. Omit to simulate through the end of the next wave (or the
+    /// current one, during a wave). Capped at 10 minutes of game time.
+    #[serde(default)]
+    pub ticks: Option<u64>,
+}
+
+/// Predicted outcome of a what-if simulation.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WhatIfResult {
+    pub start_tick: u64,
+    pub end_tick: u64,
+    /// Why the simulation stopped: "ticks", "wave_ended", "match_won", "match_lost"
+    /// or "time_limit".
+    pub stopped: String,
+    /// Actions the simulation rejected, with reasons. The rest were applied.
+    pub rejected_actions: Vec<String>,
+    pub kills: u32,
+    pub leaks: u16,
+    /// Leaks that can still be absorbed at the end; one more than this loses the match.
+    pub leaks_remaining: u16,
+    pub gold_start: u32,
+    pub gold_end: u32,
+    pub towers_built: u32,
+    pub towers_lost: u32,
+    /// Last wave started at the end of the simulation.
+    pub current_wave: u8,
+}
+
+/// Parameters for analyzing tower placements.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AnalyzePlacementsParams {
+    pub match_id: u64,
+    pub session_token: u64,
+    /// Cells to analyze. Omit to analyze every buildable cell.
+    #[serde(default)]
+    pub candidates: Option<Vec<Position>>,
+    /// Max placements to return, best coverage first (default: 20).
+    #[serde(default = "default_placement_limit")]
+    pub limit: usize,
+}
+
+fn default_placement_limit() -> usize {
+    20
+}
+
+/// Result of analyzing tower placements.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AnalyzePlacementsResult {
+    pub tower_range: f32,
+    /// Current walking distance from spawn to goal in cells (diagonal steps count 1.4),
+    /// averaged over spawns. Absent if any spawn is cut off.
+    pub path_distance: Option<f32>,
+    /// Candidates that are buildable, before applying `limit`.
+    pub buildable_candidates: u32,
+    /// Most path cells in range first; among equals, longest resulting path first.
+    pub placements: Vec<PlacementInfo>,
+}
+
+/// Effect of placing a tower on one cell.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PlacementInfo {
+    pub x: u16,
+    pub y: u16,
+    /// Cells of the current mob paths within tower range.
+    pub path_cells_in_range: u32,
+    /// Change in `path_distance`, in cells. Absent if the tower would block the path.
+    pub path_distance_change: Option<f32>,
+    /// The tower would cut a spawn off from every goal, so mobs attack towers instead.
+    pub blocks_path: bool,
 }
 
 /// Game rules and mechanics explanation.
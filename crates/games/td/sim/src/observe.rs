@@ -1,9 +1,12 @@
-use crate::config::TowerKind;
-use crate::world::{TdState, TowerId, WavePhase};
+use crate::config::{TdConfig, TowerKind, UpgradeBranch};
+use crate::events::TdEvent;
+use crate::pathing::Route;
+use crate::world::{MobId, Targeting, TdState, Tower, TowerId, WavePhase};
 use sim_core::Tick;
 use slotmap::Key;
 use td_types::{
-    MobInfo, PendingBuildInfo, Position, TdObservation, TowerInfo, WaveStatus,
+    MobInfo, PendingBuildInfo, Position, TdEventInfo, TdObservation, TowerInfo, UpgradeBranchInfo,
+    WaveStatus,
 };
 
 pub fn kind_to_string(kind: TowerKind) -> String {
@@ -12,22 +15,192 @@ pub fn kind_to_string(kind: TowerKind) -> String {
     }
 }
 
-pub fn string_to_kind(s: &str) -> TowerKind {
-    match s {
-        _ => TowerKind::Basic,
+pub fn string_to_kind(_s: &str) -> TowerKind {
+    TowerKind::Basic
+}
+
+pub fn targeting_to_string(targeting: Targeting) -> String {
+    match targeting {
+        Targeting::First => "first",
+        Targeting::Last => "last",
+        Targeting::Strongest => "strongest",
+        Targeting::Weakest => "weakest",
+        Targeting::Closest => "closest",
+    }
+    .to_string()
+}
+
+pub fn string_to_targeting(s: &str) -> Result<Targeting, String> {
+    Targeting::ALL
+        .into_iter()
+        .find(|&t| targeting_to_string(t) == s)
+        .ok_or_else(|| {
+            format!(
+                "Invalid targeting: {} (use first, last, strongest, weakest or closest)",
+                s
+            )
+        })
+}
+
+pub fn branch_to_string(branch: UpgradeBranch) -> String {
+    match branch {
+        UpgradeBranch::Damage => "damage",
+        UpgradeBranch::Range => "range",
+        UpgradeBranch::FireRate => "fire_rate",
+        UpgradeBranch::Hp => "hp",
+        UpgradeBranch::Slow => "slow",
     }
+    .to_string()
+}
+
+pub fn string_to_branch(s: &str) -> Result<UpgradeBranch, String> {
+    UpgradeBranch::ALL
+        .into_iter()
+        .find(|&b| branch_to_string(b) == s)
+        .ok_or_else(|| {
+            format!(
+                "Invalid upgrade branch: {} (use damage, range, fire_rate, hp or slow)",
+                s
+            )
+        })
 }
 
 pub fn tower_id_to_string(id: TowerId) -> String {
     id.data().as_ffi().to_string()
 }
 
+pub fn mob_id_to_string(id: MobId) -> String {
+    id.data().as_ffi().to_string()
+}
+
 pub fn string_to_tower_id(s: &str) -> Result<TowerId, String> {
     let ffi: u64 = s.parse().map_err(|_| format!("Invalid tower_id: {}", s))?;
     let key_data = slotmap::KeyData::from_ffi(ffi);
     Ok(TowerId::from(key_data))
 }
 
+pub fn event_to_info(event: &TdEvent) -> TdEventInfo {
+    match event {
+        TdEvent::TowerPlaced { id, x, y, kind } => TdEventInfo::TowerPlaced {
+            tower_id: tower_id_to_string(*id),
+            x: *x,
+            y: *y,
+            tower_type: kind_to_string(*kind),
+        },
+        TdEvent::TowerDestroyed { id, x, y } => TdEventInfo::TowerDestroyed {
+            tower_id: tower_id_to_string(*id),
+            x: *x,
+            y: *y,
+        },
+        TdEvent::MobKilled { id, x, y } => TdEventInfo::MobKilled {
+            mob_id: mob_id_to_string(*id),
+            x: *x,
+            y: *y,
+        },
+        TdEvent::MobLeaked { id } => TdEventInfo::MobLeaked {
+            mob_id: mob_id_to_string(*id),
+        },
+        TdEvent::WaveStarted { wave } => TdEventInfo::WaveStarted { wave: *wave },
+        TdEvent::WaveEnded { wave } => TdEventInfo::WaveEnded { wave: *wave },
+        TdEvent::BuildQueued { x, y, kind } => TdEventInfo::BuildQueued {
+            x: *x,
+            y: *y,
+            tower_type: kind_to_string(*kind),
+        },
+        TdEvent::InsufficientGold { cost, have } => TdEventInfo::InsufficientGold {
+            cost: *cost,
+            have: *have,
+        },
+        TdEvent::TowerUpgraded {
+            id,
+            branch,
+            new_level,
+        } => TdEventInfo::TowerUpgraded {
+            tower_id: tower_id_to_string(*id),
+            branch: branch_to_string(*branch),
+            new_level: *new_level,
+        },
+        TdEvent::UpgradeRejected { id, branch, reason } => TdEventInfo::UpgradeRejected {
+            tower_id: tower_id_to_string(*id),
+            branch: branch_to_string(*branch),
+            reason: reason.clone(),
+        },
+        TdEvent::TowerRepaired { id, hp, cost } => TdEventInfo::TowerRepaired {
+            tower_id: tower_id_to_string(*id),
+            hp: *hp,
+            cost: *cost,
+        },
+        TdEvent::RepairRejected { id, reason } => TdEventInfo::RepairRejected {
+            tower_id: tower_id_to_string(*id),
+            reason: reason.clone(),
+        },
+        TdEvent::TargetingChanged { id, targeting } => TdEventInfo::TargetingChanged {
+            tower_id: tower_id_to_string(*id),
+            targeting: targeting_to_string(*targeting),
+        },
+        TdEvent::TargetingRejected {
+            id,
+            targeting,
+            reason,
+        } => TdEventInfo::TargetingRejected {
+            tower_id: tower_id_to_string(*id),
+            targeting: targeting_to_string(*targeting),
+            reason: reason.clone(),
+        },
+        TdEvent::TowerFired {
+            tower_id,
+            mob_id,
+            damage,
+        } => TdEventInfo::TowerFired {
+            tower_id: tower_id_to_string(*tower_id),
+            mob_id: mob_id_to_string(*mob_id),
+            damage: *damage,
+        },
+        TdEvent::MobDamaged { id, damage, hp } => TdEventInfo::MobDamaged {
+            mob_id: mob_id_to_string(*id),
+            damage: *damage,
+            hp: *hp,
+        },
+        TdEvent::BuildRejected { x, y, reason } => TdEventInfo::BuildRejected {
+            x: *x,
+            y: *y,
+            reason: reason.clone(),
+        },
+    }
+}
+
+fn tower_info(config: &TdConfig, id: TowerId, t: &Tower) -> TowerInfo {
+    let stats = config.tower_stats(t.kind, &t.branch_levels);
+    let tree = &config.spec(t.kind).upgrades;
+    TowerInfo {
+        id: tower_id_to_string(id),
+        x: t.x,
+        y: t.y,
+        hp: t.hp,
+        max_hp: t.max_hp,
+        tower_type: kind_to_string(t.kind),
+        player_id: t.player_id,
+        upgrade_level: t.upgrade_level,
+        max_upgrade_level: tree.max_level,
+        upgrades: tree
+            .branches
+            .iter()
+            .map(|b| UpgradeBranchInfo {
+                branch: branch_to_string(b.branch),
+                level: t.branch_levels[b.branch as usize],
+                max_level: b.max_level,
+            })
+            .collect(),
+        damage: stats.damage,
+        range: stats.range,
+        fire_period_ticks: config.duration_to_ticks(stats.fire_period),
+        slow: stats.slow,
+        upgrade_cost: config.upgrade_cost(t.upgrade_level),
+        repair_cost: config.repair_cost(t.kind, t.max_hp - t.hp, t.max_hp),
+        targeting: targeting_to_string(t.targeting),
+    }
+}
+
 pub fn build_observation(state: &TdState, tick: Tick) -> TdObservation {
     let config = &state.config;
     let player_count = config.player_count;
@@ -66,14 +239,16 @@ pub fn build_observation(state: &TdState, tick: Tick) -> TdObservation {
 
         map_width: config.width,
         map_height: config.height,
-        spawn: Position {
-            x: config.spawn.0,
-            y: config.spawn.1,
-        },
-        goal: Position {
-            x: config.goal.0,
-            y: config.goal.1,
-        },
+        spawns: config
+            .spawns
+            .iter()
+            .map(|&(x, y)| Position { x, y })
+            .collect(),
+        goals: config
+            .goals
+            .iter()
+            .map(|&(x, y)| Position { x, y })
+            .collect(),
 
         max_leaks: config.max_leaks,
         tower_cost: current_tower_cost,
@@ -95,26 +270,24 @@ pub fn build_observation(state: &TdState, tick: Tick) -> TdObservation {
             .world
             .towers
             .iter()
-            .map(|(id, t)| TowerInfo {
-                id: tower_id_to_string(id),
-                x: t.x,
-                y: t.y,
-                hp: t.hp,
-                tower_type: kind_to_string(t.kind),
-                player_id: t.player_id,
-                upgrade_level: t.upgrade_level,
-                damage: config.tower_damage(t.kind, t.upgrade_level),
-                upgrade_cost: config.upgrade_cost(t.upgrade_level),
-            })
+            .map(|(id, t)| tower_info(config, id, t))
             .collect(),
         mobs: state
             .world
             .mobs
-            .values()
-            .map(|m| MobInfo {
+            .iter()
+            .map(|(id, m)| MobInfo {
+                id: mob_id_to_string(id),
                 x: m.x,
                 y: m.y,
                 hp: m.hp,
+                max_hp: m.max_hp,
+                speed: m.speed_at(tick),
+                target: Position {
+                    x: m.target.0,
+                    y: m.target.1,
+                },
+                spawn_tick: m.spawn_tick,
             })
             .collect(),
         build_queue: state
@@ -131,3 +304,224 @@ pub fn build_observation(state: &TdState, tick: Tick) -> TdObservation {
             .collect(),
     }
 }
+
+/// Cells of `path` whose centers are within `range` of the tower at (x, y), using the
+/// same center-to-center distance as tower targeting.
+pub fn path_cells_in_range(path: &[(u16, u16)], x: u16, y: u16, range: f32) -> u32 {
+    let range_sq = range * range;
+    path.iter()
+        .filter(|&&(px, py)| {
+            let dx = px as f32 - x as f32;
+            let dy = py as f32 - y as f32;
+            dx * dx + dy * dy <= range_sq
+        })
+        .count() as u32
+}
+
+/// A rectangle of grid cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MapRegion {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Options for [`render_ascii_map`].
+#[derive(Clone, Copy, Debug)]
+pub struct AsciiMapOptions {
+    /// Mark the mob routes passed to [`render_ascii_map`].
+    pub show_path: bool,
+    /// Add x coordinates above and y coordinates left of the grid.
+    pub rulers: bool,
+    /// Only render this region, clipped to the map. `None` renders the whole map.
+    pub region: Option<MapRegion>,
+}
+
+impl Default for AsciiMapOptions {
+    fn default() -> Self {
+        Self {
+            show_path: true,
+            rulers: true,
+            region: None,
+        }
+    }
+}
+
+pub const ASCII_MAP_LEGEND: &str = "Legend: # wall, . open, * mob path, S spawn, G goal, \
+0-9 tower (upgrade level, + above 9), b pending build, m mob, M several mobs";
+
+/// Render the grid of a full observation (including `walkable`) as text: one row per
+/// line, then the path status and a legend. `routes` are the current mob routes (see
+/// [`mob_routes`](crate::pathing::mob_routes)); `None` means that spawn is cut off.
+pub fn render_ascii_map(
+    obs: &TdObservation,
+    routes: &[Option<Route>],
+    options: &AsciiMapOptions,
+) -> String {
+    let w = obs.map_width as usize;
+    let h = obs.map_height as usize;
+
+    let mut cells: Vec<u8> = (0..w * h)
+        .map(|i| {
+            if obs.walkable.get(i).copied().unwrap_or(false) {
+                b'.'
+            } else {
+                b'#'
+            }
+        })
+        .collect();
+
+    if options.show_path {
+        for route in routes.iter().flatten() {
+            for &(x, y) in &route.cells {
+                cells[y as usize * w + x as usize] = b'*';
+            }
+        }
+    }
+    for m in &obs.mobs {
+        // Mob positions are continuous; cell (x, y) spans [x, x+1)
+        let (x, y) = (m.x.floor(), m.y.floor());
+        if x < 0.0 || y < 0.0 || x as usize >= w || y as usize >= h {
+            continue;
+        }
+        let cell = &mut cells[y as usize * w + x as usize];
+        *cell = if *cell == b'm' || *cell == b'M' {
+            b'M'
+        } else {
+            b'm'
+        };
+    }
+    for b in &obs.build_queue {
+        cells[b.y as usize * w + b.x as usize] = b'b';
+    }
+    for t in &obs.towers {
+        cells[t.y as usize * w + t.x as usize] = match t.upgrade_level {
+            level @ 0..=9 => b'0' + level,
+            _ => b'+',
+        };
+    }
+    for p in &obs.spawns {
+        cells[p.y as usize * w + p.x as usize] = b'S';
+    }
+    for p in &obs.goals {
+        cells[p.y as usize * w + p.x as usize] = b'G';
+    }
+
+    let region = options.region.unwrap_or(MapRegion {
+        x: 0,
+        y: 0,
+        width: obs.map_width,
+        height: obs.map_height,
+    });
+    let x0 = (region.x as usize).min(w);
+    let y0 = (region.y as usize).min(h);
+    let x1 = (x0 + region.width as usize).min(w);
+    let y1 = (y0 + region.height as usize).min(h);
+
+    let mut out = String::new();
+    let margin = if options.rulers {
+        y1.saturating_sub(1).to_string().len() + 1
+    } else {
+        0
+    };
+    if options.rulers {
+        // Tens digit at every multiple of 10 (and at the left edge), units below
+        let mut tens = " ".repeat(margin);
+        let mut units = " ".repeat(margin);
+        for x in x0..x1 {
+            if x % 10 == 0 || x == x0 {
+                tens.push_str(&(x / 10 % 10).to_string());
+            } else {
+                tens.push(' ');
+            }
+            units.push_str(&(x % 10).to_string());
+        }
+        out.push_str(tens.trim_end());
+        out.push('\n');
+        out.push_str(&units);
+        out.push('\n');
+    }
+    for y in y0..y1 {
+        if options.rulers {
+            out.push_str(&format!("{:>width$} ", y, width = margin - 1));
+        }
+        out.extend(cells[y * w + x0..y * w + x1].iter().map(|&c| c as char));
+        out.push('\n');
+    }
+
+    for (i, route) in routes.iter().enumerate() {
+        let label = if routes.len() > 1 {
+            format!("Mob path from spawn {}", i)
+        } else {
+            "Mob path".to_string()
+        };
+        match route {
+            Some(route) => out.push_str(&format!("{}: {} cells\n", label, route.cells.len())),
+            None => out.push_str(&format!("{}: BLOCKED (mobs will attack towers)\n", label)),
+        }
+    }
+    out.push_str(ASCII_MAP_LEGEND);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TdConfig;
+    use crate::pathing;
+    use crate::TdGame;
+    use sim_core::Game;
+
+    fn new_game() -> TdGame {
+        TdGame::new(TdConfig::default(), 7)
+    }
+
+    #[test]
+    fn render_full_map() {
+        let game = new_game();
+        let obs = game.observe(0, 0);
+        let routes = pathing::mob_routes(game.state());
+        let options = AsciiMapOptions {
+            rulers: false,
+            ..AsciiMapOptions::default()
+        };
+        let text = render_ascii_map(&obs, &routes, &options);
+        let rows: Vec<&str> = text.lines().take(obs.map_height as usize).collect();
+
+        assert_eq!(rows.len(), obs.map_height as usize);
+        assert!(rows.iter().all(|r| r.len() == obs.map_width as usize));
+        let grid = rows.concat();
+        assert_eq!(grid.matches('S').count(), 1);
+        assert_eq!(grid.matches('G').count(), 1);
+        // Spawn and goal cells are drawn over the path
+        let route = routes[0].as_ref().unwrap();
+        assert_eq!(grid.matches('*').count(), route.cells.len() - 2);
+    }
+
+    #[test]
+    fn render_cropped_with_rulers() {
+        let game = new_game();
+        let obs = game.observe(0, 0);
+        let routes = pathing::mob_routes(game.state());
+        let options = AsciiMapOptions {
+            region: Some(MapRegion {
+                x: 8,
+                y: 9,
+                width: 5,
+                height: 100,
+            }),
+            ..AsciiMapOptions::default()
+        };
+        let text = render_ascii_map(&obs, &routes, &options);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "   0 1");
+        assert_eq!(lines[1], "   89012");
+        assert!(lines[2].starts_with(" 9 "));
+        let rows = obs.map_height as usize - 9;
+        assert!(lines[2 + rows].starts_with("Mob path"));
+        assert!(lines[2..2 + rows].iter().all(|r| r.len() == 3 + 5));
+    }
+}
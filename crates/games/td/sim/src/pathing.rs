@@ -14,8 +14,8 @@ const NEIGHBORS: [(i32, i32); 8] = [
     (-1, -1), // NW
 ];
 
-const CARDINAL_COST: u32 = 10;
-const DIAGONAL_COST: u32 = 14;
+pub const CARDINAL_COST: u32 = 10;
+pub const DIAGONAL_COST: u32 = 14;
 
 fn is_diagonal(idx: usize) -> bool {
     idx % 2 == 1
@@ -41,61 +41,357 @@ fn diagonal_allowed(grid: &Grid, x: u16, y: u16, dx: i32, dy: i32) -> bool {
     !grid.is_blocked_idx(idx1) && !grid.is_blocked_idx(idx2)
 }
 
-/// Recompute the distance field using Dijkstra from the goal.
-pub fn compute_distance_field(grid: &Grid, goal: (u16, u16), dist: &mut [u32]) {
-    let width = grid.width;
-    let height = grid.height;
+/// Cells a step from `idx` can reach, in [`NEIGHBORS`] order, with the step cost. A
+/// diagonal step needs both cells it cuts the corner of to be open. Steps are
+/// symmetric, so these are also the cells that can step to `idx`.
+fn edges(grid: &Grid, idx: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+    let x = (idx % grid.width as usize) as u16;
+    let y = (idx / grid.width as usize) as u16;
+
+    NEIGHBORS
+        .iter()
+        .enumerate()
+        .filter_map(move |(i, &(dx, dy))| {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
 
-    dist.fill(u32::MAX);
+            if nx < 0 || ny < 0 || nx >= grid.width as i32 || ny >= grid.height as i32 {
+                return None;
+            }
 
-    let goal_idx = grid.idx(goal.0, goal.1);
+            let nidx = grid.idx(nx as u16, ny as u16);
+            if grid.is_blocked_idx(nidx) {
+                return None;
+            }
 
-    if grid.is_blocked_idx(goal_idx) {
-        return;
-    }
+            if is_diagonal(i) && !diagonal_allowed(grid, x, y, dx, dy) {
+                return None;
+            }
+
+            Some((nidx, neighbor_cost(i)))
+        })
+}
+
+/// `(x, y)` and its in-bounds neighbors.
+fn block_around(grid: &Grid, x: u16, y: u16) -> impl Iterator<Item = usize> + '_ {
+    (-1..=1_i32).flat_map(move |dy| {
+        (-1..=1_i32).filter_map(move |dx| {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            (nx >= 0 && ny >= 0 && grid.in_bounds(nx as u16, ny as u16))
+                .then(|| grid.idx(nx as u16, ny as u16))
+        })
+    })
+}
+
+/// Recompute the distance field using Dijkstra from the goals: each cell gets the
+/// distance to its nearest goal.
+pub fn compute_distance_field(grid: &Grid, goals: &[(u16, u16)], dist: &mut [u32]) {
+    dijkstra(grid, goals, None, dist);
+}
+
+/// Dijkstra from the goals, with an optional extra cost for entering each cell.
+fn dijkstra(grid: &Grid, goals: &[(u16, u16)], penalty: Option<&[u32]>, dist: &mut [u32]) {
+    dist.fill(u32::MAX);
 
     let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::new();
 
-    dist[goal_idx] = 0;
-    heap.push(Reverse((0, goal_idx)));
+    // A blocked goal is out of play until unblocked
+    for &(x, y) in goals {
+        let goal_idx = grid.idx(x, y);
+        if !grid.is_blocked_idx(goal_idx) {
+            dist[goal_idx] = 0;
+            heap.push(Reverse((0, goal_idx)));
+        }
+    }
+    relax(grid, penalty, dist, &mut heap);
+}
 
+/// Pop cells off `heap` and lower their neighbors' distances until it is empty.
+fn relax(
+    grid: &Grid,
+    penalty: Option<&[u32]>,
+    dist: &mut [u32],
+    heap: &mut BinaryHeap<Reverse<(u32, usize)>>,
+) {
     while let Some(Reverse((d, idx))) = heap.pop() {
         if d > dist[idx] {
             continue;
         }
 
-        let x = (idx % (width as usize)) as u16;
-        let y = (idx / (width as usize)) as u16;
-
-        for (i, &(dx, dy)) in NEIGHBORS.iter().enumerate() {
-            let nx = x as i32 + dx;
-            let ny = y as i32 + dy;
+        for (nidx, cost) in edges(grid, idx) {
+            let cost = cost + penalty.map_or(0, |p| p[nidx]);
+            let new_dist = d.saturating_add(cost);
 
-            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
-                continue;
+            if new_dist < dist[nidx] {
+                dist[nidx] = new_dist;
+                heap.push(Reverse((new_dist, nidx)));
             }
+        }
+    }
+}
 
-            let nx = nx as u16;
-            let ny = ny as u16;
-            let nidx = grid.idx(nx, ny);
+/// Repair `dist`, computed for an earlier version of `grid`, after the cells in
+/// `changed` were blocked or unblocked. Gives the same field as
+/// [`compute_distance_field`] while visiting only the cells whose distance changes
+/// and their neighbors. Cells in `changed` that did not actually change are harmless.
+pub fn update_distance_field(
+    grid: &Grid,
+    goals: &[(u16, u16)],
+    changed: &[(u16, u16)],
+    dist: &mut [u32],
+) {
+    if goals.iter().any(|goal| changed.contains(goal)) {
+        compute_distance_field(grid, goals, dist);
+        return;
+    }
 
-            if grid.is_blocked_idx(nidx) {
-                continue;
-            }
+    // Steps that appeared or disappeared all start next to a changed cell
+    let touched: Vec<usize> = changed
+        .iter()
+        .flat_map(|&(x, y)| block_around(grid, x, y))
+        .collect();
+
+    // 1. Forget the distance of every cell left without a neighbor its distance came
+    // from, then of the cells whose distance came from those. What remains are
+    // distances of routes that still exist.
+    let mut invalidated = Vec::new();
+    let mut check = touched.clone();
+    while let Some(idx) = check.pop() {
+        // Open goals are at 0, and only they are
+        if dist[idx] == 0 || dist[idx] == u32::MAX {
+            continue;
+        }
+        let supported = !grid.is_blocked_idx(idx)
+            && edges(grid, idx).any(|(n, cost)| dist[n] != u32::MAX && dist[n] + cost == dist[idx]);
+        if supported {
+            continue;
+        }
+        dist[idx] = u32::MAX;
+        invalidated.push(idx);
+        let width = grid.width as usize;
+        check.extend(block_around(
+            grid,
+            (idx % width) as u16,
+            (idx / width) as u16,
+        ));
+    }
 
-            if is_diagonal(i) && !diagonal_allowed(grid, x, y, dx, dy) {
-                continue;
-            }
+    // 2. Seed the forgotten cells from their neighbors, and the touched cells with
+    // their current distance to spread it over new steps, then run Dijkstra from there.
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::new();
+    for &idx in invalidated.iter().chain(&touched) {
+        if grid.is_blocked_idx(idx) {
+            continue;
+        }
+        let best = edges(grid, idx)
+            .filter(|&(n, _)| dist[n] != u32::MAX)
+            .map(|(n, cost)| dist[n] + cost)
+            .min();
+        if let Some(d) = best {
+            dist[idx] = dist[idx].min(d);
+        }
+        if dist[idx] != u32::MAX {
+            heap.push(Reverse((dist[idx], idx)));
+        }
+    }
+    relax(grid, None, dist, &mut heap);
+}
 
-            let cost = neighbor_cost(i);
-            let new_dist = d.saturating_add(cost);
+/// Bring `state.dist` up to date with the cells in `state.dirty_cells`.
+pub fn refresh_distance_field(state: &mut TdState) {
+    let changed = std::mem::take(&mut state.dirty_cells);
+    update_distance_field(
+        &state.world.grid,
+        &state.config.goals,
+        &changed,
+        &mut state.dist,
+    );
+}
 
-            if new_dist < dist[nidx] {
-                dist[nidx] = new_dist;
-                heap.push(Reverse((new_dist, nidx)));
+/// The neighbor a mob at `(x, y)` steps to next: the first in [`NEIGHBORS`] order with
+/// the lowest distance, if lower than the current cell's. `None` at a goal or when no
+/// goal is reachable.
+pub fn next_step(grid: &Grid, dist: &[u32], x: u16, y: u16) -> Option<(u16, u16)> {
+    let cell_dist = dist[grid.idx(x, y)];
+    if cell_dist == u32::MAX {
+        return None;
+    }
+
+    let mut best_neighbor: Option<usize> = None;
+    let mut best_dist = cell_dist;
+
+    for (nidx, _) in edges(grid, grid.idx(x, y)) {
+        let nd = dist[nidx];
+        if nd < best_dist {
+            best_dist = nd;
+            best_neighbor = Some(nidx);
+        }
+    }
+
+    best_neighbor.map(|nidx| {
+        let width = grid.width as usize;
+        ((nidx % width) as u16, (nidx / width) as u16)
+    })
+}
+
+/// A walkable route between two cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    /// Cells from start to end, inclusive.
+    pub cells: Vec<(u16, u16)>,
+    /// Walking cost: [`CARDINAL_COST`] per straight step, [`DIAGONAL_COST`] per diagonal.
+    pub cost: u32,
+}
+
+impl Route {
+    /// Walking distance in cells, with diagonal steps counting 1.4.
+    pub fn length(&self) -> f32 {
+        self.cost as f32 / CARDINAL_COST as f32
+    }
+}
+
+/// Follow `dist` down from `start` to a goal with [`next_step`], as a mob does.
+fn descend(grid: &Grid, dist: &[u32], start: (u16, u16)) -> Option<Route> {
+    if dist[grid.idx(start.0, start.1)] == u32::MAX {
+        return None;
+    }
+
+    let mut cells = vec![start];
+    let mut cost = 0;
+    let (mut x, mut y) = start;
+    while dist[grid.idx(x, y)] != 0 {
+        let (nx, ny) = next_step(grid, dist, x, y)?;
+        cost += if nx != x && ny != y {
+            DIAGONAL_COST
+        } else {
+            CARDINAL_COST
+        };
+        cells.push((nx, ny));
+        (x, y) = (nx, ny);
+    }
+
+    Some(Route { cells, cost })
+}
+
+/// The route a mob at `start` walks to the nearest goal while the grid stays unchanged.
+/// `None` if no goal is reachable, in which case mobs attack towers.
+pub fn route_from(state: &TdState, start: (u16, u16)) -> Option<Route> {
+    descend(&state.world.grid, &state.dist, start)
+}
+
+/// The route a mob spawned now walks from each spawn, in `config.spawns` order.
+pub fn mob_routes(state: &TdState) -> Vec<Option<Route>> {
+    state
+        .config
+        .spawns
+        .iter()
+        .map(|&spawn| route_from(state, spawn))
+        .collect()
+}
+
+/// Cells of all [`mob_routes`], each once, in route order.
+pub fn mob_path_cells(state: &TdState) -> Vec<(u16, u16)> {
+    let mut seen = vec![false; state.dist.len()];
+    mob_routes(state)
+        .into_iter()
+        .flatten()
+        .flat_map(|route| route.cells)
+        .filter(|&(x, y)| !std::mem::replace(&mut seen[state.world.grid.idx(x, y)], true))
+        .collect()
+}
+
+/// Extra cost for entering a cell already used by a found route, to push alternatives
+/// onto other cells.
+const ALTERNATIVE_PENALTY: u32 = 4 * CARDINAL_COST;
+
+/// Up to `count` routes from `spawn` to a goal other than the one mobs take, each found
+/// by making cells used by earlier routes more expensive, so they diverge where the map
+/// allows. Shortest first. These are routes mobs switch to if towers block the current
+/// one.
+pub fn alternative_routes(state: &TdState, spawn: (u16, u16), count: usize) -> Vec<Route> {
+    let grid = &state.world.grid;
+    let Some(main) = route_from(state, spawn) else {
+        return Vec::new();
+    };
+
+    let mut penalty = vec![0; state.dist.len()];
+    let mut dist = vec![u32::MAX; state.dist.len()];
+    let penalize = |penalty: &mut [u32], route: &Route| {
+        for &(x, y) in &route.cells {
+            penalty[grid.idx(x, y)] += ALTERNATIVE_PENALTY;
+        }
+    };
+    penalize(&mut penalty, &main);
+    let mut found = vec![main];
+
+    // Attempts can repeat a route when the map leaves no way around it
+    for _ in 0..count * 2 {
+        if found.len() > count {
+            break;
+        }
+        dijkstra(grid, &state.config.goals, Some(&penalty), &mut dist);
+        let Some(route) = descend(grid, &dist, spawn) else {
+            break;
+        };
+        penalize(&mut penalty, &route);
+        if found.iter().any(|r| r.cells == route.cells) {
+            continue;
+        }
+        found.push(route);
+    }
+
+    let mut alternatives = found.split_off(1);
+    alternatives.sort_by_key(|r| r.cost);
+    alternatives
+}
+
+/// Cells (other than spawns and goals) that cut a spawn off from every goal when
+/// blocked: a tower on one makes mobs attack towers.
+pub fn chokepoints(state: &TdState) -> Vec<(u16, u16)> {
+    let grid = &state.world.grid;
+    let routes: Option<Vec<Route>> = mob_routes(state).into_iter().collect();
+    let Some(routes) = routes else {
+        return Vec::new();
+    };
+    let spawns: Vec<usize> = state
+        .config
+        .spawns
+        .iter()
+        .map(|&(x, y)| grid.idx(x, y))
+        .collect();
+
+    // A chokepoint lies on every route from its spawn, so on the current one. Any other
+    // cell can be walked around: blocking the corner of a diagonal step leaves the way
+    // through the opposite corner.
+    let mut candidates = Vec::new();
+    let mut seen = vec![false; state.dist.len()];
+    for route in &routes {
+        let inner = route.cells.len().saturating_sub(2);
+        for &(x, y) in route.cells.iter().skip(1).take(inner) {
+            let endpoint = state.config.spawns.contains(&(x, y));
+            if !endpoint && !std::mem::replace(&mut seen[grid.idx(x, y)], true) {
+                candidates.push((x, y));
             }
         }
     }
+
+    let mut scratch = grid.clone();
+    let mut dist = vec![u32::MAX; state.dist.len()];
+    let mut changed = state.dirty_cells.clone();
+    candidates
+        .into_iter()
+        .filter(|&(x, y)| {
+            let previous = scratch.get(x, y);
+            scratch.set(x, y, CellState::Building);
+            dist.copy_from_slice(&state.dist);
+            changed.push((x, y));
+            update_distance_field(&scratch, &state.config.goals, &changed, &mut dist);
+            changed.pop();
+            scratch.set(x, y, previous);
+            spawns.iter().any(|&idx| dist[idx] == u32::MAX)
+        })
+        .collect()
 }
 
 pub enum MobMoveResult {
@@ -113,50 +409,12 @@ pub enum MobMoveResult {
 /// Uses the distance field to choose the best neighbor, or falls back
 /// to attacking / BFS-toward-tower when the path is blocked.
 pub fn pick_next_target(state: &TdState, cx: u16, cy: u16) -> MobMoveResult {
-    let goal = state.config.goal;
-
-    if cx == goal.0 && cy == goal.1 {
+    if state.config.goals.contains(&(cx, cy)) {
         return MobMoveResult::Leaked;
     }
 
-    let grid = &state.world.grid;
-    let cell_idx = grid.idx(cx, cy);
-    let cell_dist = state.dist[cell_idx];
-
-    if cell_dist != u32::MAX {
-        let mut best_neighbor: Option<(u16, u16)> = None;
-        let mut best_dist = cell_dist;
-
-        for (i, &(dx, dy)) in NEIGHBORS.iter().enumerate() {
-            let nx = cx as i32 + dx;
-            let ny = cy as i32 + dy;
-
-            if nx < 0 || ny < 0 || nx >= grid.width as i32 || ny >= grid.height as i32 {
-                continue;
-            }
-
-            let nx = nx as u16;
-            let ny = ny as u16;
-            let nidx = grid.idx(nx, ny);
-
-            if grid.is_blocked_idx(nidx) {
-                continue;
-            }
-
-            if is_diagonal(i) && !diagonal_allowed(grid, cx, cy, dx, dy) {
-                continue;
-            }
-
-            let nd = state.dist[nidx];
-            if nd < best_dist {
-                best_dist = nd;
-                best_neighbor = Some((nx, ny));
-            }
-        }
-
-        if let Some((nx, ny)) = best_neighbor {
-            return MobMoveResult::NextTarget(nx, ny);
-        }
+    if let Some((nx, ny)) = next_step(&state.world.grid, &state.dist, cx, cy) {
+        return MobMoveResult::NextTarget(nx, ny);
     }
 
     // Unreachable or stuck: attack adjacent tower
@@ -315,3 +573,257 @@ fn find_move_toward_tower(state: &TdState, mx: u16, my: u16) -> Option<(u16, u16
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::TdAction;
+    use crate::config::{TdConfig, TowerKind};
+    use crate::world::MobId;
+    use crate::TdGame;
+    use sim_core::{ActionEnvelope, Game};
+
+    fn first_route(state: &TdState) -> Option<Route> {
+        route_from(state, state.config.spawns[0])
+    }
+
+    fn new_game() -> TdGame {
+        let config = TdConfig {
+            tick_hz: 20,
+            ..TdConfig::default()
+        };
+        TdGame::new(config, 11)
+    }
+
+    /// Step until the first mob spawns, then record the cells it walks toward until it
+    /// leaks or dies. Returns the route predicted just before it spawned, and the walk.
+    fn walk_first_mob(game: &mut TdGame, actions: Vec<TdAction>) -> (Route, Vec<(u16, u16)>) {
+        let mut events = Vec::new();
+        let mut actions: Vec<_> = actions
+            .into_iter()
+            .enumerate()
+            .map(|(i, payload)| ActionEnvelope {
+                player_id: 0,
+                action_id: i as u64 + 1,
+                intended_tick: 1,
+                payload,
+            })
+            .collect();
+
+        let mut tick = 0;
+        let mut predicted = None;
+        let mut mob: Option<MobId> = None;
+        let mut walked = vec![game.state().config.spawns[0]];
+        loop {
+            tick += 1;
+            if mob.is_none() {
+                predicted = first_route(game.state());
+            }
+            game.step(tick, &std::mem::take(&mut actions), &mut events);
+
+            let state = game.state();
+            if mob.is_none() {
+                mob = state.world.mobs.keys().next();
+            }
+            let Some(id) = mob else { continue };
+            let Some(m) = state.world.mobs.get(id) else {
+                break;
+            };
+            if walked.last() != Some(&m.target) {
+                walked.push(m.target);
+            }
+            assert!(tick < 10_000, "mob never left the map");
+        }
+        (predicted.unwrap(), walked)
+    }
+
+    #[test]
+    fn mobs_walk_predicted_route() {
+        let mut game = new_game();
+        let (predicted, walked) = walk_first_mob(&mut game, Vec::new());
+
+        assert_eq!(walked, predicted.cells);
+        assert_eq!(game.state().leaks, 1);
+    }
+
+    #[test]
+    fn mobs_walk_predicted_detour() {
+        let mut game = new_game();
+        let route = first_route(game.state()).unwrap();
+        let chokepoints = chokepoints(game.state());
+        let &(x, y) = route.cells[route.cells.len() / 2..]
+            .iter()
+            .find(|cell| !chokepoints.contains(cell))
+            .unwrap();
+
+        let place = TdAction::PlaceTower {
+            x,
+            y,
+            kind: TowerKind::Basic,
+        };
+        let (predicted, walked) = walk_first_mob(&mut game, vec![place]);
+
+        assert!(!predicted.cells.contains(&(x, y)));
+        assert!(predicted.cost >= route.cost);
+        // The tower may kill the mob before it reaches the goal
+        assert!(walked.len() > 1);
+        assert_eq!(walked[..], predicted.cells[..walked.len()]);
+    }
+
+    #[test]
+    fn alternative_routes_are_valid() {
+        let game = new_game();
+        let state = game.state();
+        let spawn = state.config.spawns[0];
+        let main = route_from(state, spawn).unwrap();
+        let alternatives = alternative_routes(state, spawn, 2);
+
+        for route in &alternatives {
+            assert_ne!(route.cells, main.cells);
+            assert_eq!(route.cells.first(), Some(&spawn));
+            assert_eq!(route.cells.last(), Some(&state.config.goals[0]));
+            let mut cost = 0;
+            for w in route.cells.windows(2) {
+                let (dx, dy) = (w[0].0.abs_diff(w[1].0), w[0].1.abs_diff(w[1].1));
+                assert!(dx <= 1 && dy <= 1 && dx + dy > 0);
+                assert!(!state
+                    .world
+                    .grid
+                    .is_blocked_idx(state.world.grid.idx(w[1].0, w[1].1)));
+                cost += if dx + dy == 2 {
+                    DIAGONAL_COST
+                } else {
+                    CARDINAL_COST
+                };
+            }
+            assert_eq!(route.cost, cost);
+            assert!(route.cost >= main.cost);
+        }
+        assert!(alternatives.windows(2).all(|w| w[0].cost <= w[1].cost));
+    }
+
+    /// xorshift64, so the randomized tests are reproducible without extra dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    /// Block and unblock random batches of cells, some of them several times, and check
+    /// each repaired field against a full recompute.
+    fn check_random_updates(seed: u64, width: u16, height: u16, batch: u64, goals: usize) {
+        let mut rng = Rng(seed);
+        let walkable = (0..width as usize * height as usize)
+            .map(|_| rng.below(5) != 0)
+            .collect();
+        let mut grid = Grid::from_terrain(width, height, walkable);
+        let goals = &[(width / 2, height / 2), (0, 0), (width - 1, 3)][..goals];
+        let mut dist = vec![u32::MAX; grid.walkable.len()];
+        let mut expected = dist.clone();
+        compute_distance_field(&grid, goals, &mut dist);
+
+        for _ in 0..200 {
+            let mut changed = Vec::new();
+            for _ in 0..1 + rng.below(batch) {
+                let x = rng.below(width as u64) as u16;
+                let y = rng.below(height as u64) as u16;
+                let next = match grid.get(x, y) {
+                    CellState::Empty => CellState::Building,
+                    _ => CellState::Empty,
+                };
+                grid.set(x, y, next);
+                changed.push((x, y));
+            }
+
+            update_distance_field(&grid, goals, &changed, &mut dist);
+            compute_distance_field(&grid, goals, &mut expected);
+            assert_eq!(dist, expected, "seed {seed}, changed {changed:?}");
+        }
+    }
+
+    #[test]
+    fn update_distance_field_matches_recompute() {
+        for seed in 1..=20 {
+            check_random_updates(seed, 24, 17, 1, 1);
+            check_random_updates(seed, 24, 17, 6, 1);
+            check_random_updates(seed, 24, 17, 6, 3);
+        }
+    }
+
+    #[test]
+    fn update_distance_field_blocked_goal() {
+        let mut grid = Grid::from_terrain(5, 5, vec![true; 25]);
+        let mut dist = vec![u32::MAX; 25];
+        compute_distance_field(&grid, &[(2, 2)], &mut dist);
+
+        grid.set(2, 2, CellState::Building);
+        update_distance_field(&grid, &[(2, 2)], &[(2, 2)], &mut dist);
+        assert!(dist.iter().all(|&d| d == u32::MAX));
+
+        grid.set(2, 2, CellState::Empty);
+        update_distance_field(&grid, &[(2, 2)], &[(2, 2)], &mut dist);
+        assert_eq!((dist[12], dist[0]), (0, 2 * DIAGONAL_COST));
+    }
+
+    #[test]
+    fn game_keeps_distance_field_exact() {
+        let mut game = new_game();
+        let route = first_route(game.state()).unwrap();
+        let chokepoints = chokepoints(game.state());
+        let actions: Vec<_> = route.cells[1..]
+            .iter()
+            .filter(|cell| !chokepoints.contains(cell))
+            .take(3)
+            .map(|&(x, y)| TdAction::PlaceTower {
+                x,
+                y,
+                kind: TowerKind::Basic,
+            })
+            .collect();
+        walk_first_mob(&mut game, actions);
+
+        let state = game.state();
+        let mut expected = vec![u32::MAX; state.dist.len()];
+        compute_distance_field(&state.world.grid, &state.config.goals, &mut expected);
+        assert!(state.dirty_cells.is_empty());
+        assert_eq!(state.dist, expected);
+    }
+
+    /// A 6x3 map whose middle row is open, plus the cells in `extra`.
+    fn corridor(extra: &[(u16, u16)]) -> TdState {
+        let config = TdConfig {
+            width: 6,
+            height: 3,
+            spawns: vec![(0, 1)],
+            goals: vec![(5, 1)],
+            ..TdConfig::default()
+        };
+        let mut walkable = vec![false; 18];
+        for x in 0..6 {
+            walkable[6 + x] = true;
+        }
+        for &(x, y) in extra {
+            walkable[y as usize * 6 + x as usize] = true;
+        }
+        let mut state = TdState::with_terrain(config, walkable);
+        compute_distance_field(&state.world.grid, &state.config.goals, &mut state.dist);
+        state
+    }
+
+    #[test]
+    fn chokepoints_in_corridor() {
+        let state = corridor(&[]);
+        assert_eq!(chokepoints(&state), vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+        assert!(alternative_routes(&state, (0, 1), 2).is_empty());
+
+        // A bypass below x = 2..=3 leaves only the cells next to spawn and goal
+        let state = corridor(&[(1, 2), (2, 2), (3, 2), (4, 2)]);
+        assert_eq!(chokepoints(&state), vec![(1, 1), (4, 1)]);
+        assert!(!alternative_routes(&state, (0, 1), 2).is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! Tower placement analysis: path coverage and path-length impact of each candidate
+//! cell, so agents don't have to compute them from the path and the grid.
+
+use crate::config::TowerKind;
+use crate::mcp::types::{AnalyzePlacementsResult, PlacementInfo};
+use crate::observe::path_cells_in_range;
+use crate::pathing::{self, update_distance_field, CARDINAL_COST};
+use crate::world::{CellState, TdState};
+use std::cmp::{Ordering, Reverse};
+use td_types::Position;
+
+fn to_cells(dist: u32) -> Option<f32> {
+    (dist != u32::MAX).then(|| dist as f32 / CARDINAL_COST as f32)
+}
+
+/// Walking distance from spawn to the nearest goal in cells, averaged over spawns; `None`
+/// if any spawn is cut off.
+fn spawn_distance(state: &TdState, dist: &[u32]) -> Option<f32> {
+    let spawns = &state.config.spawns;
+    let total: Option<f32> = spawns
+        .iter()
+        .map(|&(x, y)| to_cells(dist[state.world.grid.idx(x, y)]))
+        .sum();
+    total.map(|total| total / spawns.len() as f32)
+}
+
+/// Analyze `candidates` (every buildable cell if `None`), skipping cells that can't be
+/// built on, and return the best `limit` placements.
+pub fn analyze_placements(
+    state: &TdState,
+    candidates: Option<&[Position]>,
+    limit: usize,
+) -> AnalyzePlacementsResult {
+    // Builds queued this tick are on the grid but not yet in `state.dist`. Apply them
+    // once, so the baseline and every candidate are measured against the same grid.
+    let refreshed;
+    let state = if state.dirty_cells.is_empty() {
+        state
+    } else {
+        let mut copy = state.clone();
+        pathing::refresh_distance_field(&mut copy);
+        refreshed = copy;
+        &refreshed
+    };
+
+    let config = &state.config;
+    let grid = &state.world.grid;
+    let range = config.spec(TowerKind::Basic).range;
+
+    let path = pathing::mob_path_cells(state);
+    let path_distance = spawn_distance(state, &state.dist);
+
+    // Blocking a cell can only lengthen the path if the path steps on it, or it is a
+    // corner of a diagonal step on it; both are within one cell of the path.
+    let mut near_path = vec![false; state.dist.len()];
+    for &(x, y) in &path {
+        for dy in -1..=1_i32 {
+            for dx in -1..=1_i32 {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if nx >= 0 && ny >= 0 && grid.in_bounds(nx as u16, ny as u16) {
+                    near_path[grid.idx(nx as u16, ny as u16)] = true;
+                }
+            }
+        }
+    }
+
+    let all_cells: Vec<Position>;
+    let candidates = match candidates {
+        Some(candidates) => candidates,
+        None => {
+            all_cells = (0..grid.height)
+                .flat_map(|y| (0..grid.width).map(move |x| Position { x, y }))
+                .collect();
+            &all_cells
+        }
+    };
+
+    let mut scratch = grid.clone();
+    let mut dist = vec![u32::MAX; state.dist.len()];
+    let mut placements: Vec<PlacementInfo> = candidates
+        .iter()
+        .filter(|p| grid.in_bounds(p.x, p.y) && !grid.is_blocked_idx(grid.idx(p.x, p.y)))
+        .map(|p| {
+            let idx = grid.idx(p.x, p.y);
+            let new_distance = if path_distance.is_none() || !near_path[idx] {
+                path_distance
+            } else {
+                scratch.set(p.x, p.y, CellState::Building);
+                dist.copy_from_slice(&state.dist);
+                update_distance_field(&scratch, &config.goals, &[(p.x, p.y)], &mut dist);
+                scratch.set(p.x, p.y, CellState::Empty);
+                spawn_distance(state, &dist)
+            };
+            PlacementInfo {
+                x: p.x,
+                y: p.y,
+                path_cells_in_range: path_cells_in_range(&path, p.x, p.y, range),
+                path_distance_change: new_distance.zip(path_distance).map(|(new, old)| new - old),
+                blocks_path: path_distance.is_some() && new_distance.is_none(),
+            }
+        })
+        .collect();
+    let buildable_candidates = placements.len() as u32;
+
+    placements.sort_by(|a, b| {
+        Reverse(a.path_cells_in_range)
+            .cmp(&Reverse(b.path_cells_in_range))
+            .then_with(|| {
+                let change = |p: &PlacementInfo| p.path_distance_change.unwrap_or(f32::MIN);
+                change(b).partial_cmp(&change(a)).unwrap_or(Ordering::Equal)
+            })
+    });
+    placements.truncate(limit);
+
+    AnalyzePlacementsResult {
+        tower_range: range,
+        path_distance,
+        buildable_candidates,
+        placements,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TdConfig;
+    use crate::pathing::compute_distance_field;
+
+    /// A 7x3 map: a corridor along the middle row with a side pocket below x=3.
+    fn corridor() -> TdState {
+        let config = TdConfig {
+            width: 7,
+            height: 3,
+            spawns: vec![(0, 1)],
+            goals: vec![(6, 1)],
+            ..TdConfig::default()
+        };
+        let mut walkable = vec![false; 21];
+        for x in 0..7 {
+            walkable[7 + x] = true;
+        }
+        walkable[14 + 3] = true;
+        let mut state = TdState::with_terrain(config, walkable);
+        compute_distance_field(&state.world.grid, &state.config.goals, &mut state.dist);
+        state
+    }
+
+    #[test]
+    fn analyze_corridor() {
+        let state = corridor();
+        let result = analyze_placements(&state, None, 100);
+
+        assert_eq!(result.path_distance, Some(6.0));
+        assert_eq!(result.buildable_candidates, 8);
+
+        let pocket = result
+            .placements
+            .iter()
+            .find(|p| (p.x, p.y) == (3, 2))
+            .unwrap();
+        assert!(!pocket.blocks_path);
+        assert_eq!(pocket.path_distance_change, Some(0.0));
+        assert_eq!(pocket.path_cells_in_range, 7);
+
+        let middle = result
+            .placements
+            .iter()
+            .find(|p| (p.x, p.y) == (3, 1))
+            .unwrap();
+        assert!(middle.blocks_path);
+        assert_eq!(middle.path_distance_change, None);
+    }
+
+    #[test]
+    fn analyze_matches_full_recompute() {
+        use crate::TdGame;
+        use sim_core::Game;
+
+        let game = TdGame::new(TdConfig::default(), 3);
+        let mut state = game.state().clone();
+        let mut dist = vec![u32::MAX; state.dist.len()];
+        let check = |state: &TdState, dist: &mut Vec<u32>| {
+            let result = analyze_placements(state, None, usize::MAX);
+            compute_distance_field(&state.world.grid, &state.config.goals, dist);
+            assert_eq!(result.path_distance, spawn_distance(state, dist));
+
+            for p in &result.placements {
+                let mut grid = state.world.grid.clone();
+                grid.set(p.x, p.y, CellState::Building);
+                compute_distance_field(&grid, &state.config.goals, dist);
+                let expected =
+                    spawn_distance(state, dist).map(|d| d - result.path_distance.unwrap());
+                assert_eq!(p.path_distance_change, expected, "at ({},{})", p.x, p.y);
+            }
+        };
+        check(&state, &mut dist);
+
+        // A build queued on the path but not yet in `state.dist`
+        let route = pathing::mob_routes(&state).remove(0).unwrap();
+        let chokepoints = pathing::chokepoints(&state);
+        let &(x, y) = route.cells[1..]
+            .iter()
+            .find(|cell| !chokepoints.contains(cell))
+            .unwrap();
+        let mut events = Vec::new();
+        assert!(crate::systems::try_queue_build(
+            &mut state,
+            x,
+            y,
+            TowerKind::Basic,
+            0,
+            0,
+            &mut events
+        ));
+        assert!(!state.dirty_cells.is_empty());
+        check(&state, &mut dist);
+    }
+
+    #[test]
+    fn analyze_skips_unbuildable_candidates() {
+        let state = corridor();
+        let candidates = [
+            Position { x: 0, y: 0 },
+            Position { x: 3, y: 2 },
+            Position { x: 9, y: 9 },
+        ];
+        let result = analyze_placements(&state, Some(&candidates), 1);
+
+        assert_eq!(result.buildable_candidates, 1);
+        assert_eq!(result.placements.len(), 1);
+        assert_eq!((result.placements[0].x, result.placements[0].y), (3, 2));
+    }
+}
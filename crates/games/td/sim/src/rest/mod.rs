@@ -0,0 +1,414 @@
+//! Plain REST/JSON API mirroring the MCP tools.
+//!
+//! Every route calls the same [`TdApi`] operation as the corresponding MCP tool, so
+//! validation and error messages are identical. `GET /api/openapi.json` describes the
+//! routes; see [`openapi::document`].
+
+pub mod openapi;
+
+use crate::api::{self, ApiError, TdApi};
+use crate::mcp::types::*;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Query (or body) identifying the caller's session.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionParams {
+    pub session_token: u64,
+}
+
+/// Query for `GET /api/matches/{match_id}/observe_next`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObserveNextQuery {
+    pub session_token: u64,
+    /// Last tick you observed. Returns when a tick > this is available. Use 0 for the first call.
+    #[serde(default)]
+    pub after_tick: u64,
+    /// Max time to wait in milliseconds (default: 5000).
+    #[serde(default = "default_max_wait")]
+    pub max_wait_ms: u64,
+}
+
+fn default_max_wait() -> u64 {
+    5000
+}
+
+/// Body of `POST /api/matches/{match_id}/actions`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SubmitActionParams {
+    pub session_token: u64,
+    /// The tick at which this action should be executed. Use 0 to execute immediately.
+    #[serde(default)]
+    pub intended_tick: u64,
+    #[serde(flatten)]
+    pub action: ActionParams,
+}
+
+/// Query for `GET /api/matches/{match_id}/map`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RenderMapQuery {
+    pub session_token: u64,
+    /// Mark the current mob path with '*' (default: true).
+    #[serde(default = "default_true")]
+    pub show_path: bool,
+    /// Add coordinate rulers above and left of the grid (default: true).
+    #[serde(default = "default_true")]
+    pub rulers: bool,
+    /// Left edge of the region to render (default: 0).
+    #[serde(default)]
+    pub x: Option<u16>,
+    /// Top edge of the region to render (default: 0).
+    #[serde(default)]
+    pub y: Option<u16>,
+    /// Width of the region to render (default: to the right edge of the map).
+    #[serde(default)]
+    pub width: Option<u16>,
+    /// Height of the region to render (default: to the bottom edge of the map).
+    #[serde(default)]
+    pub height: Option<u16>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Query for `GET /api/matches/{match_id}/placements`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PlacementsQuery {
+    pub session_token: u64,
+    /// Cells to analyze as `x,y` pairs separated by `;`, e.g. `3,4;5,4`. Omit to
+    /// analyze every buildable cell.
+    #[serde(default)]
+    pub candidates: Option<String>,
+    /// Max placements to return, best coverage first (default: 20).
+    #[serde(default = "default_placement_limit")]
+    pub limit: usize,
+}
+
+fn default_placement_limit() -> usize {
+    20
+}
+
+/// Cells from a `PlacementsQuery::candidates` list.
+fn parse_candidates(candidates: &str) -> Result<Vec<Position>, ApiError> {
+    candidates
+        .split(';')
+        .filter(|cell| !cell.trim().is_empty())
+        .map(|cell| {
+            let (x, y) = cell.split_once(',').unwrap_or((cell, ""));
+            match (x.trim().parse(), y.trim().parse()) {
+                (Ok(x), Ok(y)) => Ok(Position { x, y }),
+                _ => Err(ApiError::Rejected(format!(
+                    "Invalid candidate '{}': expected x,y",
+                    cell
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// Body of `POST /api/matches/{match_id}/what_if`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WhatIfBody {
+    pub session_token: u64,
+    /// Hypothetical actions, applied in order on the next tick.
+    #[serde(default)]
+    pub actions: Vec<ActionParams>,
+    /// Ticks to simulate. Omit to simulate through the end of the next wave (or the
+    /// current one, during a wave). Capped at 10 minutes of game time.
+    #[serde(default)]
+    pub ticks: Option<u64>,
+}
+
+/// Body of every error response.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorResult {
+    pub error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Rejected(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unavailable(_) => StatusCode::CONFLICT,
+        };
+        let body = ErrorResult {
+            error: self.message().to_string(),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Routes under `/api` backed by `api`. Merge into a router with any state.
+pub fn router<S>(api: TdApi) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/api/openapi.json", get(openapi_document))
+        .route("/api/rules", get(rules))
+        .route("/api/maps", get(list_maps))
+        .route("/api/maps/preview", post(preview_map))
+        .route("/api/matches", get(list_matches).post(create_match))
+        .route("/api/matches/{match_id}", delete(terminate_match))
+        .route("/api/matches/{match_id}/join", post(join_match))
+        .route("/api/matches/{match_id}/leave", post(leave_match))
+        .route("/api/matches/{match_id}/actions", post(submit_action))
+        .route("/api/matches/{match_id}/observe", get(observe))
+        .route("/api/matches/{match_id}/observe_next", get(observe_next))
+        .route(
+            "/api/matches/{match_id}/buildable_cells",
+            get(buildable_cells),
+        )
+        .route("/api/matches/{match_id}/path", get(current_path))
+        .route("/api/matches/{match_id}/map", get(render_map))
+        .route("/api/matches/{match_id}/summary", get(summary))
+        .route(
+            "/api/matches/{match_id}/placements",
+            get(analyze_placements),
+        )
+        .route("/api/matches/{match_id}/what_if", post(what_if))
+        .with_state(api)
+}
+
+async fn openapi_document() -> Json<serde_json::Value> {
+    Json(openapi::document())
+}
+
+async fn rules() -> Json<RulesResult> {
+    Json(api::rules())
+}
+
+async fn list_maps(State(api): State<TdApi>) -> ApiResult<ListMapsResult> {
+    Ok(Json(api.list_maps().await?))
+}
+
+async fn preview_map(
+    State(api): State<TdApi>,
+    Json(params): Json<PreviewMapParams>,
+) -> ApiResult<PreviewMapResult> {
+    api.preview_map(params).await.map(Json)
+}
+
+async fn list_matches(State(api): State<TdApi>) -> Json<ListMatchesResult> {
+    Json(api.list_matches().await)
+}
+
+async fn create_match(
+    State(api): State<TdApi>,
+    Json(params): Json<CreateMatchParams>,
+) -> Result<(StatusCode, Json<CreateMatchResult>), ApiError> {
+    let result = api.create_match(params).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+async fn terminate_match(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+) -> Result<StatusCode, ApiError> {
+    api.terminate_match(match_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn join_match(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+) -> ApiResult<JoinMatchResult> {
+    api.join_match(match_id).await.map(Json)
+}
+
+async fn leave_match(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Json(params): Json<SessionParams>,
+) -> Result<StatusCode, ApiError> {
+    api.leave_match(match_id, params.session_token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn submit_action(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Json(params): Json<SubmitActionParams>,
+) -> ApiResult<ActionResult> {
+    let result = match params.action {
+        ActionParams::PlaceTower { x, y, tower_type } => {
+            api.place_tower(PlaceTowerParams {
+                match_id,
+                session_token: params.session_token,
+                intended_tick: params.intended_tick,
+                x,
+                y,
+                tower_type,
+            })
+            .await
+        }
+        ActionParams::UpgradeTower { tower_id, branch } => {
+            api.upgrade_tower(UpgradeTowerParams {
+                match_id,
+                session_token: params.session_token,
+                intended_tick: params.intended_tick,
+                tower_id,
+                branch,
+            })
+            .await
+        }
+        ActionParams::RepairTower { tower_id } => {
+            api.repair_tower(RepairTowerParams {
+                match_id,
+                session_token: params.session_token,
+                intended_tick: params.intended_tick,
+                tower_id,
+            })
+            .await
+        }
+        ActionParams::SetTargeting {
+            tower_id,
+            targeting,
+        } => {
+            api.set_targeting(SetTargetingParams {
+                match_id,
+                session_token: params.session_token,
+                intended_tick: params.intended_tick,
+                tower_id,
+                targeting,
+            })
+            .await
+        }
+    };
+    result.map(Json)
+}
+
+async fn observe(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Query(params): Query<SessionParams>,
+) -> ApiResult<TdObservation> {
+    api.observe(match_id, params.session_token).await.map(Json)
+}
+
+async fn observe_next(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Query(query): Query<ObserveNextQuery>,
+) -> ApiResult<ObserveNextResult> {
+    api.observe_next(ObserveNextParams {
+        match_id,
+        session_token: query.session_token,
+        after_tick: query.after_tick,
+        max_wait_ms: query.max_wait_ms,
+    })
+    .await
+    .map(Json)
+}
+
+async fn buildable_cells(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Query(params): Query<SessionParams>,
+) -> ApiResult<GetBuildableCellsResult> {
+    api.get_buildable_cells(match_id, params.session_token)
+        .await
+        .map(Json)
+}
+
+async fn current_path(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Query(params): Query<SessionParams>,
+) -> ApiResult<GetCurrentPathResult> {
+    api.get_current_path(match_id, params.session_token)
+        .await
+        .map(Json)
+}
+
+async fn render_map(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Query(query): Query<RenderMapQuery>,
+) -> Result<String, ApiError> {
+    api.render_map(RenderMapParams {
+        match_id,
+        session_token: query.session_token,
+        show_path: query.show_path,
+        rulers: query.rulers,
+        x: query.x,
+        y: query.y,
+        width: query.width,
+        height: query.height,
+    })
+    .await
+}
+
+async fn summary(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Query(params): Query<SessionParams>,
+) -> ApiResult<SummaryResult> {
+    api.get_summary(match_id, params.session_token)
+        .await
+        .map(Json)
+}
+
+async fn analyze_placements(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Query(query): Query<PlacementsQuery>,
+) -> ApiResult<AnalyzePlacementsResult> {
+    let candidates = query
+        .candidates
+        .as_deref()
+        .map(parse_candidates)
+        .transpose()?;
+    api.analyze_placements(AnalyzePlacementsParams {
+        match_id,
+        session_token: query.session_token,
+        candidates,
+        limit: query.limit,
+    })
+    .await
+    .map(Json)
+}
+
+async fn what_if(
+    State(api): State<TdApi>,
+    Path(match_id): Path<u64>,
+    Json(body): Json<WhatIfBody>,
+) -> ApiResult<WhatIfResult> {
+    api.what_if(WhatIfParams {
+        match_id,
+        session_token: body.session_token,
+        actions: body.actions,
+        ticks: body.ticks,
+    })
+    .await
+    .map(Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_candidates() {
+        assert_eq!(
+            parse_candidates("3,4; 5,4;").unwrap(),
+            vec![Position { x: 3, y: 4 }, Position { x: 5, y: 4 }]
+        );
+        assert!(parse_candidates("").unwrap().is_empty());
+        assert_eq!(
+            parse_candidates("3,4;7").unwrap_err(),
+            ApiError::Rejected("Invalid candidate '7': expected x,y".to_string())
+        );
+    }
+}
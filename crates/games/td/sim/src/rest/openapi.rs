@@ -0,0 +1,319 @@
+//! OpenAPI 3 document for the REST API, with schemas generated from the request and
+//! response types.
+
+use super::{
+    ErrorResult, ObserveNextQuery, PlacementsQuery, RenderMapQuery, SessionParams,
+    SubmitActionParams, WhatIfBody,
+};
+use crate::mcp::types::*;
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+/// One documented route.
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    operation_id: &'static str,
+    summary: &'static str,
+    parameters: Vec<Value>,
+    request_body: Option<Value>,
+    status: &'static str,
+    response: Option<Value>,
+    response_type: &'static str,
+}
+
+impl Operation {
+    fn new(method: &'static str, path: &'static str, operation_id: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            operation_id,
+            summary: "",
+            parameters: Vec::new(),
+            request_body: None,
+            status: "200",
+            response: None,
+            response_type: "application/json",
+        }
+    }
+
+    fn summary(mut self, summary: &'static str) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    fn match_id(mut self) -> Self {
+        self.parameters.push(json!({
+            "name": "match_id",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "integer", "format": "uint64", "minimum": 0 },
+        }));
+        self
+    }
+
+    fn query<T: JsonSchema>(mut self, generator: &mut SchemaGenerator) -> Self {
+        self.parameters.extend(query_parameters::<T>(generator));
+        self
+    }
+
+    fn body<T: JsonSchema>(mut self, generator: &mut SchemaGenerator) -> Self {
+        self.request_body = Some(json!({
+            "required": true,
+            "content": { "application/json": { "schema": generator.subschema_for::<T>() } },
+        }));
+        self
+    }
+
+    fn returns<T: JsonSchema>(
+        mut self,
+        status: &'static str,
+        generator: &mut SchemaGenerator,
+    ) -> Self {
+        self.status = status;
+        self.response = Some(generator.subschema_for::<T>().to_value());
+        self
+    }
+
+    fn returns_text(mut self) -> Self {
+        self.status = "200";
+        self.response = Some(json!({ "type": "string" }));
+        self.response_type = "text/plain";
+        self
+    }
+
+    fn no_content(mut self) -> Self {
+        self.status = "204";
+        self.response = None;
+        self
+    }
+
+    fn to_value(&self, error: &Value) -> Value {
+        let success = match &self.response {
+            Some(schema) => json!({
+                "description": "Success",
+                "content": { self.response_type: { "schema": schema } },
+            }),
+            None => json!({ "description": "Success" }),
+        };
+        let error_response = |description: &str| {
+            json!({
+                "description": description,
+                "content": { "application/json": { "schema": error } },
+            })
+        };
+
+        let mut operation = json!({
+            "operationId": self.operation_id,
+            "summary": self.summary,
+            "responses": {
+                self.status: success,
+                "400": error_response("Invalid session or parameters, or the action was rejected"),
+                "404": error_response("Match or tower not found"),
+                "409": error_response("Not possible in the match's current state"),
+            },
+        });
+        if !self.parameters.is_empty() {
+            operation["parameters"] = Value::Array(self.parameters.clone());
+        }
+        if let Some(body) = &self.request_body {
+            operation["requestBody"] = body.clone();
+        }
+        operation
+    }
+}
+
+/// Query parameters from the top-level properties of `T`'s schema.
+fn query_parameters<T: JsonSchema>(generator: &mut SchemaGenerator) -> Vec<Value> {
+    let schema = generator.root_schema_for::<T>();
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let mut property = property.clone();
+            let description = property
+                .as_object_mut()
+                .and_then(|p| p.remove("description"));
+            let mut parameter = json!({
+                "name": name,
+                "in": "query",
+                "required": required.contains(&name.as_str()),
+                "schema": property,
+            });
+            if let Some(description) = description {
+                parameter["description"] = description;
+            }
+            parameter
+        })
+        .collect()
+}
+
+/// Build the OpenAPI document served at `/api/openapi.json`.
+pub fn document() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let g = &mut generator;
+
+    let operations = vec![
+        Operation::new("get", "/api/rules", "rules")
+            .summary("Get the complete rules and mechanics of the game")
+            .returns::<RulesResult>("200", g),
+        Operation::new("get", "/api/maps", "list_maps")
+            .summary("List the named maps create_match accepts")
+            .returns::<ListMapsResult>("200", g),
+        Operation::new("get", "/api/matches", "list_matches")
+            .summary("List all active matches")
+            .returns::<ListMatchesResult>("200", g),
+        Operation::new("post", "/api/matches", "create_match")
+            .summary("Create a new match with the specified seed and player count")
+            .body::<CreateMatchParams>(g)
+            .returns::<CreateMatchResult>("201", g),
+        Operation::new("delete", "/api/matches/{match_id}", "terminate_match")
+            .summary("Terminate an active match")
+            .match_id()
+            .no_content(),
+        Operation::new("post", "/api/matches/{match_id}/join", "join_match")
+            .summary("Join a match as a new player")
+            .match_id()
+            .returns::<JoinMatchResult>("200", g),
+        Operation::new("post", "/api/matches/{match_id}/leave", "leave_match")
+            .summary("Leave a match")
+            .match_id()
+            .body::<SessionParams>(g)
+            .no_content(),
+        Operation::new("post", "/api/matches/{match_id}/actions", "submit_action")
+            .summary("Place, upgrade or repair a tower, or set its targeting")
+            .match_id()
+            .body::<SubmitActionParams>(g)
+            .returns::<ActionResult>("200", g),
+        Operation::new("get", "/api/matches/{match_id}/observe", "observe")
+            .summary("Get the current observation, including the walkable grid")
+            .match_id()
+            .query::<SessionParams>(g)
+            .returns::<TdObservation>("200", g),
+        Operation::new(
+            "get",
+            "/api/matches/{match_id}/observe_next",
+            "observe_next",
+        )
+        .summary("Wait for the next decision-tick observation (long-poll)")
+        .match_id()
+        .query::<ObserveNextQuery>(g)
+        .returns::<ObserveNextResult>("200", g),
+        Operation::new(
+            "get",
+            "/api/matches/{match_id}/buildable_cells",
+            "get_buildable_cells",
+        )
+        .summary("Get all buildable cell coordinates on the map")
+        .match_id()
+        .query::<SessionParams>(g)
+        .returns::<GetBuildableCellsResult>("200", g),
+        Operation::new("get", "/api/matches/{match_id}/path", "get_current_path")
+            .summary("Get the current path mobs follow from spawn to goal")
+            .match_id()
+            .query::<SessionParams>(g)
+            .returns::<GetCurrentPathResult>("200", g),
+        Operation::new("get", "/api/matches/{match_id}/map", "render_map")
+            .summary("Render the map as ASCII art with a legend, optionally cropped")
+            .match_id()
+            .query::<RenderMapQuery>(g)
+            .returns_text(),
+        Operation::new("get", "/api/matches/{match_id}/summary", "get_summary")
+            .summary("Get a concise summary of the match state")
+            .match_id()
+            .query::<SessionParams>(g)
+            .returns::<SummaryResult>("200", g),
+        Operation::new(
+            "get",
+            "/api/matches/{match_id}/placements",
+            "analyze_placements",
+        )
+        .summary("Rank tower cells by path coverage and path-length impact")
+        .match_id()
+        .query::<PlacementsQuery>(g)
+        .returns::<AnalyzePlacementsResult>("200", g),
+        Operation::new("post", "/api/matches/{match_id}/what_if", "what_if")
+            .summary("Predict the outcome of hypothetical actions on a fork of the match")
+            .match_id()
+            .body::<WhatIfBody>(g)
+            .returns::<WhatIfResult>("200", g),
+        Operation::new("post", "/api/maps/preview", "preview_map")
+            .summary("Preview the map a match with the same settings would start on")
+            .body::<PreviewMapParams>(g)
+            .returns::<PreviewMapResult>("200", g),
+    ];
+
+    let error = generator.subschema_for::<ErrorResult>().to_value();
+    let mut paths = Map::new();
+    for operation in &operations {
+        let item = paths
+            .entry(operation.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        item[operation.method] = operation.to_value(&error);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Tower Defense API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": { "schemas": generator.take_definitions(true) },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(r)) = map.get("$ref") {
+                    refs.push(r);
+                }
+                map.values().for_each(|v| collect_refs(v, refs));
+            }
+            Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_refs_resolve() {
+        let doc = document();
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+        assert!(schemas.contains_key("TdObservation"));
+
+        let mut refs = Vec::new();
+        collect_refs(&doc, &mut refs);
+        assert!(!refs.is_empty());
+        for r in refs {
+            let name = r.strip_prefix("#/components/schemas/").unwrap();
+            assert!(schemas.contains_key(name), "unresolved $ref {}", r);
+        }
+
+        let observe_next = &doc["paths"]["/api/matches/{match_id}/observe_next"]["get"];
+        let mut params: Vec<&str> = observe_next["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        params.sort_unstable();
+        assert_eq!(
+            params,
+            vec!["after_tick", "match_id", "max_wait_ms", "session_token"]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Match summaries for agents: the arithmetic an agent would otherwise have to do on a
+//! raw observation, precomputed from the game state.
+
+use crate::config::TowerKind;
+use crate::mcp::types::{MobProgress, SummaryResult, TowerCoverage, WaveThreat};
+use crate::observe::{self, path_cells_in_range};
+use crate::pathing::{self, CARDINAL_COST};
+use crate::world::{TdState, WavePhase};
+use std::cmp::{Ordering, Reverse};
+
+/// Mobs listed in [`SummaryResult::mobs_closest_to_goal`].
+const MAX_MOBS: usize = 5;
+
+pub fn summarize(state: &TdState) -> SummaryResult {
+    let config = &state.config;
+    let tick = state.tick;
+    let seconds = |ticks: u64| ticks as f32 / config.tick_hz as f32;
+
+    let tower_cost = config.build_cost(state.current_wave, TowerKind::Basic);
+    let path = pathing::mob_path_cells(state);
+
+    let (in_wave, seconds_until_next_wave) = match state.phase {
+        WavePhase::InWave { .. } => (true, None),
+        WavePhase::Pause { until_tick } if state.current_wave < config.waves_total => {
+            (false, Some(seconds(until_tick.saturating_sub(tick))))
+        }
+        WavePhase::Pause { .. } => (false, None),
+    };
+
+    let mut towers_near_path: Vec<TowerCoverage> = state
+        .world
+        .towers
+        .iter()
+        .filter_map(|(id, t)| {
+            let stats = config.tower_stats(t.kind, &t.branch_levels);
+            let cells = path_cells_in_range(&path, t.x, t.y, stats.range);
+            (cells > 0).then(|| TowerCoverage {
+                tower_id: observe::tower_id_to_string(id),
+                x: t.x,
+                y: t.y,
+                upgrade_level: t.upgrade_level,
+                damage: stats.damage,
+                path_cells_in_range: cells,
+            })
+        })
+        .collect();
+    towers_near_path.sort_by_key(|t| Reverse(t.path_cells_in_range));
+
+    let mut mobs_closest_to_goal: Vec<MobProgress> = state
+        .world
+        .mobs
+        .iter()
+        .map(|(id, m)| {
+            let (cx, cy) = (m.x.floor() as u16, m.y.floor() as u16);
+            let cells_to_goal = state
+                .world
+                .grid
+                .in_bounds(cx, cy)
+                .then(|| state.dist[state.world.grid.idx(cx, cy)])
+                .filter(|&d| d != u32::MAX)
+                .map(|d| d as f32 / CARDINAL_COST as f32);
+            MobProgress {
+                mob_id: observe::mob_id_to_string(id),
+                x: m.x,
+                y: m.y,
+                hp: m.hp,
+                max_hp: m.max_hp,
+                cells_to_goal,
+            }
+        })
+        .collect();
+    // Unreachable mobs last
+    mobs_closest_to_goal.sort_by(|a, b| match (a.cells_to_goal, b.cells_to_goal) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+    mobs_closest_to_goal.truncate(MAX_MOBS);
+
+    let next_wave = (state.current_wave < config.waves_total).then(|| {
+        let wave = state.current_wave + 1;
+        let mob_count = config.wave_size(wave, config.player_count);
+        let mob_hp = config.mob_hp(wave, config.player_count);
+        let spawn_seconds = seconds(config.duration_to_ticks(config.spawn_interval));
+
+        // Each tower shoots a mob for as long as it walks through range; with a stream
+        // of mobs, for as long as the whole wave takes to pass.
+        let mut damage_per_mob = 0.0;
+        let mut wave_damage_capacity = 0.0;
+        for t in state.world.towers.values() {
+            let stats = config.tower_stats(t.kind, &t.branch_levels);
+            let cells = path_cells_in_range(&path, t.x, t.y, stats.range);
+            if cells == 0 {
+                continue;
+            }
+            let dps =
+                stats.damage as f32 / seconds(config.duration_to_ticks(stats.fire_period).max(1));
+            let seconds_in_range = cells as f32 / config.mob_speed;
+            damage_per_mob += dps * seconds_in_range;
+            wave_damage_capacity +=
+                dps * (seconds_in_range + mob_count.saturating_sub(1) as f32 * spawn_seconds);
+        }
+
+        let total_hp = mob_count as u64 * mob_hp.max(0) as u64;
+        let threat = if damage_per_mob < mob_hp as f32 {
+            "high"
+        } else if wave_damage_capacity < total_hp as f32 {
+            "medium"
+        } else {
+            "low"
+        };
+
+        WaveThreat {
+            wave,
+            mob_count,
+            mob_hp,
+            total_hp,
+            damage_per_mob,
+            wave_damage_capacity,
+            threat: threat.to_string(),
+        }
+    });
+
+    SummaryResult {
+        tick,
+        current_wave: state.current_wave,
+        waves_total: config.waves_total,
+        in_wave,
+        seconds_until_next_wave,
+        gold: state.gold,
+        tower_cost,
+        affordable_towers: state.gold.checked_div(tower_cost).unwrap_or(0),
+        leaks: state.leaks,
+        max_leaks: config.max_leaks,
+        leaks_remaining: config.max_leaks.saturating_sub(state.leaks),
+        tower_count: state.world.towers.len() as u32,
+        path_length: path.len() as u32,
+        towers_near_path,
+        mobs_closest_to_goal,
+        next_wave,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TdConfig;
+    use crate::TdGame;
+    use sim_core::Game;
+
+    #[test]
+    fn summarize_new_match() {
+        let config = TdConfig::default();
+        let pause =
+            config.duration_to_ticks(config.inter_wave_pause) as f32 / config.tick_hz as f32;
+        let game = TdGame::new(config, 7);
+        let summary = summarize(game.state());
+
+        assert!(!summary.in_wave);
+        assert_eq!(summary.seconds_until_next_wave, Some(pause));
+        assert_eq!(summary.affordable_towers, summary.gold / summary.tower_cost);
+        assert_eq!(summary.leaks_remaining, summary.max_leaks);
+        assert!(summary.path_length > 0);
+        assert!(summary.towers_near_path.is_empty());
+
+        // Nothing defends the path yet
+        let next_wave = summary.next_wave.unwrap();
+        assert_eq!(next_wave.wave, 1);
+        assert_eq!(next_wave.damage_per_mob, 0.0);
+        assert_eq!(next_wave.threat, "high");
+    }
+}
@@ -1,7 +1,9 @@
-use crate::config::TowerKind;
+use crate::config::{BranchLevels, TowerKind, TowerStats, UpgradeBranch, SLOW_DURATION};
 use crate::events::TdEvent;
-use crate::pathing::{compute_distance_field, pick_next_target, MobMoveResult};
-use crate::world::{CellState, Mob, MobId, PendingBuild, TdState, Tower, TowerId, WavePhase};
+use crate::pathing::{pick_next_target, refresh_distance_field, MobMoveResult, CARDINAL_COST};
+use crate::world::{
+    CellState, Mob, MobId, PendingBuild, Targeting, TdState, Tower, TowerId, WavePhase,
+};
 use sim_core::{PlayerId, Tick};
 
 pub fn try_queue_build(
@@ -43,6 +45,7 @@ pub fn try_queue_build(
 
     state.gold -= cost;
     state.world.grid.set(x, y, CellState::Building);
+    state.dirty_cells.push((x, y));
 
     let build_ticks = state.config.duration_to_ticks(state.config.build_time);
     let complete_tick = tick + build_ticks;
@@ -62,13 +65,30 @@ pub fn try_queue_build(
 pub fn try_upgrade_tower(
     state: &mut TdState,
     tower_id: TowerId,
+    branch: UpgradeBranch,
     events: &mut Vec<TdEvent>,
 ) -> bool {
     let cost = {
-        let tower = match state.world.towers.get(tower_id) {
-            Some(t) => t,
-            None => return false,
+        let Some(tower) = state.world.towers.get(tower_id) else {
+            events.push(TdEvent::UpgradeRejected {
+                id: tower_id,
+                branch,
+                reason: "tower not found".to_string(),
+            });
+            return false;
         };
+        let (levels, total) = (&tower.branch_levels, tower.upgrade_level);
+        if let Some(reason) = state
+            .config
+            .upgrade_blocked(tower.kind, levels, total, branch)
+        {
+            events.push(TdEvent::UpgradeRejected {
+                id: tower_id,
+                branch,
+                reason: reason.to_string(),
+            });
+            return false;
+        }
         state.config.upgrade_cost(tower.upgrade_level)
     };
 
@@ -83,14 +103,96 @@ pub fn try_upgrade_tower(
     state.gold -= cost;
     let tower = state.world.towers.get_mut(tower_id).unwrap();
     tower.upgrade_level += 1;
+    tower.branch_levels[branch as usize] += 1;
+    // A higher max HP comes with the HP gained
+    let max_hp = state
+        .config
+        .tower_stats(tower.kind, &tower.branch_levels)
+        .max_hp;
+    tower.hp += max_hp - tower.max_hp;
+    tower.max_hp = max_hp;
 
     events.push(TdEvent::TowerUpgraded {
         id: tower_id,
+        branch,
         new_level: tower.upgrade_level,
     });
     true
 }
 
+/// Restore `tower_id` to full HP for the repair cost of its missing HP. Fails if the
+/// tower is missing, undamaged or unaffordable.
+pub fn try_repair_tower(state: &mut TdState, tower_id: TowerId, events: &mut Vec<TdEvent>) -> bool {
+    let reject = |events: &mut Vec<TdEvent>, reason: &str| {
+        events.push(TdEvent::RepairRejected {
+            id: tower_id,
+            reason: reason.to_string(),
+        });
+        false
+    };
+    let Some(tower) = state.world.towers.get(tower_id) else {
+        return reject(events, "tower not found");
+    };
+    let missing = tower.max_hp - tower.hp;
+    if missing <= 0 {
+        return reject(events, "tower is at full HP");
+    }
+    let cost = state.config.repair_cost(tower.kind, missing, tower.max_hp);
+
+    if state.gold < cost {
+        events.push(TdEvent::InsufficientGold {
+            cost,
+            have: state.gold,
+        });
+        return false;
+    }
+
+    state.gold -= cost;
+    let tower = state.world.towers.get_mut(tower_id).unwrap();
+    tower.hp = tower.max_hp;
+
+    events.push(TdEvent::TowerRepaired {
+        id: tower_id,
+        hp: tower.hp,
+        cost,
+    });
+    true
+}
+
+pub fn set_targeting(
+    state: &mut TdState,
+    tower_id: TowerId,
+    targeting: Targeting,
+    events: &mut Vec<TdEvent>,
+) -> bool {
+    let Some(tower) = state.world.towers.get_mut(tower_id) else {
+        events.push(TdEvent::TargetingRejected {
+            id: tower_id,
+            targeting,
+            reason: "tower not found".to_string(),
+        });
+        return false;
+    };
+    tower.targeting = targeting;
+    events.push(TdEvent::TargetingChanged {
+        id: tower_id,
+        targeting,
+    });
+    true
+}
+
+/// Between waves, once per second, give every tower its spec's `regen` HP back.
+pub fn regen_towers(state: &mut TdState, tick: Tick) {
+    let second = tick.is_multiple_of(state.config.tick_hz as u64);
+    if !second || !matches!(state.phase, WavePhase::Pause { .. }) {
+        return;
+    }
+    for tower in state.world.towers.values_mut() {
+        let regen = state.config.spec(tower.kind).regen;
+        tower.hp = (tower.hp + regen).min(tower.max_hp);
+    }
+}
+
 pub fn process_builds(state: &mut TdState, tick: Tick, events: &mut Vec<TdEvent>) -> bool {
     let mut towers_placed = false;
 
@@ -107,6 +209,8 @@ pub fn process_builds(state: &mut TdState, tick: Tick, events: &mut Vec<TdEvent>
                 next_fire_tick: tick,
                 player_id: build.player_id,
                 upgrade_level: 0,
+                branch_levels: BranchLevels::default(),
+                targeting: Targeting::default(),
             };
             let id = state.world.towers.insert(tower);
             state.world.grid.set(build.x, build.y, CellState::Tower(id));
@@ -157,15 +261,21 @@ pub fn update_wave(state: &mut TdState, tick: Tick, events: &mut Vec<TdEvent>) {
             next_spawn_tick,
         } => {
             if tick >= *next_spawn_tick && *spawned < *wave_size {
-                let spawn = state.config.spawn;
+                let spawn = state
+                    .config
+                    .spawn_for(state.current_wave, *spawned, *wave_size);
                 let mob_hp = state.config.mob_hp(state.current_wave, player_count);
                 state.world.mobs.insert(Mob {
                     x: spawn.0 as f32 + 0.5,
                     y: spawn.1 as f32 + 0.5,
                     hp: mob_hp,
+                    max_hp: mob_hp,
                     dmg: 1,
-                    speed: 2.0,
+                    speed: state.config.mob_speed,
                     target: spawn,
+                    spawn_tick: tick,
+                    slow: 0.0,
+                    slowed_until: 0,
                 });
                 *spawned += 1;
                 *next_spawn_tick =
@@ -190,7 +300,7 @@ pub fn update_wave(state: &mut TdState, tick: Tick, events: &mut Vec<TdEvent>) {
     }
 }
 
-pub fn move_mobs(state: &mut TdState, _tick: Tick, events: &mut Vec<TdEvent>) {
+pub fn move_mobs(state: &mut TdState, tick: Tick, events: &mut Vec<TdEvent>) {
     let dt = 1.0 / state.config.tick_hz as f32;
     let mob_ids: Vec<MobId> = state.world.mobs.keys().collect();
 
@@ -199,8 +309,7 @@ pub fn move_mobs(state: &mut TdState, _tick: Tick, events: &mut Vec<TdEvent>) {
 
     for mob_id in mob_ids {
         let mob = &state.world.mobs[mob_id];
-        let speed = mob.speed;
-        let step = speed * dt;
+        let step = mob.speed_at(tick) * dt;
         let tx = mob.target.0 as f32 + 0.5;
         let ty = mob.target.1 as f32 + 0.5;
         let dx = tx - mob.x;
@@ -249,6 +358,7 @@ pub fn move_mobs(state: &mut TdState, _tick: Tick, events: &mut Vec<TdEvent>) {
     for &tower_id in &destroyed_towers {
         if let Some(tower) = state.world.towers.remove(tower_id) {
             state.world.grid.set(tower.x, tower.y, CellState::Empty);
+            state.dirty_cells.push((tower.x, tower.y));
             events.push(TdEvent::TowerDestroyed {
                 id: tower_id,
                 x: tower.x,
@@ -257,9 +367,9 @@ pub fn move_mobs(state: &mut TdState, _tick: Tick, events: &mut Vec<TdEvent>) {
         }
     }
 
-    // Recompute distance field if towers were destroyed
+    // Update distance field if towers were destroyed
     if !destroyed_towers.is_empty() {
-        compute_distance_field(&state.world.grid, state.config.goal, &mut state.dist);
+        refresh_distance_field(state);
     }
 
     // Handle leaked mobs
@@ -271,9 +381,9 @@ pub fn move_mobs(state: &mut TdState, _tick: Tick, events: &mut Vec<TdEvent>) {
     }
 }
 
-pub fn tower_attacks(state: &mut TdState, tick: Tick, _events: &mut Vec<TdEvent>) {
+pub fn tower_attacks(state: &mut TdState, tick: Tick, events: &mut Vec<TdEvent>) {
     // Collect tower firing info (can't iterate and mutate simultaneously)
-    let tower_shots: Vec<(TowerId, u16, u16, f32, i32)> = state
+    let tower_shots: Vec<(TowerId, u16, u16, TowerStats, Targeting)> = state
         .world
         .towers
         .iter()
@@ -281,54 +391,98 @@ pub fn tower_attacks(state: &mut TdState, tick: Tick, _events: &mut Vec<TdEvent>
             if tick < tower.next_fire_tick {
                 return None;
             }
-            let spec = state.config.spec(tower.kind);
-            let damage = state.config.tower_damage(tower.kind, tower.upgrade_level);
-            Some((id, tower.x, tower.y, spec.range, damage))
+            let stats = state.config.tower_stats(tower.kind, &tower.branch_levels);
+            Some((id, tower.x, tower.y, stats, tower.targeting))
         })
         .collect();
 
-    for (tower_id, tx, ty, range, damage) in tower_shots {
-        if let Some(target_id) = find_tower_target(tx, ty, range, &state.world.mobs) {
-            state.world.mobs[target_id].hp -= damage;
-            let fire_period = state.config.spec(state.world.towers[tower_id].kind).fire_period;
+    let slow_ticks = state.config.duration_to_ticks(SLOW_DURATION);
+    for (tower_id, tx, ty, stats, targeting) in tower_shots {
+        if let Some(target_id) = find_tower_target(state, tx, ty, stats.range, targeting) {
+            let damage = stats.damage;
+            let mob = &mut state.world.mobs[target_id];
+            mob.hp -= damage;
+            if stats.slow > 0.0 {
+                // Each hit refreshes the slow, keeping the stronger of the active and new one
+                if tick >= mob.slowed_until || stats.slow >= mob.slow {
+                    mob.slow = stats.slow;
+                }
+                mob.slowed_until = tick + slow_ticks;
+            }
+            if state.config.combat_events {
+                events.push(TdEvent::TowerFired {
+                    tower_id,
+                    mob_id: target_id,
+                    damage,
+                });
+                events.push(TdEvent::MobDamaged {
+                    id: target_id,
+                    damage,
+                    hp: mob.hp,
+                });
+            }
             state.world.towers[tower_id].next_fire_tick =
-                tick + state.config.duration_to_ticks(fire_period);
+                tick + state.config.duration_to_ticks(stats.fire_period);
         }
     }
 }
 
+/// The mob in range of the tower at (`tx`, `ty`) that `targeting` picks. Ties go to the
+/// mob closest to the tower (least HP for [`Targeting::Closest`]), then to the first
+/// found.
 fn find_tower_target(
+    state: &TdState,
     tx: u16,
     ty: u16,
     range: f32,
-    mobs: &slotmap::SlotMap<MobId, Mob>,
+    targeting: Targeting,
 ) -> Option<MobId> {
     let range_sq = range * range;
     let tcx = tx as f32 + 0.5;
     let tcy = ty as f32 + 0.5;
-    let mut best: Option<(MobId, f32, i32)> = None;
+    // Lowest (primary, secondary) key wins
+    let mut best: Option<(MobId, f64, f64)> = None;
 
-    for (id, mob) in mobs.iter() {
+    for (id, mob) in state.world.mobs.iter() {
         let dx = mob.x - tcx;
         let dy = mob.y - tcy;
         let dist_sq = dx * dx + dy * dy;
+        if dist_sq > range_sq {
+            continue;
+        }
 
-        if dist_sq <= range_sq {
-            let dominated = match best {
-                None => false,
-                Some((_, best_dist, best_hp)) => {
-                    dist_sq < best_dist || (dist_sq == best_dist && mob.hp < best_hp)
-                }
-            };
-            if best.is_none() || dominated {
-                best = Some((id, dist_sq, mob.hp));
-            }
+        let (primary, secondary) = match targeting {
+            Targeting::First => (distance_to_goal(state, mob), dist_sq as f64),
+            Targeting::Last => (-distance_to_goal(state, mob), dist_sq as f64),
+            Targeting::Strongest => (-mob.hp as f64, dist_sq as f64),
+            Targeting::Weakest => (mob.hp as f64, dist_sq as f64),
+            Targeting::Closest => (dist_sq as f64, mob.hp as f64),
+        };
+        let better = match best {
+            None => true,
+            Some((_, p, s)) => primary < p || (primary == p && secondary < s),
+        };
+        if better {
+            best = Some((id, primary, secondary));
         }
     }
 
     best.map(|(id, _, _)| id)
 }
 
+/// Walking distance from `mob` to the nearest goal in cells, through the cell it is
+/// heading for; `f64::MAX` if that cell is cut off from every goal.
+fn distance_to_goal(state: &TdState, mob: &Mob) -> f64 {
+    let (x, y) = mob.target;
+    let dist = state.dist[state.world.grid.idx(x, y)];
+    if dist == u32::MAX {
+        return f64::MAX;
+    }
+    let dx = x as f32 + 0.5 - mob.x;
+    let dy = y as f32 + 0.5 - mob.y;
+    dist as f64 / CARDINAL_COST as f64 + (dx * dx + dy * dy).sqrt() as f64
+}
+
 pub fn remove_dead(state: &mut TdState, events: &mut Vec<TdEvent>) {
     let gold_per_kill = state.config.gold_per_kill(state.current_wave);
     let dead: Vec<MobId> = state
@@ -349,3 +503,265 @@ pub fn remove_dead(state: &mut TdState, events: &mut Vec<TdEvent>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TdConfig;
+    use crate::pathing::compute_distance_field;
+
+    /// A 10x1 corridor from (0,0) to (9,0) with a tower at (5,0) and three mobs in range.
+    fn corridor() -> (TdState, TowerId, [MobId; 3]) {
+        let config = TdConfig {
+            width: 10,
+            height: 1,
+            spawns: vec![(0, 0)],
+            goals: vec![(9, 0)],
+            ..TdConfig::default()
+        };
+        let mut state = TdState::with_terrain(config, vec![true; 10]);
+        compute_distance_field(&state.world.grid, &state.config.goals, &mut state.dist);
+
+        let tower = state.world.towers.insert(Tower {
+            x: 5,
+            y: 0,
+            kind: TowerKind::Basic,
+            hp: 100,
+            max_hp: 100,
+            next_fire_tick: 0,
+            player_id: 0,
+            upgrade_level: 0,
+            branch_levels: BranchLevels::default(),
+            targeting: Targeting::default(),
+        });
+        let mut mob = |x: f32, hp: i32| {
+            state.world.mobs.insert(Mob {
+                x,
+                y: 0.5,
+                hp,
+                max_hp: hp,
+                dmg: 1,
+                speed: 1.0,
+                target: (x as u16 + 1, 0),
+                spawn_tick: 0,
+                slow: 0.0,
+                slowed_until: 0,
+            })
+        };
+        // Far from the goal and strong; next to the tower; near the goal and weak
+        let mobs = [mob(2.5, 30), mob(5.5, 20), mob(7.5, 10)];
+        (state, tower, mobs)
+    }
+
+    #[test]
+    fn targeting_modes() {
+        let (state, _, [far, near_tower, near_goal]) = corridor();
+        let target = |targeting| find_tower_target(&state, 5, 0, 4.0, targeting);
+
+        assert_eq!(target(Targeting::First), Some(near_goal));
+        assert_eq!(target(Targeting::Last), Some(far));
+        assert_eq!(target(Targeting::Strongest), Some(far));
+        assert_eq!(target(Targeting::Weakest), Some(near_goal));
+        assert_eq!(target(Targeting::Closest), Some(near_tower));
+        // Nothing within range of the spawn
+        assert_eq!(find_tower_target(&state, 0, 0, 1.0, Targeting::First), None);
+    }
+
+    #[test]
+    fn set_targeting_changes_the_target() {
+        let (mut state, tower, [_, near_tower, near_goal]) = corridor();
+        let mut events = Vec::new();
+
+        tower_attacks(&mut state, 0, &mut events);
+        assert_eq!(state.world.mobs[near_tower].hp, 15);
+
+        events.clear();
+        assert!(set_targeting(
+            &mut state,
+            tower,
+            Targeting::First,
+            &mut events
+        ));
+        assert!(matches!(
+            events[..],
+            [TdEvent::TargetingChanged {
+                targeting: Targeting::First,
+                ..
+            }]
+        ));
+        let next_fire_tick = state.world.towers[tower].next_fire_tick;
+        tower_attacks(&mut state, next_fire_tick, &mut events);
+        assert_eq!(state.world.mobs[near_goal].hp, 5);
+
+        events.clear();
+        assert!(!set_targeting(
+            &mut state,
+            TowerId::default(),
+            Targeting::Last,
+            &mut events
+        ));
+        assert!(matches!(
+            &events[..],
+            [TdEvent::TargetingRejected {
+                targeting: Targeting::Last,
+                reason,
+                ..
+            }] if reason == "tower not found"
+        ));
+    }
+
+    #[test]
+    fn repair_and_regen_restore_tower_hp() {
+        let (mut state, tower, _) = corridor();
+        let mut events = Vec::new();
+
+        // Undamaged towers need no repair
+        assert!(!try_repair_tower(&mut state, tower, &mut events));
+        assert!(matches!(
+            &events[..],
+            [TdEvent::RepairRejected { id, reason }]
+                if *id == tower && reason == "tower is at full HP"
+        ));
+
+        events.clear();
+        assert!(!try_repair_tower(
+            &mut state,
+            TowerId::default(),
+            &mut events
+        ));
+        assert!(matches!(
+            &events[..],
+            [TdEvent::RepairRejected { reason, .. }] if reason == "tower not found"
+        ));
+
+        events.clear();
+        state.world.towers[tower].hp = 40;
+        state.gold = 4;
+        assert!(!try_repair_tower(&mut state, tower, &mut events));
+        assert!(matches!(
+            events[..],
+            [TdEvent::InsufficientGold { cost: 5, have: 4 }]
+        ));
+
+        events.clear();
+        state.gold = 10;
+        assert!(try_repair_tower(&mut state, tower, &mut events));
+        assert_eq!(state.gold, 5);
+        assert_eq!(state.world.towers[tower].hp, 100);
+        assert!(matches!(
+            events[..],
+            [TdEvent::TowerRepaired {
+                hp: 100,
+                cost: 5,
+                ..
+            }]
+        ));
+
+        state.config.basic_spec.regen = 30;
+        state.world.towers[tower].hp = 40;
+        state.phase = WavePhase::InWave {
+            spawned: 0,
+            wave_size: 1,
+            next_spawn_tick: 0,
+        };
+        regen_towers(&mut state, 0);
+        assert_eq!(state.world.towers[tower].hp, 40);
+
+        state.phase = WavePhase::Pause { until_tick: 1000 };
+        regen_towers(&mut state, 1);
+        assert_eq!(state.world.towers[tower].hp, 40);
+        regen_towers(&mut state, 60);
+        assert_eq!(state.world.towers[tower].hp, 70);
+        regen_towers(&mut state, 120);
+        assert_eq!(state.world.towers[tower].hp, 100);
+    }
+
+    #[test]
+    fn upgrade_branches() {
+        let (mut state, tower, _) = corridor();
+        let mut events = Vec::new();
+        state.gold = 1000;
+        state.world.towers[tower].hp = 90;
+
+        assert!(try_upgrade_tower(
+            &mut state,
+            tower,
+            UpgradeBranch::Hp,
+            &mut events
+        ));
+        let t = &state.world.towers[tower];
+        assert_eq!((t.upgrade_level, t.branch_levels), (1, [0, 0, 0, 1, 0]));
+        // Max HP 100 → 125, healing the 25 gained
+        assert_eq!((t.hp, t.max_hp), (115, 125));
+        assert_eq!(state.gold, 1000 - 24);
+
+        events.clear();
+        for _ in 0..3 {
+            assert!(try_upgrade_tower(
+                &mut state,
+                tower,
+                UpgradeBranch::Slow,
+                &mut events
+            ));
+        }
+        assert!(!try_upgrade_tower(
+            &mut state,
+            tower,
+            UpgradeBranch::Slow,
+            &mut events
+        ));
+        assert!(matches!(
+            &events[..],
+            [
+                TdEvent::TowerUpgraded { new_level: 2, .. },
+                TdEvent::TowerUpgraded { new_level: 3, .. },
+                TdEvent::TowerUpgraded {
+                    branch: UpgradeBranch::Slow,
+                    new_level: 4,
+                    ..
+                },
+                TdEvent::UpgradeRejected {
+                    branch: UpgradeBranch::Slow,
+                    ..
+                },
+            ]
+        ));
+        assert_eq!(state.world.towers[tower].upgrade_level, 4);
+    }
+
+    #[test]
+    fn slowing_towers_slow_their_target() {
+        let (mut state, tower, [_, near_tower, _]) = corridor();
+        state.world.towers[tower].branch_levels[UpgradeBranch::Slow as usize] = 2;
+        let mut events = Vec::new();
+
+        tower_attacks(&mut state, 10, &mut events);
+        let mob = &state.world.mobs[near_tower];
+        // 2 levels of 15%, for a second at 60 Hz
+        assert!((mob.speed_at(10) - 0.7).abs() < 1e-6);
+        assert!((mob.speed_at(69) - 0.7).abs() < 1e-6);
+        assert_eq!(mob.speed_at(70), 1.0);
+    }
+
+    #[test]
+    fn tower_attacks_emit_combat_events() {
+        let (mut state, tower, [_, near_tower, _]) = corridor();
+        let mut events = Vec::new();
+
+        tower_attacks(&mut state, 0, &mut events);
+        assert!(matches!(
+            events[..],
+            [
+                TdEvent::TowerFired { tower_id, mob_id, damage: 5 },
+                TdEvent::MobDamaged { id, damage: 5, hp: 15 },
+            ] if tower_id == tower && mob_id == near_tower && id == near_tower
+        ));
+
+        state.config.combat_events = false;
+        events.clear();
+        let next_fire_tick = state.world.towers[tower].next_fire_tick;
+        tower_attacks(&mut state, next_fire_tick, &mut events);
+        assert_eq!(state.world.mobs[near_tower].hp, 10);
+        assert!(events.is_empty());
+    }
+}
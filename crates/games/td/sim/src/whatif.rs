@@ -0,0 +1,247 @@
+//! What-if simulation: apply hypothetical actions to a fork of a match and fast-forward
+//! it headlessly. The simulation is deterministic, so the prediction is exact as long as
+//! nobody acts on the real match in the meantime.
+
+use crate::actions::TdAction;
+use crate::events::TdEvent;
+use crate::mcp::types::WhatIfResult;
+use crate::observe;
+use crate::world::WavePhase;
+use crate::TdGame;
+use sim_core::{ActionEnvelope, Game, PlayerId, TerminalOutcome};
+
+/// Cap on simulated game time, so every simulation finishes quickly.
+pub const MAX_SIMULATED_SECONDS: u64 = 600;
+
+/// How far to fast-forward.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Horizon {
+    Ticks(u64),
+    /// Until the current wave ends, or the next one if between waves.
+    ThroughNextWave,
+}
+
+/// Apply `actions` for `player_id` on the tick after the fork, then step until the
+/// horizon, the end of the match or [`MAX_SIMULATED_SECONDS`].
+///
+/// Actions already submitted to the real match for future ticks are not included.
+pub fn simulate(
+    mut game: TdGame,
+    player_id: PlayerId,
+    actions: Vec<TdAction>,
+    horizon: Horizon,
+) -> WhatIfResult {
+    let state = game.state();
+    let start_tick = state.tick;
+    let gold_start = state.gold;
+    let max_ticks = state.config.tick_hz as u64 * MAX_SIMULATED_SECONDS;
+    let target_wave = match state.phase {
+        WavePhase::InWave { .. } => state.current_wave,
+        WavePhase::Pause { .. } => state.current_wave + 1,
+    };
+
+    let mut envelopes: Vec<_> = actions
+        .into_iter()
+        .enumerate()
+        .map(|(i, action)| ActionEnvelope {
+            player_id,
+            action_id: i as u64 + 1,
+            intended_tick: start_tick + 1,
+            payload: action,
+        })
+        .collect();
+
+    let mut result = WhatIfResult {
+        start_tick,
+        end_tick: start_tick,
+        stopped: String::new(),
+        rejected_actions: Vec::new(),
+        kills: 0,
+        leaks: 0,
+        leaks_remaining: 0,
+        gold_start,
+        gold_end: gold_start,
+        towers_built: 0,
+        towers_lost: 0,
+        current_wave: state.current_wave,
+    };
+
+    let mut events = Vec::new();
+    let mut wave_ended = false;
+    let mut tick = start_tick;
+    let stopped = loop {
+        if let Some(outcome) = game.is_terminal() {
+            break match outcome {
+                TerminalOutcome::Win => "match_won",
+                _ => "match_lost",
+            };
+        }
+        if wave_ended {
+            break "wave_ended";
+        }
+        let elapsed = tick - start_tick;
+        match horizon {
+            Horizon::Ticks(n) if elapsed >= n.min(max_ticks) => {
+                break if n > max_ticks { "time_limit" } else { "ticks" };
+            }
+            _ if elapsed >= max_ticks => break "time_limit",
+            _ => {}
+        }
+
+        tick += 1;
+        let actions = if tick == start_tick + 1 {
+            std::mem::take(&mut envelopes)
+        } else {
+            Vec::new()
+        };
+        events.clear();
+        game.step(tick, &actions, &mut events);
+
+        for event in &events {
+            match event {
+                TdEvent::MobKilled { .. } => result.kills += 1,
+                TdEvent::MobLeaked { .. } => result.leaks += 1,
+                TdEvent::TowerPlaced { .. } => result.towers_built += 1,
+                TdEvent::TowerDestroyed { .. } => result.towers_lost += 1,
+                TdEvent::WaveEnded { wave } => {
+                    wave_ended |= horizon == Horizon::ThroughNextWave && *wave >= target_wave;
+                }
+                TdEvent::BuildRejected { x, y, reason } => result
+                    .rejected_actions
+                    .push(format!("place tower at ({},{}): {}", x, y, reason)),
+                TdEvent::UpgradeRejected { id, branch, reason } => {
+                    result.rejected_actions.push(format!(
+                        "upgrade tower '{}' ({}): {}",
+                        observe::tower_id_to_string(*id),
+                        observe::branch_to_string(*branch),
+                        reason
+                    ))
+                }
+                TdEvent::RepairRejected { id, reason } => result.rejected_actions.push(format!(
+                    "repair tower '{}': {}",
+                    observe::tower_id_to_string(*id),
+                    reason
+                )),
+                TdEvent::TargetingRejected {
+                    id,
+                    targeting,
+                    reason,
+                } => result.rejected_actions.push(format!(
+                    "set targeting of tower '{}' to {}: {}",
+                    observe::tower_id_to_string(*id),
+                    observe::targeting_to_string(*targeting),
+                    reason
+                )),
+                TdEvent::InsufficientGold { cost, have } => result
+                    .rejected_actions
+                    .push(format!("insufficient gold (need {}, have {})", cost, have)),
+                _ => {}
+            }
+        }
+    };
+
+    let state = game.state();
+    result.end_tick = tick;
+    result.stopped = stopped.to_string();
+    result.leaks_remaining = state.config.max_leaks.saturating_sub(state.leaks);
+    result.gold_end = state.gold;
+    result.current_wave = state.current_wave;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TdConfig;
+    use crate::pathing;
+
+    fn new_game() -> TdGame {
+        let config = TdConfig {
+            tick_hz: 20,
+            waves_total: 3,
+            ..TdConfig::default()
+        };
+        TdGame::new(config, 7)
+    }
+
+    #[test]
+    fn simulate_is_deterministic_and_leaves_fork_untouched() {
+        let game = new_game();
+        let a = simulate(game.clone(), 0, Vec::new(), Horizon::ThroughNextWave);
+        let b = simulate(game.clone(), 0, Vec::new(), Horizon::ThroughNextWave);
+
+        assert_eq!(a.stopped, "wave_ended");
+        assert_eq!(a.current_wave, 1);
+        // Nothing defends the path, so every mob of the wave leaks
+        assert_eq!((a.kills, a.leaks), (0, 8));
+        assert_eq!(
+            (a.end_tick, a.kills, a.leaks, a.gold_end),
+            (b.end_tick, b.kills, b.leaks, b.gold_end)
+        );
+        assert_eq!(game.state().tick, 0);
+    }
+
+    #[test]
+    fn simulate_towers_on_path() {
+        let game = new_game();
+        // On the path, where mobs can walk around it
+        let route = pathing::mob_routes(game.state()).remove(0).unwrap();
+        let chokepoints = pathing::chokepoints(game.state());
+        let &(x, y) = route.cells[route.cells.len() / 2..]
+            .iter()
+            .find(|cell| !chokepoints.contains(cell))
+            .unwrap();
+        let actions = vec![
+            TdAction::PlaceTower {
+                x,
+                y,
+                kind: crate::TowerKind::Basic,
+            },
+            // Same cell again: rejected
+            TdAction::PlaceTower {
+                x,
+                y,
+                kind: crate::TowerKind::Basic,
+            },
+        ];
+
+        let baseline = simulate(game.clone(), 0, Vec::new(), Horizon::ThroughNextWave);
+        let result = simulate(game, 0, actions, Horizon::ThroughNextWave);
+
+        assert_eq!(result.towers_built, 1);
+        assert_eq!(result.rejected_actions.len(), 1);
+        assert!(result.kills > baseline.kills);
+        assert!(result.leaks < baseline.leaks);
+    }
+
+    #[test]
+    fn simulate_rejects_actions_on_unknown_towers() {
+        let tower_id = crate::TowerId::default();
+        let actions = vec![
+            TdAction::UpgradeTower {
+                tower_id,
+                branch: crate::UpgradeBranch::Damage,
+            },
+            TdAction::RepairTower { tower_id },
+            TdAction::SetTargeting {
+                tower_id,
+                targeting: crate::Targeting::Strongest,
+            },
+        ];
+
+        let result = simulate(new_game(), 0, actions, Horizon::Ticks(1));
+        assert_eq!(result.rejected_actions.len(), 3);
+        assert!(result
+            .rejected_actions
+            .iter()
+            .all(|reason| reason.ends_with(": tower not found")));
+    }
+
+    #[test]
+    fn simulate_fixed_ticks() {
+        let result = simulate(new_game(), 0, Vec::new(), Horizon::Ticks(10));
+        assert_eq!(result.stopped, "ticks");
+        assert_eq!(result.end_tick, 10);
+        assert_eq!(result.current_wave, 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::{TdConfig, TowerKind};
+use crate::config::{BranchLevels, TdConfig, TowerKind};
 use sim_core::{PlayerId, Tick};
 use slotmap::{new_key_type, SlotMap};
 use std::collections::VecDeque;
@@ -75,6 +75,32 @@ impl Grid {
     }
 }
 
+/// Which mob in range a tower shoots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Targeting {
+    /// Closest to the goal along the path.
+    First,
+    /// Furthest from the goal along the path.
+    Last,
+    /// Most HP.
+    Strongest,
+    /// Least HP.
+    Weakest,
+    /// Closest to the tower, least HP among equals.
+    #[default]
+    Closest,
+}
+
+impl Targeting {
+    pub const ALL: [Targeting; 5] = [
+        Targeting::First,
+        Targeting::Last,
+        Targeting::Strongest,
+        Targeting::Weakest,
+        Targeting::Closest,
+    ];
+}
+
 #[derive(Clone, Debug)]
 pub struct Tower {
     pub x: u16,
@@ -84,7 +110,10 @@ pub struct Tower {
     pub max_hp: i32,
     pub next_fire_tick: Tick,
     pub player_id: PlayerId,
+    /// Upgrades taken, across all branches.
     pub upgrade_level: u8,
+    pub branch_levels: BranchLevels,
+    pub targeting: Targeting,
 }
 
 #[derive(Clone, Debug)]
@@ -92,10 +121,26 @@ pub struct Mob {
     pub x: f32,
     pub y: f32,
     pub hp: i32,
+    pub max_hp: i32,
     pub dmg: i32,
     pub speed: f32,
     /// Next grid cell this mob is walking toward.
     pub target: (u16, u16),
+    pub spawn_tick: Tick,
+    /// Fraction of `speed` lost until `slowed_until`.
+    pub slow: f32,
+    pub slowed_until: Tick,
+}
+
+impl Mob {
+    /// Speed at `tick`, after any slow from tower hits.
+    pub fn speed_at(&self, tick: Tick) -> f32 {
+        if tick < self.slowed_until {
+            self.speed * (1.0 - self.slow)
+        } else {
+            self.speed
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -156,6 +201,8 @@ pub struct TdState {
     pub phase: WavePhase,
     pub leaks: u16,
     pub dist: Vec<u32>,
+    /// Cells blocked or unblocked since `dist` was last updated.
+    pub dirty_cells: Vec<(u16, u16)>,
     pub gold: u32,
 }
 
@@ -174,6 +221,7 @@ impl TdState {
             },
             leaks: 0,
             dist: vec![u32::MAX; size],
+            dirty_cells: Vec::new(),
             gold: gold_start,
             config,
         }
@@ -193,6 +241,7 @@ impl TdState {
             },
             leaks: 0,
             dist: vec![u32::MAX; size],
+            dirty_cells: Vec::new(),
             gold: gold_start,
             config,
         }
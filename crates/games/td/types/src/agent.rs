@@ -0,0 +1,137 @@
+//! Messages of the agent WebSocket protocol.
+//!
+//! An agent connects to `/api/agent/ws/{match_id}` and is joined as a new player. The
+//! server replies with [`AgentMessage::Joined`], then pushes an [`AgentMessage::Observation`]
+//! on every decision tick. The agent sends [`AgentRequest`]s on the same connection; each
+//! action is answered with [`AgentMessage::ActionAck`] or [`AgentMessage::ActionRejected`]
+//! carrying the agent-chosen `request_id`. All messages are JSON text frames.
+
+use crate::{GameEventRecord, MatchStatusInfo, TdObservation};
+use serde::{Deserialize, Serialize};
+
+fn default_tower_type() -> String {
+    "Basic".to_string()
+}
+
+/// Message from an agent to the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum AgentRequest {
+    /// Queue a tower build.
+    PlaceTower {
+        /// Echoed back in the acknowledgement.
+        #[serde(default)]
+        request_id: u64,
+        /// The tick at which this action should be executed. Use 0 to execute immediately.
+        #[serde(default)]
+        intended_tick: u64,
+        x: u16,
+        y: u16,
+        #[serde(default = "default_tower_type")]
+        tower_type: String,
+    },
+    /// Upgrade an existing tower.
+    UpgradeTower {
+        /// Echoed back in the acknowledgement.
+        #[serde(default)]
+        request_id: u64,
+        /// The tick at which this action should be executed. Use 0 to execute immediately.
+        #[serde(default)]
+        intended_tick: u64,
+        tower_id: String,
+        /// damage (default), range, fire_rate, hp or slow.
+        #[serde(default)]
+        branch: Option<String>,
+    },
+    /// Restore a damaged tower to full HP.
+    RepairTower {
+        /// Echoed back in the acknowledgement.
+        #[serde(default)]
+        request_id: u64,
+        /// The tick at which this action should be executed. Use 0 to execute immediately.
+        #[serde(default)]
+        intended_tick: u64,
+        tower_id: String,
+    },
+    /// Choose which mob in range a tower shoots.
+    SetTargeting {
+        /// Echoed back in the acknowledgement.
+        #[serde(default)]
+        request_id: u64,
+        /// The tick at which this action should be executed. Use 0 to execute immediately.
+        #[serde(default)]
+        intended_tick: u64,
+        tower_id: String,
+        /// first, last, strongest, weakest or closest.
+        targeting: String,
+    },
+    /// Leave the match and close the connection.
+    Leave,
+}
+
+/// Message from the server to an agent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum AgentMessage {
+    /// Sent once after the connection joined the match.
+    Joined {
+        match_id: u64,
+        session_token: u64,
+        player_id: u8,
+    },
+    /// State at a decision tick, plus the events since the previous observation.
+    /// `walkable` is only included in the first observation since terrain never changes.
+    Observation {
+        observation: Box<TdObservation>,
+        events: Vec<GameEventRecord>,
+    },
+    /// The action was accepted and will execute at `scheduled_tick`.
+    ActionAck {
+        request_id: u64,
+        action_id: u64,
+        scheduled_tick: u64,
+    },
+    /// The action was not submitted.
+    ActionRejected { request_id: u64, reason: String },
+    /// The match finished or was terminated. The server closes the connection next.
+    MatchEnded { status: MatchStatusInfo },
+    /// A request could not be handled (e.g. malformed JSON), or joining failed.
+    Error { message: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_defaults() {
+        let request: AgentRequest =
+            serde_json::from_str(r#"{"type":"PlaceTower","x":3,"y":4}"#).unwrap();
+        assert_eq!(
+            request,
+            AgentRequest::PlaceTower {
+                request_id: 0,
+                intended_tick: 0,
+                x: 3,
+                y: 4,
+                tower_type: "Basic".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_ack_serialization() {
+        let ack = AgentMessage::ActionAck {
+            request_id: 7,
+            action_id: 1,
+            scheduled_tick: 40,
+        };
+        let json = serde_json::to_string(&ack).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"ActionAck","request_id":7,"action_id":1,"scheduled_tick":40}"#
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Delta-compressed observation stream for viewers.
+//!
+//! A stream starts with a [`ViewerFrame::Keyframe`] carrying a full observation, followed
+//! by [`ViewerFrame::Delta`] frames that each describe the changes from the previous tick.
+//! Static map data (size, spawns, goals, walkable grid) is only sent in keyframes unless it
+//! actually changes.
+
+use crate::{MobInfo, PendingBuildInfo, TdEventInfo, TdObservation, TowerInfo, WaveStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// One message of the viewer stream.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum ViewerFrame {
+    /// Full state. Replaces whatever the viewer had.
+    Keyframe(TdObservation),
+    /// Changes relative to the observation at `base_tick`.
+    Delta(ObservationDelta),
+}
+
+/// Changes between two consecutive observations. Unchanged fields are omitted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ObservationDelta {
+    /// Tick of the observation this delta applies to.
+    pub base_tick: u64,
+    /// Tick of the resulting observation.
+    pub tick: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gold: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaks: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_wave: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wave_status: Option<WaveStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tower_cost: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tower_damage: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gold_per_mob_kill: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub walkable: Option<Vec<bool>>,
+
+    /// Towers that were added or changed, keyed by `id`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub towers_changed: Vec<TowerInfo>,
+    /// IDs of towers that no longer exist.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub towers_removed: Vec<String>,
+    /// Mobs that spawned or changed (moved, took damage), keyed by `id`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mobs_changed: Vec<MobInfo>,
+    /// IDs of mobs that died or leaked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mobs_removed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_queue: Option<Vec<PendingBuildInfo>>,
+
+    /// Events since `base_tick`, for effects such as attack lines. Not part of the
+    /// observation; [`TdObservation::apply_delta`] ignores them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<TdEventInfo>,
+}
+
+/// Error applying a delta to an observation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeltaError {
+    /// The delta was computed against a different tick; the viewer needs a keyframe.
+    BaseMismatch { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeltaError::BaseMismatch { expected, actual } => write!(
+                f,
+                "delta base tick {} does not match observation tick {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+fn changed<T: Clone + PartialEq>(prev: &T, next: &T) -> Option<T> {
+    (prev != next).then(|| next.clone())
+}
+
+/// Entities with a stable string ID (towers, mobs).
+trait Keyed: Clone + PartialEq {
+    fn key(&self) -> &str;
+}
+
+impl Keyed for TowerInfo {
+    fn key(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Keyed for MobInfo {
+    fn key(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Entities added or changed in `next`, and IDs missing from `next`.
+fn diff_by_id<T: Keyed>(prev: &[T], next: &[T]) -> (Vec<T>, Vec<String>) {
+    let prev_by_id: HashMap<&str, &T> = prev.iter().map(|e| (e.key(), e)).collect();
+    let next_ids: HashSet<&str> = next.iter().map(|e| e.key()).collect();
+
+    let changed = next
+        .iter()
+        .filter(|e| prev_by_id.get(e.key()) != Some(e))
+        .cloned()
+        .collect();
+    let removed = prev
+        .iter()
+        .filter(|e| !next_ids.contains(e.key()))
+        .map(|e| e.key().to_string())
+        .collect();
+    (changed, removed)
+}
+
+fn apply_by_id<T: Keyed>(entities: &mut Vec<T>, changed: &[T], removed: &[String]) {
+    if !removed.is_empty() {
+        let removed: HashSet<&str> = removed.iter().map(String::as_str).collect();
+        entities.retain(|e| !removed.contains(e.key()));
+    }
+    let mut index: HashMap<String, usize> = entities
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.key().to_string(), i))
+        .collect();
+    for entity in changed {
+        match index.get(entity.key()) {
+            Some(&i) => entities[i] = entity.clone(),
+            None => {
+                index.insert(entity.key().to_string(), entities.len());
+                entities.push(entity.clone());
+            }
+        }
+    }
+}
+
+impl ObservationDelta {
+    /// Compute the delta that turns `prev` into `next`.
+    pub fn between(prev: &TdObservation, next: &TdObservation) -> Self {
+        let (towers_changed, towers_removed) = diff_by_id(&prev.towers, &next.towers);
+        let (mobs_changed, mobs_removed) = diff_by_id(&prev.mobs, &next.mobs);
+
+        Self {
+            base_tick: prev.tick,
+            tick: next.tick,
+            gold: changed(&prev.gold, &next.gold),
+            leaks: changed(&prev.leaks, &next.leaks),
+            current_wave: changed(&prev.current_wave, &next.current_wave),
+            wave_status: changed(&prev.wave_status, &next.wave_status),
+            tower_cost: changed(&prev.tower_cost, &next.tower_cost),
+            tower_damage: changed(&prev.tower_damage, &next.tower_damage),
+            gold_per_mob_kill: changed(&prev.gold_per_mob_kill, &next.gold_per_mob_kill),
+            walkable: changed(&prev.walkable, &next.walkable),
+            towers_changed,
+            towers_removed,
+            mobs_changed,
+            mobs_removed,
+            build_queue: changed(&prev.build_queue, &next.build_queue),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl TdObservation {
+    /// Apply a delta in place. Fails without modifying `self` if the delta was not
+    /// computed against this observation's tick.
+    pub fn apply_delta(&mut self, delta: &ObservationDelta) -> Result<(), DeltaError> {
+        if delta.base_tick != self.tick {
+            return Err(DeltaError::BaseMismatch {
+                expected: delta.base_tick,
+                actual: self.tick,
+            });
+        }
+
+        self.tick = delta.tick;
+        if let Some(gold) = delta.gold {
+            self.gold = gold;
+        }
+        if let Some(leaks) = delta.leaks {
+            self.leaks = leaks;
+        }
+        if let Some(current_wave) = delta.current_wave {
+            self.current_wave = current_wave;
+        }
+        if let Some(wave_status) = &delta.wave_status {
+            self.wave_status = wave_status.clone();
+        }
+        if let Some(tower_cost) = delta.tower_cost {
+            self.tower_cost = tower_cost;
+        }
+        if let Some(tower_damage) = delta.tower_damage {
+            self.tower_damage = tower_damage;
+        }
+        if let Some(gold_per_mob_kill) = delta.gold_per_mob_kill {
+            self.gold_per_mob_kill = gold_per_mob_kill;
+        }
+        if let Some(walkable) = &delta.walkable {
+            self.walkable = walkable.clone();
+        }
+
+        apply_by_id(&mut self.towers, &delta.towers_changed, &delta.towers_removed);
+        apply_by_id(&mut self.mobs, &delta.mobs_changed, &delta.mobs_removed);
+        if let Some(build_queue) = &delta.build_queue {
+            self.build_queue = build_queue.clone();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    fn tower(id: &str, x: u16, hp: i32) -> TowerInfo {
+        TowerInfo {
+            id: id.to_string(),
+            x,
+            y: 0,
+            hp,
+            max_hp: 100,
+            tower_type: "Basic".to_string(),
+            player_id: 0,
+            upgrade_level: 0,
+            max_upgrade_level: 10,
+            upgrades: Vec::new(),
+            damage: 5,
+            range: 3.0,
+            fire_period_ticks: 20,
+            slow: 0.0,
+            upgrade_cost: 50,
+            repair_cost: 0,
+            targeting: "closest".to_string(),
+        }
+    }
+
+    fn mob(id: &str, x: f32) -> MobInfo {
+        MobInfo {
+            id: id.to_string(),
+            x,
+            y: 0.5,
+            hp: 10,
+            max_hp: 10,
+            speed: 2.0,
+            target: Position { x: 1, y: 0 },
+            spawn_tick: 0,
+        }
+    }
+
+    fn observation(tick: u64) -> TdObservation {
+        TdObservation {
+            tick,
+            ticks_per_second: 20,
+            map_width: 4,
+            map_height: 1,
+            spawns: vec![Position { x: 0, y: 0 }],
+            goals: vec![Position { x: 3, y: 0 }],
+            max_leaks: 10,
+            tower_cost: 25,
+            tower_range: 3.0,
+            tower_damage: 5,
+            build_time_ticks: 20,
+            gold_per_mob_kill: 1,
+            gold: 100,
+            leaks: 0,
+            current_wave: 1,
+            waves_total: 5,
+            wave_status: WaveStatus::default(),
+            walkable: vec![true; 4],
+            towers: vec![tower("1", 1, 100), tower("2", 2, 100)],
+            mobs: vec![],
+            build_queue: vec![],
+        }
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let prev = observation(10);
+        let mut next = observation(11);
+        next.gold = 75;
+        next.towers = vec![tower("2", 2, 80), tower("3", 3, 100)];
+        next.mobs = vec![mob("7", 0.5)];
+
+        let delta = ObservationDelta::between(&prev, &next);
+        assert_eq!(delta.gold, Some(75));
+        assert_eq!(delta.leaks, None);
+        assert_eq!(delta.walkable, None);
+        assert_eq!(delta.towers_removed, vec!["1".to_string()]);
+        assert_eq!(delta.towers_changed.len(), 2);
+
+        let mut applied = prev.clone();
+        applied.apply_delta(&delta).unwrap();
+        assert_eq!(applied, next);
+    }
+
+    #[test]
+    fn test_mobs_tracked_by_id() {
+        let mut prev = observation(10);
+        prev.mobs = vec![mob("1", 0.5), mob("2", 1.5), mob("3", 2.5)];
+        let mut next = observation(11);
+        next.mobs = vec![mob("1", 0.5), mob("3", 2.6), mob("4", 0.5)];
+
+        let delta = ObservationDelta::between(&prev, &next);
+        let changed: Vec<&str> = delta.mobs_changed.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(changed, vec!["3", "4"]);
+        assert_eq!(delta.mobs_removed, vec!["2".to_string()]);
+
+        let mut applied = prev.clone();
+        applied.apply_delta(&delta).unwrap();
+        assert_eq!(applied, next);
+    }
+
+    #[test]
+    fn test_delta_rejects_wrong_base() {
+        let prev = observation(10);
+        let next = observation(11);
+        let delta = ObservationDelta::between(&prev, &next);
+
+        let mut stale = observation(9);
+        assert_eq!(
+            stale.apply_delta(&delta),
+            Err(DeltaError::BaseMismatch {
+                expected: 10,
+                actual: 9
+            })
+        );
+        assert_eq!(stale, observation(9));
+    }
+
+    #[test]
+    fn test_frame_serialization_is_tagged() {
+        let delta = ObservationDelta {
+            base_tick: 1,
+            tick: 2,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&ViewerFrame::Delta(delta.clone())).unwrap();
+        assert_eq!(json, r#"{"type":"Delta","base_tick":1,"tick":2}"#);
+        let parsed: ViewerFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, ViewerFrame::Delta(delta));
+    }
+}
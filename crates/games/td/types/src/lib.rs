@@ -5,8 +5,14 @@
 
 use serde::{Deserialize, Serialize};
 
+mod agent;
+mod delta;
+
+pub use agent::{AgentMessage, AgentRequest};
+pub use delta::{DeltaError, ObservationDelta, ViewerFrame};
+
 /// Position on the map.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Position {
     pub x: u16,
@@ -14,7 +20,7 @@ pub struct Position {
 }
 
 /// Current wave status.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type")]
 pub enum WaveStatus {
@@ -48,31 +54,66 @@ impl Default for WaveStatus {
 }
 
 /// Information about a tower.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TowerInfo {
     pub id: String,
     pub x: u16,
     pub y: u16,
     pub hp: i32,
+    pub max_hp: i32,
     pub tower_type: String,
     pub player_id: u8,
+    /// Upgrades taken, across all branches.
     pub upgrade_level: u8,
+    /// Upgrades the tower can take in total.
+    pub max_upgrade_level: u8,
+    /// The branches the tower can be upgraded in, with its level in each.
+    pub upgrades: Vec<UpgradeBranchInfo>,
+    /// Damage per shot, after upgrades.
     pub damage: i32,
+    /// Range in cells, after upgrades.
+    pub range: f32,
+    /// Ticks between shots, after upgrades.
+    pub fire_period_ticks: u64,
+    /// Fraction of their speed that mobs hit by the tower lose for a second.
+    pub slow: f32,
     pub upgrade_cost: u32,
+    /// Gold to restore the tower to `max_hp`; 0 when undamaged.
+    pub repair_cost: u32,
+    /// Which mob in range the tower shoots: first, last, strongest, weakest or closest.
+    pub targeting: String,
+}
+
+/// A tower's level in one upgrade branch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UpgradeBranchInfo {
+    /// damage, range, fire_rate, hp or slow.
+    pub branch: String,
+    pub level: u8,
+    pub max_level: u8,
 }
 
 /// Information about a mob.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MobInfo {
+    /// Unique for the match: a dead mob's ID is never given to another mob.
+    pub id: String,
     pub x: f32,
     pub y: f32,
     pub hp: i32,
+    pub max_hp: i32,
+    /// Movement speed in cells per second, after any slow.
+    pub speed: f32,
+    /// Next grid cell the mob is walking toward.
+    pub target: Position,
+    pub spawn_tick: u64,
 }
 
 /// Information about a pending build.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PendingBuildInfo {
     pub x: u16,
@@ -83,7 +124,7 @@ pub struct PendingBuildInfo {
 }
 
 /// Full game state observation.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TdObservation {
     pub tick: u64,
@@ -91,8 +132,10 @@ pub struct TdObservation {
 
     pub map_width: u16,
     pub map_height: u16,
-    pub spawn: Position,
-    pub goal: Position,
+    /// Where mobs enter.
+    pub spawns: Vec<Position>,
+    /// Where mobs leak; each mob heads for the nearest.
+    pub goals: Vec<Position>,
 
     pub max_leaks: u16,
     pub tower_cost: u32,
@@ -116,6 +159,47 @@ pub struct TdObservation {
     pub build_queue: Vec<PendingBuildInfo>,
 }
 
+/// A game event, as pushed to agents.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum TdEventInfo {
+    TowerPlaced {
+        tower_id: String,
+        x: u16,
+        y: u16,
+        tower_type: String,
+    },
+    TowerDestroyed { tower_id: String, x: u16, y: u16 },
+    MobKilled { mob_id: String, x: f32, y: f32 },
+    MobLeaked { mob_id: String },
+    WaveStarted { wave: u8 },
+    WaveEnded { wave: u8 },
+    BuildQueued { x: u16, y: u16, tower_type: String },
+    InsufficientGold { cost: u32, have: u32 },
+    TowerUpgraded { tower_id: String, branch: String, new_level: u8 },
+    UpgradeRejected { tower_id: String, branch: String, reason: String },
+    TowerRepaired { tower_id: String, hp: i32, cost: u32 },
+    RepairRejected { tower_id: String, reason: String },
+    TargetingChanged { tower_id: String, targeting: String },
+    TargetingRejected { tower_id: String, targeting: String, reason: String },
+    TowerFired { tower_id: String, mob_id: String, damage: i32 },
+    /// `hp` is what the mob has left; at or below zero it dies this tick.
+    MobDamaged { mob_id: String, damage: i32, hp: i32 },
+    BuildRejected { x: u16, y: u16, reason: String },
+}
+
+/// A game event together with when it happened.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GameEventRecord {
+    /// Per-match sequence number, increasing by one per event.
+    pub sequence: u64,
+    pub tick: u64,
+    #[serde(flatten)]
+    pub event: TdEventInfo,
+}
+
 /// Result of observe_next (long-poll observation).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -135,10 +219,13 @@ pub struct MatchInfoResult {
     pub status: MatchStatusInfo,
     pub current_tick: u64,
     pub player_count: u8,
+    /// Ticks the match is running behind real time (0 when keeping up).
+    #[serde(default)]
+    pub lag_ticks: u64,
 }
 
 /// Match status.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type")]
 pub enum MatchStatusInfo {
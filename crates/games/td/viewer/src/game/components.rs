@@ -14,9 +14,12 @@ pub struct Tower {
 /// Marker component for mob entities.
 #[derive(Component)]
 pub struct Mob {
+    /// Server-side mob ID.
+    pub id: String,
     pub grid_x: f32,
     pub grid_y: f32,
     pub hp: i32,
+    pub max_hp: i32,
     /// Previous position for interpolation.
     pub prev_x: f32,
     pub prev_y: f32,
@@ -17,8 +17,8 @@ pub struct GameStateCache {
 
     pub map_width: u16,
     pub map_height: u16,
-    pub spawn: (u16, u16),
-    pub goal: (u16, u16),
+    pub spawns: Vec<(u16, u16)>,
+    pub goals: Vec<(u16, u16)>,
 
     pub max_leaks: u16,
     pub tower_cost: u32,
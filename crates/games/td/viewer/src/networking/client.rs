@@ -9,6 +9,11 @@ pub fn stream_url(server_url: &str, match_id: u64) -> String {
     }
 }
 
+/// Build the URL for requesting a keyframe on a match stream (resync).
+pub fn keyframe_request_url(server_url: &str, match_id: u64) -> String {
+    format!("{}/keyframe", stream_url(server_url, match_id))
+}
+
 /// Build the SSE stream URL for the match list.
 pub fn match_list_stream_url(server_url: &str) -> String {
     if server_url.is_empty() {
@@ -55,4 +55,8 @@ pub struct SseConnectionState {
     pub is_connected: bool,
     /// Whether we have an active EventSource for match list.
     pub match_list_connected: bool,
+    /// Last reconstructed observation; deltas from the stream are applied to it.
+    pub observation: Option<td_types::TdObservation>,
+    /// Whether a resync keyframe was requested and hasn't arrived yet.
+    pub keyframe_requested: bool,
 }
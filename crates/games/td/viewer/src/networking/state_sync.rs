@@ -2,18 +2,24 @@
 //!
 //! Game state and match list are received via SSE (Server-Sent Events) from the
 //! viewer server's streaming endpoints. This enables efficient fan-out to many
-//! concurrent viewers with a single upstream poll per stream.
+//! concurrent viewers with a single upstream subscription per stream.
+//!
+//! Game state arrives as a keyframe followed by per-tick deltas (`ViewerFrame`).
+//! If a delta doesn't apply, the viewer asks the server for a fresh keyframe.
 
 use bevy::prelude::*;
 use crate::game::{
-    ConnectionState, ConnectionStatus, GameStateCache, MatchList,
+    ConnectionState, ConnectionStatus, GameStateCache, MatchList, RenderConfig,
     UiState,
 };
 use crate::networking::{
-    client::{stream_url, match_list_stream_url},
+    client::{keyframe_request_url, match_list_stream_url, stream_url},
     SseChannel, SseConnectionState,
 };
-use td_types::{TdObservation, ListMatchesResult};
+use crate::rendering::spawn_attack_line;
+use td_types::{
+    DeltaError, ListMatchesResult, ObservationDelta, TdEventInfo, TdObservation, ViewerFrame,
+};
 use wasm_bindgen::prelude::*;
 
 /// Manage the SSE EventSource connection for game observation.
@@ -35,6 +41,8 @@ pub fn manage_sse_connection(
                         sse_state.is_connected = false;
                         sse_state.connected_match_id = None;
                     }
+                    sse_state.observation = None;
+                    sse_state.keyframe_requested = false;
 
                     // Open new SSE connection
                     let url = stream_url(&connection.server_url, match_id);
@@ -53,6 +61,7 @@ pub fn manage_sse_connection(
                 close_event_source();
                 sse_state.is_connected = false;
                 sse_state.connected_match_id = None;
+                sse_state.observation = None;
                 tracing::info!("SSE: disconnected (returned to match selection)");
             }
         }
@@ -129,6 +138,11 @@ fn open_event_source(url: &str, tx: crossbeam_channel::Sender<String>) {
     js_sys::eval(&js_code).unwrap();
 }
 
+/// Ask the server to send the next frame of a match stream as a keyframe.
+fn post_keyframe_request(url: &str) {
+    let _ = js_sys::eval(&format!("fetch('{}', {{ method: 'POST' }});", url));
+}
+
 /// Close the active EventSource connection for game observation.
 fn close_event_source() {
     let _ = js_sys::eval(
@@ -191,42 +205,109 @@ fn close_match_list_event_source() {
     );
 }
 
+/// Copy a reconstructed observation into the render cache.
+fn apply_observation(game_state: &mut GameStateCache, obs: &TdObservation) {
+    game_state.tick = obs.tick;
+    game_state.ticks_per_second = obs.ticks_per_second;
+    game_state.map_width = obs.map_width;
+    game_state.map_height = obs.map_height;
+    game_state.spawns = obs.spawns.iter().map(|p| (p.x, p.y)).collect();
+    game_state.goals = obs.goals.iter().map(|p| (p.x, p.y)).collect();
+    game_state.max_leaks = obs.max_leaks;
+    game_state.tower_cost = obs.tower_cost;
+    game_state.tower_range = obs.tower_range;
+    game_state.tower_damage = obs.tower_damage;
+    game_state.build_time_ticks = obs.build_time_ticks;
+    game_state.gold_per_mob_kill = obs.gold_per_mob_kill;
+    game_state.gold = obs.gold;
+    game_state.leaks = obs.leaks;
+    game_state.current_wave = obs.current_wave;
+    game_state.waves_total = obs.waves_total;
+    game_state.wave_status = obs.wave_status.clone();
+    game_state.walkable = obs.walkable.clone();
+    game_state.towers = obs.towers.clone();
+    game_state.mobs = obs.mobs.clone();
+    game_state.build_queue = obs.build_queue.clone();
+    game_state.initialized = true;
+}
+
+/// Tower cell and mob position of each shot in `delta`, looked up before the delta is
+/// applied so that mobs killed by the shot can still be found.
+fn attack_lines(obs: &TdObservation, delta: &ObservationDelta) -> Vec<(u16, u16, f32, f32)> {
+    delta
+        .events
+        .iter()
+        .filter_map(|event| {
+            let TdEventInfo::TowerFired { tower_id, mob_id, .. } = event else {
+                return None;
+            };
+            let tower = delta
+                .towers_changed
+                .iter()
+                .chain(&obs.towers)
+                .find(|t| &t.id == tower_id)?;
+            let mob = delta.mobs_changed.iter().chain(&obs.mobs).find(|m| &m.id == mob_id)?;
+            Some((tower.x, tower.y, mob.x, mob.y))
+        })
+        .collect()
+}
+
+/// Request a resync keyframe unless one is already on its way.
+fn request_keyframe(connection: &ConnectionState, sse_state: &mut SseConnectionState) {
+    if sse_state.keyframe_requested {
+        return;
+    }
+    if let Some(match_id) = connection.match_id {
+        post_keyframe_request(&keyframe_request_url(&connection.server_url, match_id));
+        sse_state.keyframe_requested = true;
+    }
+}
+
 /// Process SSE messages for both game observation and match list.
 pub fn process_responses(
+    mut commands: Commands,
+    render_config: Res<RenderConfig>,
     sse_channel: Res<SseChannel>,
+    mut sse_state: ResMut<SseConnectionState>,
     mut game_state: ResMut<GameStateCache>,
     mut connection: ResMut<ConnectionState>,
     mut match_list: ResMut<MatchList>,
     mut ui_state: ResMut<UiState>,
 ) {
-    // Process SSE observe messages (drain all available)
+    // Process SSE observe frames (drain all available), render the latest state once
+    let mut updated = false;
+    let mut attacks = Vec::new();
     while let Ok(data) = sse_channel.observe_rx.try_recv() {
-        match serde_json::from_str::<TdObservation>(&data) {
-            Ok(obs) => {
-                game_state.tick = obs.tick;
-                game_state.ticks_per_second = obs.ticks_per_second;
-                game_state.map_width = obs.map_width;
-                game_state.map_height = obs.map_height;
-                game_state.spawn = (obs.spawn.x, obs.spawn.y);
-                game_state.goal = (obs.goal.x, obs.goal.y);
-                game_state.max_leaks = obs.max_leaks;
-                game_state.tower_cost = obs.tower_cost;
-                game_state.tower_range = obs.tower_range;
-                game_state.tower_damage = obs.tower_damage;
-                game_state.build_time_ticks = obs.build_time_ticks;
-                game_state.gold_per_mob_kill = obs.gold_per_mob_kill;
-                game_state.gold = obs.gold;
-                game_state.leaks = obs.leaks;
-                game_state.current_wave = obs.current_wave;
-                game_state.waves_total = obs.waves_total;
-                game_state.wave_status = obs.wave_status;
-                game_state.walkable = obs.walkable;
-                game_state.towers = obs.towers;
-                game_state.mobs = obs.mobs;
-                game_state.build_queue = obs.build_queue;
-                game_state.initialized = true;
-
-                connection.status = ConnectionStatus::Connected;
+        match serde_json::from_str::<ViewerFrame>(&data) {
+            Ok(ViewerFrame::Keyframe(obs)) => {
+                sse_state.observation = Some(obs);
+                sse_state.keyframe_requested = false;
+                updated = true;
+            }
+            Ok(ViewerFrame::Delta(delta)) => {
+                let result = match sse_state.observation.as_mut() {
+                    // Deltas older than our state can arrive right after a keyframe
+                    Some(obs) if delta.tick <= obs.tick => continue,
+                    Some(obs) => {
+                        let lines = attack_lines(obs, &delta);
+                        let result = obs.apply_delta(&delta);
+                        if result.is_ok() {
+                            attacks.extend(lines);
+                        }
+                        result
+                    }
+                    None => Err(DeltaError::BaseMismatch {
+                        expected: delta.base_tick,
+                        actual: 0,
+                    }),
+                };
+                match result {
+                    Ok(()) => updated = true,
+                    Err(e) => {
+                        tracing::debug!("Dropping delta ({}), requesting keyframe", e);
+                        request_keyframe(&connection, &mut sse_state);
+                    }
+                }
             }
             Err(e) => {
                 // Check if this is an error message from the server
@@ -237,6 +318,9 @@ pub fn process_responses(
                         return;
                     }
                     tracing::warn!("SSE error from server: {}", data);
+                    if data.contains("lagged") {
+                        request_keyframe(&connection, &mut sse_state);
+                    }
                 } else {
                     tracing::warn!("Failed to parse SSE observe data: {} - data: {}", e, data);
                 }
@@ -244,6 +328,16 @@ pub fn process_responses(
         }
     }
 
+    if updated {
+        if let Some(obs) = &sse_state.observation {
+            apply_observation(&mut game_state, obs);
+            connection.status = ConnectionStatus::Connected;
+        }
+    }
+    for (tower_x, tower_y, mob_x, mob_y) in attacks {
+        spawn_attack_line(&mut commands, &render_config, tower_x, tower_y, mob_x, mob_y);
+    }
+
     // Process SSE match list messages (drain all, keep latest)
     let mut latest_match_data = None;
     while let Ok(data) = sse_channel.match_list_rx.try_recv() {
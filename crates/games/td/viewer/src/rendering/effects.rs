@@ -7,17 +7,16 @@ pub const COLOR_ATTACK_LINE: Color = Color::srgba(1.0, 1.0, 0.3, 0.8);
 pub const COLOR_DEATH_PARTICLE: Color = Color::srgba(0.9, 0.3, 0.3, 1.0);
 
 /// Spawn an attack line effect from tower to mob.
-#[allow(dead_code)]
 pub fn spawn_attack_line(
     commands: &mut Commands,
     render_config: &RenderConfig,
     tower_x: u16,
     tower_y: u16,
-    mob_x: u16,
-    mob_y: u16,
+    mob_x: f32,
+    mob_y: f32,
 ) {
     let start = render_config.grid_to_world(tower_x, tower_y);
-    let end = render_config.grid_to_world(mob_x, mob_y);
+    let end = render_config.grid_to_world_f32(mob_x, mob_y);
 
     let midpoint = (start + end) / 2.0;
     let diff = end - start;
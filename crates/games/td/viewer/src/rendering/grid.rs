@@ -20,8 +20,6 @@ pub fn spawn_grid(
     let cell_size = render_config.cell_size;
     let map_width = game_state.map_width;
     let map_height = game_state.map_height;
-    let spawn = game_state.spawn;
-    let goal = game_state.goal;
 
     // Calculate offset to center the grid in the area below the HUD (60px tall)
     let mut offset = RenderConfig::calculate_offset(cell_size, map_width, map_height);
@@ -32,8 +30,8 @@ pub fn spawn_grid(
     for y in 0..map_height {
         for x in 0..map_width {
             let world_pos = render_config.grid_to_world(x, y);
-            let is_spawn = (x, y) == spawn;
-            let is_goal = (x, y) == goal;
+            let is_spawn = game_state.spawns.contains(&(x, y));
+            let is_goal = game_state.goals.contains(&(x, y));
             let idx = (y as usize) * (map_width as usize) + (x as usize);
             let is_walkable = game_state.walkable.get(idx).copied().unwrap_or(true);
 
@@ -67,10 +65,10 @@ pub fn spawn_grid(
     }
 
     tracing::info!(
-        "Grid spawned: {}x{}, spawn={:?}, goal={:?}",
+        "Grid spawned: {}x{}, spawns={:?}, goals={:?}",
         map_width,
         map_height,
-        spawn,
-        goal
+        game_state.spawns,
+        game_state.goals
     );
 }
@@ -1,20 +1,23 @@
 //! Mob rendering system.
 
 use bevy::prelude::*;
-use crate::game::{GameStateCache, Mob, RenderConfig};
+use crate::game::{GameStateCache, Mob, MobInfo, RenderConfig};
+use std::collections::{HashMap, HashSet};
 
 pub const COLOR_MOB: Color = Color::srgba(0.9, 0.3, 0.3, 1.0);
 pub const COLOR_MOB_HP_BG: Color = Color::srgba(0.2, 0.2, 0.2, 1.0);
 pub const COLOR_MOB_HP_FILL: Color = Color::srgba(0.8, 0.8, 0.2, 1.0);
 
-const MOB_HP_MAX: i32 = 10; // Default mob HP
+fn hp_ratio(hp: i32, max_hp: i32) -> f32 {
+    (hp as f32 / max_hp.max(1) as f32).clamp(0.0, 1.0)
+}
 
-/// Sync mob entities with game state.
+/// Sync mob entities with game state, matching them by mob ID.
 pub fn sync_mobs(
     mut commands: Commands,
     game_state: Res<GameStateCache>,
     render_config: Res<RenderConfig>,
-    mut mobs: Query<(Entity, &mut Mob, &mut Transform, &Children)>,
+    mut mobs: Query<(Entity, &mut Mob, &Transform, &Children)>,
     mut hp_fills: Query<&mut Sprite, Without<Mob>>,
 ) {
     if !game_state.initialized {
@@ -24,77 +27,46 @@ pub fn sync_mobs(
     let cell_size = render_config.cell_size;
     let mob_size = cell_size * 0.6;
 
-    // We need to match mobs by position since there's no ID
-    // This is imperfect but works for visualization
-    let server_mobs: Vec<_> = game_state.mobs.iter().collect();
-
-    // Track which server mobs we've matched
-    let mut matched_server_indices: std::collections::HashSet<usize> = std::collections::HashSet::new();
-    let mut entities_to_despawn: Vec<Entity> = Vec::new();
-
-    // Try to match existing mob entities to server mobs
-    for (entity, mut mob, mut transform, children) in mobs.iter_mut() {
-        // Find the best matching server mob (closest to current position)
-        let mut best_match: Option<(usize, f32)> = None;
-
-        for (i, mob_info) in server_mobs.iter().enumerate() {
-            if matched_server_indices.contains(&i) {
-                continue;
-            }
-
-            let dx = mob_info.x - mob.grid_x;
-            let dy = mob_info.y - mob.grid_y;
-            let dist = dx * dx + dy * dy;
-
-            // Allow matching if close (within 2 cells)
-            if dist <= 4.0 {
-                if best_match.is_none() || dist < best_match.unwrap().1 {
-                    best_match = Some((i, dist));
-                }
-            }
-        }
-
-        if let Some((idx, _)) = best_match {
-            let mob_info = server_mobs[idx];
-            matched_server_indices.insert(idx);
-
-            // Store previous position for interpolation
-            mob.prev_x = transform.translation.x;
-            mob.prev_y = transform.translation.y;
-            mob.grid_x = mob_info.x;
-            mob.grid_y = mob_info.y;
-            mob.hp = mob_info.hp;
-
-            // Target position (will be interpolated)
-            let target_pos = render_config.grid_to_world_f32(mob_info.x, mob_info.y);
-            transform.translation = target_pos.extend(2.0);
-
-            // Update HP bar
-            for child in children.iter() {
-                if let Ok(mut sprite) = hp_fills.get_mut(child) {
-                    let hp_ratio = (mob.hp as f32 / MOB_HP_MAX as f32).clamp(0.0, 1.0);
-                    sprite.custom_size = Some(Vec2::new(mob_size * hp_ratio, 2.0));
-                }
+    let server_mobs: HashMap<&str, &MobInfo> = game_state
+        .mobs
+        .iter()
+        .map(|m| (m.id.as_str(), m))
+        .collect();
+    let mut existing: HashSet<String> = HashSet::new();
+
+    for (entity, mut mob, transform, children) in mobs.iter_mut() {
+        let Some(mob_info) = server_mobs.get(mob.id.as_str()) else {
+            // Died or leaked
+            commands.entity(entity).despawn();
+            continue;
+        };
+        existing.insert(mob.id.clone());
+
+        // New target position; interpolate_mob_positions moves the sprite there
+        mob.prev_x = transform.translation.x;
+        mob.prev_y = transform.translation.y;
+        mob.grid_x = mob_info.x;
+        mob.grid_y = mob_info.y;
+        mob.hp = mob_info.hp;
+        mob.max_hp = mob_info.max_hp;
+
+        // Update HP bar
+        for child in children.iter() {
+            if let Ok(mut sprite) = hp_fills.get_mut(child) {
+                let ratio = hp_ratio(mob.hp, mob.max_hp);
+                sprite.custom_size = Some(Vec2::new(mob_size * ratio, 2.0));
             }
-        } else {
-            // No match found, despawn
-            entities_to_despawn.push(entity);
         }
     }
 
-    // Despawn unmatched entities
-    for entity in entities_to_despawn {
-        commands.entity(entity).despawn();
-    }
-
     // Spawn new mobs
-    for (i, mob_info) in server_mobs.iter().enumerate() {
-        if matched_server_indices.contains(&i) {
+    for mob_info in &game_state.mobs {
+        if existing.contains(&mob_info.id) {
             continue;
         }
 
         let world_pos = render_config.grid_to_world_f32(mob_info.x, mob_info.y);
-        let hp_ratio = (mob_info.hp as f32 / MOB_HP_MAX as f32).clamp(0.0, 1.0);
+        let hp_ratio = hp_ratio(mob_info.hp, mob_info.max_hp);
 
         commands.spawn((
             Sprite {
@@ -104,9 +76,11 @@ pub fn sync_mobs(
             },
             Transform::from_translation(world_pos.extend(2.0)),
             Mob {
+                id: mob_info.id.clone(),
                 grid_x: mob_info.x,
                 grid_y: mob_info.y,
                 hp: mob_info.hp,
+                max_hp: mob_info.max_hp,
                 prev_x: world_pos.x,
                 prev_y: world_pos.y,
             },
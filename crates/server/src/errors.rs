@@ -113,3 +113,24 @@ impl fmt::Display for ObserveNextError {
 }
 
 impl std::error::Error for ObserveNextError {}
+
+/// Error when receiving from a match subscription.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionError {
+    /// The subscriber fell behind and this many updates were dropped.
+    /// The subscription stays usable; the next update carries a fresh observation.
+    Lagged(u64),
+    /// The match ended, was removed, or the session left.
+    Closed,
+}
+
+impl fmt::Display for SubscriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubscriptionError::Lagged(n) => write!(f, "subscriber lagged, {} updates dropped", n),
+            SubscriptionError::Closed => write!(f, "subscription closed"),
+        }
+    }
+}
+
+impl std::error::Error for SubscriptionError {}
@@ -32,6 +32,11 @@ impl<E: Clone> EventBuffer<E> {
         });
     }
 
+    /// Cursor pointing past the most recently pushed event.
+    pub fn cursor(&self) -> EventCursor {
+        EventCursor(self.next_sequence)
+    }
+
     /// Get events starting from the given cursor.
     /// Returns the events and a new cursor pointing past the last returned event.
     pub fn get_from_cursor(&self, cursor: EventCursor) -> (Vec<ServerEvent<E>>, EventCursor) {
@@ -44,11 +49,7 @@ impl<E: Clone> EventBuffer<E> {
         }
 
         // Calculate the oldest available sequence
-        let oldest_available = if self.next_sequence > self.capacity as u64 {
-            self.next_sequence - self.capacity as u64
-        } else {
-            0
-        };
+        let oldest_available = self.next_sequence.saturating_sub(self.capacity as u64);
 
         // Start from the requested cursor or oldest available, whichever is newer
         let effective_start = start_seq.max(oldest_available);
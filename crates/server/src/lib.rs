@@ -1,12 +1,21 @@
 pub mod errors;
 pub mod events;
 pub mod match_handle;
+pub mod metrics;
+pub mod scheduler;
 pub mod server;
+pub mod subscription;
 pub mod tick_loop;
 pub mod types;
 
-pub use errors::{CreateMatchError, JoinError, MatchError, ObserveNextError, SubmitError};
+pub use errors::{
+    CreateMatchError, JoinError, MatchError, ObserveNextError, SubmitError, SubscriptionError,
+};
 pub use events::EventBuffer;
-pub use match_handle::MatchHandle;
+pub use match_handle::{MatchHandle, MatchSnapshot, MatchUpdate};
+pub use metrics::{MatchMetrics, ServerMetrics};
 pub use server::GameServer;
-pub use types::{EventCursor, MatchInfo, MatchStatus, ServerConfig, ServerEvent, SessionToken};
+pub use subscription::MatchSubscription;
+pub use types::{
+    EventCursor, MatchInfo, MatchStatus, ServerConfig, ServerEvent, SessionToken, TickUpdate,
+};
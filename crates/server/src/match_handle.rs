@@ -1,12 +1,17 @@
 use crate::events::EventBuffer;
+use crate::metrics::MatchMetrics;
+use crate::subscription::MatchSubscription;
 use crate::types::{EventCursor, MatchStatus, ServerEvent, SessionToken};
 use sim_core::{ActionEnvelope, ActionId, Game, PlayerId, Tick};
 use sim_host::MatchHost;
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
-use tokio::sync::{Mutex, Notify};
+use tokio::sync::{broadcast, Mutex, Notify};
+
+/// Updates buffered per subscriber before it starts lagging.
+const UPDATE_CHANNEL_CAPACITY: usize = 64;
 
 /// Per-session observation tracking for observe_next.
 pub struct SessionObserveState {
@@ -14,6 +19,62 @@ pub struct SessionObserveState {
     pub is_waiting: bool,
 }
 
+/// Immutable view of a match, published after every tick and membership change.
+///
+/// Readers clone the current `Arc` and never take the match lock, so listing matches
+/// does not contend with stepping. Observing only takes it when the observations are
+/// older than `tick`.
+pub struct MatchSnapshot<G: Game> {
+    pub tick: Tick,
+    pub status: MatchStatus,
+    pub player_count: u8,
+    /// Tick the observations show. They are rebuilt on decision ticks, on membership
+    /// changes and on every tick that has subscribers; otherwise they are carried over.
+    pub observed_tick: Tick,
+    /// Observation at `observed_tick` for each player, and for spectators under player
+    /// 0. The spectator view is always present.
+    pub observations: Arc<HashMap<PlayerId, G::Observation>>,
+    /// Which player each session observes as. Shared between snapshots until
+    /// membership changes.
+    viewers: Arc<HashMap<SessionToken, PlayerId>>,
+}
+
+impl<G: Game> MatchSnapshot<G> {
+    /// Check if a session is valid (player or spectator).
+    pub fn is_valid_session(&self, session: SessionToken) -> bool {
+        self.viewers.contains_key(&session)
+    }
+
+    /// The observation for a player or spectator session.
+    pub fn observation(&self, session: SessionToken) -> Option<&G::Observation> {
+        let player_id = self.viewers.get(&session)?;
+        self.observations.get(player_id)
+    }
+
+    /// The spectator view, available without a session.
+    pub fn spectator_observation(&self) -> &G::Observation {
+        &self.observations[&0]
+    }
+}
+
+/// Published to subscribers after every stepped tick, and when the match is terminated.
+pub struct MatchUpdate<G: Game> {
+    pub snapshot: Arc<MatchSnapshot<G>>,
+    /// Events produced by this tick.
+    pub events: Arc<Vec<ServerEvent<G::Event>>>,
+    pub decision: bool,
+}
+
+impl<G: Game> Clone for MatchUpdate<G> {
+    fn clone(&self) -> Self {
+        Self {
+            snapshot: Arc::clone(&self.snapshot),
+            events: Arc::clone(&self.events),
+            decision: self.decision,
+        }
+    }
+}
+
 /// Internal state of a match.
 pub struct MatchInner<G: Game> {
     pub host: MatchHost<G>,
@@ -30,8 +91,13 @@ pub struct MatchInner<G: Game> {
     pub decision_stride: u64,
     pub last_decision_tick: Tick,
     pub decision_notify: Arc<Notify>,
-    pub cached_observations: HashMap<PlayerId, G::Observation>,
+    pub cached_observations: Arc<HashMap<PlayerId, G::Observation>>,
     pub session_observe_state: HashMap<SessionToken, SessionObserveState>,
+
+    // Lock-free read path
+    pub snapshot: Arc<RwLock<Arc<MatchSnapshot<G>>>>,
+    viewers: Arc<HashMap<SessionToken, PlayerId>>,
+    pub updates: broadcast::Sender<MatchUpdate<G>>,
 }
 
 impl<G: Game> MatchInner<G> {
@@ -43,6 +109,20 @@ impl<G: Game> MatchInner<G> {
     ) -> Self {
         let tick_hz = host.tick_hz();
         let decision_stride = (tick_hz / decision_hz).max(1) as u64;
+        let status = MatchStatus::WaitingForPlayers {
+            current: 0,
+            required: required_players,
+        };
+        let viewers = Arc::new(HashMap::new());
+        let tick = host.current_tick();
+        let snapshot = MatchSnapshot {
+            tick,
+            status,
+            player_count: 0,
+            observed_tick: tick,
+            observations: Arc::new(HashMap::from([(0, host.game().observe(tick, 0))])),
+            viewers: Arc::clone(&viewers),
+        };
         Self {
             host,
             events: EventBuffer::new(event_buffer_capacity),
@@ -52,36 +132,133 @@ impl<G: Game> MatchInner<G> {
             next_session_id: 1,
             next_action_id: 1,
             required_players,
-            status: MatchStatus::WaitingForPlayers {
-                current: 0,
-                required: required_players,
-            },
+            status,
             decision_stride,
             last_decision_tick: 0,
             decision_notify: Arc::new(Notify::new()),
-            cached_observations: HashMap::new(),
+            cached_observations: Arc::new(HashMap::new()),
             session_observe_state: HashMap::new(),
+            snapshot: Arc::new(RwLock::new(Arc::new(snapshot))),
+            viewers,
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
         }
     }
 
     pub fn player_count(&self) -> u8 {
         self.sessions.len() as u8
     }
+
+    /// Rebuild the session-to-player map after a join, leave or spectate.
+    fn update_viewers(&mut self) {
+        let mut viewers = self.sessions.clone();
+        viewers.extend(self.spectators.iter().map(|&session| (session, 0)));
+        self.viewers = Arc::new(viewers);
+    }
+
+    /// Publish the current tick and match status as the new snapshot. With `observe`,
+    /// observe the spectator view and every player being viewed; otherwise keep the
+    /// previous snapshot's observations.
+    pub fn publish_snapshot(&mut self, observe: bool) -> Arc<MatchSnapshot<G>> {
+        let tick = self.host.current_tick();
+        let (observed_tick, observations) = if observe {
+            let mut observations = HashMap::new();
+            observations.insert(0, self.host.game().observe(tick, 0));
+            for &player_id in self.viewers.values() {
+                observations
+                    .entry(player_id)
+                    .or_insert_with(|| self.host.game().observe(tick, player_id));
+            }
+            (tick, Arc::new(observations))
+        } else {
+            let previous = self.snapshot.read().unwrap();
+            (previous.observed_tick, Arc::clone(&previous.observations))
+        };
+
+        let snapshot = Arc::new(MatchSnapshot {
+            tick,
+            status: self.status,
+            player_count: self.player_count(),
+            observed_tick,
+            observations,
+            viewers: Arc::clone(&self.viewers),
+        });
+        *self.snapshot.write().unwrap() = Arc::clone(&snapshot);
+        snapshot
+    }
+
+    /// Step one tick and update status.
+    /// Returns true if the game is now finished.
+    pub fn step_one_tick(&mut self) -> bool {
+        // Only step if running
+        if !matches!(self.status, MatchStatus::Running) {
+            return matches!(
+                self.status,
+                MatchStatus::Finished(_) | MatchStatus::Terminated
+            );
+        }
+
+        let events_start = self.events.cursor();
+        if let Some(events) = self.host.step_one_tick() {
+            let tick = self.host.current_tick();
+            for event in events {
+                self.events.push(tick, event);
+            }
+        }
+
+        let terminal = self.host.is_terminal();
+        if let Some(outcome) = terminal {
+            self.status = MatchStatus::Finished(outcome);
+        }
+
+        // Observations for players and spectators (player_id 0), only when someone
+        // can see them before the next decision tick
+        let current_tick = self.host.current_tick();
+        let decision = current_tick.is_multiple_of(self.decision_stride);
+        let observe = decision || terminal.is_some() || self.updates.receiver_count() > 0;
+        let snapshot = self.publish_snapshot(observe);
+
+        if decision {
+            self.cached_observations = Arc::clone(&snapshot.observations);
+            self.last_decision_tick = current_tick;
+            self.decision_notify.notify_waiters();
+        }
+
+        if self.updates.receiver_count() > 0 {
+            let (events, _) = self.events.get_from_cursor(events_start);
+            let _ = self.updates.send(MatchUpdate {
+                snapshot,
+                events: Arc::new(events),
+                decision: decision || terminal.is_some(),
+            });
+        }
+
+        if terminal.is_some() {
+            // Notify any waiting observers so they unblock
+            self.decision_notify.notify_waiters();
+            return true;
+        }
+
+        false
+    }
 }
 
 /// Thread-safe handle to a match.
 pub struct MatchHandle<G: Game> {
     pub inner: Arc<Mutex<MatchInner<G>>>,
+    snapshot: Arc<RwLock<Arc<MatchSnapshot<G>>>>,
     shutdown: Arc<AtomicBool>,
     tick_hz: u32,
+    metrics: Arc<MatchMetrics>,
 }
 
 impl<G: Game> Clone for MatchHandle<G> {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
+            snapshot: Arc::clone(&self.snapshot),
             shutdown: Arc::clone(&self.shutdown),
             tick_hz: self.tick_hz,
+            metrics: Arc::clone(&self.metrics),
         }
     }
 }
@@ -94,15 +271,13 @@ impl<G: Game> MatchHandle<G> {
         decision_hz: u32,
     ) -> Self {
         let tick_hz = host.tick_hz();
+        let inner = MatchInner::new(host, event_buffer_capacity, required_players, decision_hz);
         Self {
-            inner: Arc::new(Mutex::new(MatchInner::new(
-                host,
-                event_buffer_capacity,
-                required_players,
-                decision_hz,
-            ))),
+            snapshot: Arc::clone(&inner.snapshot),
+            inner: Arc::new(Mutex::new(inner)),
             shutdown: Arc::new(AtomicBool::new(false)),
             tick_hz,
+            metrics: Arc::new(MatchMetrics::default()),
         }
     }
 
@@ -110,6 +285,16 @@ impl<G: Game> MatchHandle<G> {
         self.tick_hz
     }
 
+    /// Performance metrics for this match (step durations, skipped ticks, lag).
+    pub fn metrics(&self) -> &MatchMetrics {
+        &self.metrics
+    }
+
+    /// The snapshot published after the most recent tick. Never waits on stepping.
+    pub fn snapshot(&self) -> Arc<MatchSnapshot<G>> {
+        Arc::clone(&self.snapshot.read().unwrap())
+    }
+
     pub fn should_shutdown(&self) -> bool {
         self.shutdown.load(Ordering::Relaxed)
     }
@@ -132,6 +317,8 @@ impl<G: Game> MatchHandle<G> {
                 is_waiting: false,
             },
         );
+        inner.update_viewers();
+        inner.publish_snapshot(true);
         session
     }
 
@@ -170,6 +357,8 @@ impl<G: Game> MatchHandle<G> {
                         required,
                     };
                 }
+                inner.update_viewers();
+                inner.publish_snapshot(true);
 
                 Some((session, player_id))
             }
@@ -183,12 +372,37 @@ impl<G: Game> MatchHandle<G> {
 
         inner.session_observe_state.remove(&session);
 
-        if let Some(player_id) = inner.sessions.remove(&session) {
+        let removed = if let Some(player_id) = inner.sessions.remove(&session) {
             inner.players.remove(&player_id);
             true
         } else {
             inner.spectators.remove(&session)
+        };
+        if removed {
+            inner.update_viewers();
+            inner.publish_snapshot(true);
         }
+        removed
+    }
+
+    /// Subscribe a player or spectator session to updates pushed as ticks complete.
+    /// With `decision_only`, only decision ticks (and the final update) are delivered and
+    /// events in between are accumulated. Returns `None` for an unknown session.
+    pub async fn subscribe(
+        &self,
+        session: SessionToken,
+        decision_only: bool,
+    ) -> Option<MatchSubscription<G>> {
+        // Subscribe and read the snapshot under the lock so no tick falls in between
+        let inner = self.inner.lock().await;
+        let rx = inner.updates.subscribe();
+        let snapshot = self.snapshot();
+        drop(inner);
+
+        if !snapshot.is_valid_session(session) {
+            return None;
+        }
+        Some(MatchSubscription::new(rx, snapshot, session, decision_only))
     }
 
     /// Submit an action for a player.
@@ -232,19 +446,47 @@ impl<G: Game> MatchHandle<G> {
 
     /// Get the current observation for a player or spectator.
     pub async fn observe(&self, session: SessionToken) -> Option<G::Observation> {
-        let inner = self.inner.lock().await;
+        let snapshot = self.snapshot();
+        if snapshot.observed_tick == snapshot.tick {
+            return snapshot.observation(session).cloned();
+        }
 
-        let player_id = if let Some(&pid) = inner.sessions.get(&session) {
-            pid
-        } else if inner.spectators.contains(&session) {
-            0
-        } else {
-            return None;
-        };
+        // Between decision ticks the snapshot carries older observations; observe now
+        let inner = self.inner.lock().await;
+        let player_id = *inner.viewers.get(&session)?;
         let tick = inner.host.current_tick();
         Some(inner.host.game().observe(tick, player_id))
     }
 
+    /// Get the current spectator view.
+    pub async fn observe_spectator(&self) -> G::Observation {
+        let snapshot = self.snapshot();
+        if snapshot.observed_tick == snapshot.tick {
+            return snapshot.spectator_observation().clone();
+        }
+
+        let inner = self.inner.lock().await;
+        let tick = inner.host.current_tick();
+        inner.host.game().observe(tick, 0)
+    }
+
+    /// Run `f` on the live game state and the session's player ID (0 for spectators)
+    /// under the match lock, blocking the tick loop while it runs, so keep `f` short.
+    /// Returns `None` for an unknown session.
+    pub async fn inspect<R>(
+        &self,
+        session: SessionToken,
+        f: impl FnOnce(&G, PlayerId) -> R,
+    ) -> Option<R> {
+        let inner = self.inner.lock().await;
+        let player_id = match inner.sessions.get(&session) {
+            Some(&player_id) => player_id,
+            None if inner.spectators.contains(&session) => 0,
+            None => return None,
+        };
+        Some(f(inner.host.game(), player_id))
+    }
+
     /// Poll events from the given cursor.
     pub async fn poll_events(
         &self,
@@ -262,83 +504,47 @@ impl<G: Game> MatchHandle<G> {
     }
 
     /// Get the current tick.
-    pub async fn current_tick(&self) -> Tick {
-        let inner = self.inner.lock().await;
-        inner.host.current_tick()
+    pub fn current_tick(&self) -> Tick {
+        self.snapshot().tick
     }
 
     /// Get the current match status.
-    pub async fn status(&self) -> MatchStatus {
-        let inner = self.inner.lock().await;
-        inner.status
+    pub fn status(&self) -> MatchStatus {
+        self.snapshot().status
     }
 
     /// Get the player count.
-    pub async fn player_count(&self) -> u8 {
-        let inner = self.inner.lock().await;
-        inner.player_count()
+    pub fn player_count(&self) -> u8 {
+        self.snapshot().player_count
     }
 
     /// Check if a session is valid (player or spectator).
-    pub async fn is_valid_session(&self, session: SessionToken) -> bool {
-        let inner = self.inner.lock().await;
-        inner.sessions.contains_key(&session) || inner.spectators.contains(&session)
+    pub fn is_valid_session(&self, session: SessionToken) -> bool {
+        self.snapshot().is_valid_session(session)
     }
 
     /// Step one tick and update status.
     /// Returns true if the game is now finished.
     pub async fn step_one_tick(&self) -> bool {
-        let mut inner = self.inner.lock().await;
-
-        // Only step if running
-        if !matches!(inner.status, MatchStatus::Running) {
-            return matches!(
-                inner.status,
-                MatchStatus::Finished(_) | MatchStatus::Terminated
-            );
-        }
-
-        if let Some(events) = inner.host.step_one_tick() {
-            let tick = inner.host.current_tick();
-            for event in events {
-                inner.events.push(tick, event);
-            }
-        }
-
-        // Check if this is a decision tick
-        let current_tick = inner.host.current_tick();
-        if current_tick % inner.decision_stride == 0 {
-            // Collect player IDs first to avoid borrow conflict
-            let mut player_ids: Vec<PlayerId> = inner.players.keys().copied().collect();
-            // Also cache for spectators (player_id 0)
-            if !inner.spectators.is_empty() && !player_ids.contains(&0) {
-                player_ids.push(0);
-            }
-
-            for player_id in player_ids {
-                let obs = inner.host.game().observe(current_tick, player_id);
-                inner.cached_observations.insert(player_id, obs);
-            }
-
-            inner.last_decision_tick = current_tick;
-            inner.decision_notify.notify_waiters();
-        }
-
-        // Check if terminal
-        if let Some(outcome) = inner.host.is_terminal() {
-            inner.status = MatchStatus::Finished(outcome);
-            // Notify any waiting observers so they unblock
-            inner.decision_notify.notify_waiters();
-            return true;
-        }
+        self.inner.lock().await.step_one_tick()
+    }
 
-        false
+    /// Blocking variant of [`step_one_tick`](Self::step_one_tick) for scheduler worker
+    /// threads. Must not be called from within an async context.
+    pub fn step_one_tick_blocking(&self) -> bool {
+        self.inner.blocking_lock().step_one_tick()
     }
 
     /// Terminate the match.
     pub async fn terminate(&self) {
         let mut inner = self.inner.lock().await;
         inner.status = MatchStatus::Terminated;
+        let snapshot = inner.publish_snapshot(true);
+        let _ = inner.updates.send(MatchUpdate {
+            snapshot,
+            events: Arc::new(Vec::new()),
+            decision: true,
+        });
         inner.decision_notify.notify_waiters();
         drop(inner);
         self.request_shutdown();
@@ -392,7 +598,7 @@ impl<G: Game> MatchHandle<G> {
             let elapsed = start_time.elapsed();
             let remaining_ms = max_wait_ms.saturating_sub(elapsed.as_millis() as u64);
 
-            let (notify, should_return_timeout) = {
+            let notify = {
                 let mut inner = self.inner.lock().await;
 
                 // Resolve player_id for this session
@@ -462,7 +668,7 @@ impl<G: Game> MatchHandle<G> {
                 if let Some(state) = inner.session_observe_state.get_mut(&session) {
                     state.is_waiting = true;
                 }
-                (Arc::clone(&inner.decision_notify), false)
+                Arc::clone(&inner.decision_notify)
             };
 
             // Wait outside the lock for the remaining time, then loop back
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (in seconds) of the step duration histogram buckets.
+pub const STEP_DURATION_BUCKETS: [f64; 12] = [
+    0.000_05, 0.000_1, 0.000_25, 0.000_5, 0.001, 0.002_5, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25,
+];
+
+/// A monotonically increasing counter.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A value that can go up and down.
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Increment now and decrement when the returned guard is dropped.
+    /// Keeps the gauge correct even if the tracked future is cancelled.
+    pub fn track(&self) -> GaugeGuard<'_> {
+        self.inc();
+        GaugeGuard(self)
+    }
+}
+
+/// Decrements its gauge on drop. See [`Gauge::track`].
+pub struct GaugeGuard<'a>(&'a Gauge);
+
+impl Drop for GaugeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// A counter partitioned by a single label value (e.g. a rejection reason).
+#[derive(Debug, Default)]
+pub struct LabeledCounter(Mutex<BTreeMap<String, u64>>);
+
+impl LabeledCounter {
+    pub fn inc(&self, label: &str) {
+        let mut values = self.0.lock().unwrap();
+        *values.entry(label.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn get(&self, label: &str) -> u64 {
+        self.0.lock().unwrap().get(label).copied().unwrap_or(0)
+    }
+
+    pub fn snapshot(&self) -> Vec<(String, u64)> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(label, &value)| (label.clone(), value))
+            .collect()
+    }
+}
+
+/// A fixed-bucket histogram of durations.
+///
+/// Buckets are non-cumulative internally and accumulated when rendered.
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    buckets: Vec<AtomicU64>,
+    sum_nanos: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            // One extra bucket for +Inf
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_nanos: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let index = self
+            .bounds
+            .iter()
+            .position(|&bound| secs <= bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn sum(&self) -> Duration {
+        Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Write the histogram series (`_bucket`, `_sum`, `_count`) for one label set.
+    /// `labels` is either empty or a comma-separated list like `match_id="1"`.
+    pub fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = match self.bounds.get(i) {
+                Some(bound) => bound.to_string(),
+                None => "+Inf".to_string(),
+            };
+            let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{le}\"}} {cumulative}");
+        }
+        let braces = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let _ = writeln!(out, "{name}_sum{braces} {}", self.sum().as_secs_f64());
+        let _ = writeln!(out, "{name}_count{braces} {}", self.count());
+    }
+}
+
+/// Per-match performance metrics, owned by the match handle and updated by the tick loop.
+#[derive(Debug)]
+pub struct MatchMetrics {
+    /// Wall-clock time spent in each `step_one_tick` call.
+    pub step_duration: Histogram,
+    /// Ticks dropped by the tick loop because it fell behind its interval.
+    pub ticks_skipped: Counter,
+    /// How many ticks game time lags behind wall-clock time.
+    pub lag_ticks: Gauge,
+    /// Duration of the most recent step, in microseconds.
+    pub last_step_micros: Gauge,
+    /// Steps that took longer than the per-tick budget.
+    pub step_overruns: Counter,
+}
+
+impl Default for MatchMetrics {
+    fn default() -> Self {
+        Self {
+            step_duration: Histogram::new(&STEP_DURATION_BUCKETS),
+            ticks_skipped: Counter::default(),
+            lag_ticks: Gauge::default(),
+            last_step_micros: Gauge::default(),
+            step_overruns: Counter::default(),
+        }
+    }
+}
+
+/// Server-wide metrics shared by all matches.
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    pub matches_created: Counter,
+    pub actions_submitted: Counter,
+    /// Rejected actions by reason (e.g. `invalid_session`, `terminated`).
+    pub actions_rejected: LabeledCounter,
+    /// Number of `observe_next` calls currently waiting.
+    pub observe_next_waiters: Gauge,
+    pub observe_next_timeouts: Counter,
+}
+
+impl ServerMetrics {
+    /// Record an action that was rejected before reaching the simulation.
+    pub fn record_action_rejected(&self, reason: &str) {
+        self.actions_rejected.inc(reason);
+    }
+}
+
+/// Append a `# HELP` / `# TYPE` header for a metric family.
+pub fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let histogram = Histogram::new(&[0.001, 0.01]);
+        histogram.observe(Duration::from_micros(500));
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_millis(50));
+
+        let mut out = String::new();
+        histogram.render(&mut out, "step", "match_id=\"1\"");
+
+        assert!(out.contains("step_bucket{match_id=\"1\",le=\"0.001\"} 1\n"));
+        assert!(out.contains("step_bucket{match_id=\"1\",le=\"0.01\"} 2\n"));
+        assert!(out.contains("step_bucket{match_id=\"1\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("step_count{match_id=\"1\"} 3\n"));
+    }
+
+    #[test]
+    fn test_histogram_without_labels() {
+        let histogram = Histogram::new(&[1.0]);
+        histogram.observe(Duration::from_millis(1500));
+
+        let mut out = String::new();
+        histogram.render(&mut out, "step", "");
+
+        assert!(out.contains("step_bucket{le=\"1\"} 0\n"));
+        assert!(out.contains("step_bucket{le=\"+Inf\"} 1\n"));
+        assert!(out.contains("step_sum 1.5\n"));
+        assert!(out.contains("step_count 1\n"));
+    }
+
+    #[test]
+    fn test_labeled_counter() {
+        let counter = LabeledCounter::default();
+        counter.inc("terminated");
+        counter.inc("invalid_session");
+        counter.inc("terminated");
+
+        assert_eq!(counter.get("terminated"), 2);
+        assert_eq!(counter.get("not_found"), 0);
+        assert_eq!(
+            counter.snapshot(),
+            vec![
+                ("invalid_session".to_string(), 1),
+                ("terminated".to_string(), 2)
+            ]
+        );
+    }
+}
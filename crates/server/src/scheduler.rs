@@ -0,0 +1,216 @@
+use crate::match_handle::MatchHandle;
+use crate::tick_loop::{TickLoopConfig, TickRunner};
+use sim_core::Game;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Maximum number of matches handed to a worker in one batch.
+const MAX_BATCH: usize = 16;
+
+/// Batches a worker may have queued before the dispatcher blocks.
+const QUEUED_BATCHES_PER_WORKER: usize = 2;
+
+/// A registered match waiting for its next tick.
+struct Slot<G: Game> {
+    due: Instant,
+    /// Registration order, breaks ties between equal deadlines so no match starves.
+    seq: u64,
+    runner: TickRunner<G>,
+}
+
+impl<G: Game> PartialEq for Slot<G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.due == other.due && self.seq == other.seq
+    }
+}
+
+impl<G: Game> Eq for Slot<G> {}
+
+impl<G: Game> PartialOrd for Slot<G> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<G: Game> Ord for Slot<G> {
+    // Reversed so the max-heap pops the earliest deadline first
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.due, other.seq).cmp(&(self.due, self.seq))
+    }
+}
+
+struct State<G: Game> {
+    queue: BinaryHeap<Slot<G>>,
+    next_seq: u64,
+    stopped: bool,
+}
+
+struct Shared<G: Game> {
+    state: Mutex<State<G>>,
+    wake: Condvar,
+}
+
+impl<G: Game> Shared<G> {
+    fn push(&self, runner: TickRunner<G>) {
+        let mut state = self.state.lock().unwrap();
+        if state.stopped {
+            return;
+        }
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.queue.push(Slot {
+            due: runner.next_due(),
+            seq,
+            runner,
+        });
+        drop(state);
+        self.wake.notify_one();
+    }
+}
+
+/// Steps all matches of a server on a fixed pool of worker threads.
+///
+/// A dispatcher thread keeps matches ordered by their next deadline and hands batches of
+/// due matches to the workers over a bounded channel. Each dispatch runs at most
+/// `1 + max_catch_up_ticks` ticks of a match before it is requeued, so one slow match
+/// cannot monopolise a worker. When all workers are busy the dispatcher blocks instead of
+/// queueing more work; matches that fall behind are paced by their [`TickRunner`].
+pub struct Scheduler<G: Game> {
+    shared: Arc<Shared<G>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl<G: Game + Send + 'static> Scheduler<G>
+where
+    G::Action: Send,
+    G::Observation: Send,
+    G::Event: Send,
+    G::Config: Send,
+{
+    /// Start the dispatcher and `worker_threads` workers (at least one).
+    pub fn new(worker_threads: usize) -> Self {
+        let worker_threads = worker_threads.max(1);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: BinaryHeap::new(),
+                next_seq: 0,
+                stopped: false,
+            }),
+            wake: Condvar::new(),
+        });
+
+        let (tx, rx) = sync_channel(worker_threads * QUEUED_BATCHES_PER_WORKER);
+        let rx = Arc::new(Mutex::new(rx));
+
+        let mut threads = Vec::with_capacity(worker_threads + 1);
+        for i in 0..worker_threads {
+            let shared = Arc::clone(&shared);
+            let rx = Arc::clone(&rx);
+            threads.push(
+                thread::Builder::new()
+                    .name(format!("sim-worker-{i}"))
+                    .spawn(move || run_worker(&shared, &rx))
+                    .expect("failed to spawn sim worker"),
+            );
+        }
+
+        let dispatcher_shared = Arc::clone(&shared);
+        threads.push(
+            thread::Builder::new()
+                .name("sim-dispatcher".to_string())
+                .spawn(move || run_dispatcher(&dispatcher_shared, tx))
+                .expect("failed to spawn sim dispatcher"),
+        );
+
+        Self { shared, threads }
+    }
+
+    /// Start stepping a match. It is dropped from the schedule once it finishes
+    /// or shutdown is requested on its handle.
+    pub fn register(&self, handle: MatchHandle<G>, config: TickLoopConfig) {
+        self.shared
+            .push(TickRunner::new(handle, config, Instant::now()));
+    }
+}
+
+impl<G: Game> Scheduler<G> {
+    /// Number of matches currently waiting for their next tick.
+    pub fn queued(&self) -> usize {
+        self.shared.state.lock().unwrap().queue.len()
+    }
+}
+
+impl<G: Game> Drop for Scheduler<G> {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.stopped = true;
+            state.queue.clear();
+        }
+        self.shared.wake.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Hands due matches to the workers. Dropping `tx` on return stops the workers.
+fn run_dispatcher<G: Game>(shared: &Shared<G>, tx: SyncSender<Vec<TickRunner<G>>>) {
+    let mut state = shared.state.lock().unwrap();
+    loop {
+        if state.stopped {
+            return;
+        }
+
+        let now = Instant::now();
+        match state.queue.peek() {
+            None => {
+                state = shared.wake.wait(state).unwrap();
+                continue;
+            }
+            Some(slot) if slot.due > now => {
+                let timeout = slot.due - now;
+                state = shared.wake.wait_timeout(state, timeout).unwrap().0;
+                continue;
+            }
+            Some(_) => {}
+        }
+
+        let mut batch = Vec::new();
+        while batch.len() < MAX_BATCH {
+            match state.queue.peek() {
+                Some(slot) if slot.due <= now => {
+                    batch.push(state.queue.pop().unwrap().runner);
+                }
+                _ => break,
+            }
+        }
+
+        // Blocks while all workers are busy (backpressure)
+        drop(state);
+        if tx.send(batch).is_err() {
+            return;
+        }
+        state = shared.state.lock().unwrap();
+    }
+}
+
+fn run_worker<G: Game>(shared: &Shared<G>, rx: &Mutex<Receiver<Vec<TickRunner<G>>>>) {
+    loop {
+        let batch = match rx.lock().unwrap().recv() {
+            Ok(batch) => batch,
+            Err(_) => return,
+        };
+
+        for mut runner in batch {
+            let done = runner.run_due(Instant::now());
+            if !done {
+                shared.push(runner);
+            }
+        }
+    }
+}
@@ -1,18 +1,22 @@
 use crate::errors::{CreateMatchError, JoinError, MatchError, ObserveNextError, SubmitError};
 use crate::match_handle::MatchHandle;
-use crate::tick_loop::spawn_tick_loop;
-use crate::types::{EventCursor, MatchInfo, ServerConfig, ServerEvent, SessionToken};
-use sim_core::{ActionId, Game, MatchId, Tick};
+use crate::metrics::{write_header, ServerMetrics};
+use crate::scheduler::Scheduler;
+use crate::subscription::MatchSubscription;
+use crate::tick_loop::TickLoopConfig;
+use crate::types::{EventCursor, MatchInfo, MatchStatus, ServerConfig, ServerEvent, SessionToken};
+use sim_core::{ActionId, Game, MatchId, PlayerId, Tick};
 use sim_host::MatchHost;
 use std::collections::HashMap;
+use std::fmt::Write;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tokio::task::JoinHandle;
 
 struct MatchEntry<G: Game> {
     handle: MatchHandle<G>,
-    task: JoinHandle<()>,
 }
 
 /// Game server that manages multiple concurrent matches.
@@ -20,6 +24,8 @@ pub struct GameServer<G: Game> {
     pub config: ServerConfig,
     matches: Arc<RwLock<HashMap<MatchId, MatchEntry<G>>>>,
     next_match_id: AtomicU64,
+    metrics: Arc<ServerMetrics>,
+    scheduler: Scheduler<G>,
 }
 
 impl<G: Game + Send + 'static> GameServer<G>
@@ -31,20 +37,39 @@ where
 {
     /// Create a new game server with the given configuration.
     pub fn new(config: ServerConfig) -> Self {
+        let worker_threads = match config.worker_threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
         Self {
             config,
             matches: Arc::new(RwLock::new(HashMap::new())),
             next_match_id: AtomicU64::new(1),
+            metrics: Arc::new(ServerMetrics::default()),
+            scheduler: Scheduler::new(worker_threads),
+        }
+    }
+
+    fn tick_loop_config(&self) -> TickLoopConfig {
+        let tick_duration = Duration::from_secs_f64(1.0 / self.config.simulation_rate as f64);
+        TickLoopConfig {
+            step_budget: self.config.step_budget.unwrap_or(tick_duration),
+            max_catch_up_ticks: self.config.max_catch_up_ticks,
         }
     }
 
+    /// Server-wide metrics (action counts, observe_next waiters, ...).
+    pub fn metrics(&self) -> &ServerMetrics {
+        &self.metrics
+    }
+
     /// Shutdown the server, terminating all matches.
     pub async fn shutdown(&self) {
         let mut matches = self.matches.write().await;
 
+        // The scheduler drops each match on its next dispatch
         for (_, entry) in matches.drain() {
             entry.handle.request_shutdown();
-            let _ = entry.task.await;
         }
     }
 
@@ -79,12 +104,14 @@ where
             self.config.interaction_rate,
         );
 
-        let task = spawn_tick_loop(handle.clone());
+        self.scheduler
+            .register(handle.clone(), self.tick_loop_config());
 
-        let entry = MatchEntry { handle, task };
+        let entry = MatchEntry { handle };
 
         let mut matches = self.matches.write().await;
         matches.insert(match_id, entry);
+        self.metrics.matches_created.inc();
 
         Ok(match_id)
     }
@@ -95,15 +122,17 @@ where
         let mut infos = Vec::with_capacity(matches.len());
 
         for (&match_id, entry) in matches.iter() {
-            let status = entry.handle.status().await;
-            let current_tick = entry.handle.current_tick().await;
-            let player_count = entry.handle.player_count().await;
+            let snapshot = entry.handle.snapshot();
+            let metrics = entry.handle.metrics();
 
             infos.push(MatchInfo {
                 match_id,
-                status,
-                current_tick,
-                player_count,
+                status: snapshot.status,
+                current_tick: snapshot.tick,
+                player_count: snapshot.player_count,
+                lag_ticks: metrics.lag_ticks.get().max(0) as u64,
+                last_step_micros: metrics.last_step_micros.get().max(0) as u64,
+                step_overruns: metrics.step_overruns.get(),
             });
         }
 
@@ -116,7 +145,6 @@ where
 
         if let Some(entry) = matches.remove(&match_id) {
             entry.handle.terminate().await;
-            let _ = entry.task.await;
             Ok(())
         } else {
             Err(MatchError::NotFound)
@@ -179,12 +207,26 @@ where
     ) -> Result<(ActionId, Tick), SubmitError> {
         let matches = self.matches.read().await;
 
-        let entry = matches.get(&match_id).ok_or(SubmitError::NotFound)?;
+        let Some(entry) = matches.get(&match_id) else {
+            self.metrics.record_action_rejected("not_found");
+            return Err(SubmitError::NotFound);
+        };
 
-        entry
+        let result = entry
             .handle
             .submit_action(session, action, intended_tick)
-            .await
+            .await;
+
+        match &result {
+            Ok(_) => self.metrics.actions_submitted.inc(),
+            Err(SubmitError::NotFound) => self.metrics.record_action_rejected("not_found"),
+            Err(SubmitError::InvalidSession) => {
+                self.metrics.record_action_rejected("invalid_session")
+            }
+            Err(SubmitError::Terminated) => self.metrics.record_action_rejected("terminated"),
+        }
+
+        result
     }
 
     /// Get the current observation for a player.
@@ -204,6 +246,35 @@ where
             .ok_or(MatchError::InvalidSession)
     }
 
+    /// Get the current spectator view of a match without a session.
+    pub async fn observe_spectator(&self, match_id: MatchId) -> Result<G::Observation, MatchError> {
+        let matches = self.matches.read().await;
+
+        let entry = matches.get(&match_id).ok_or(MatchError::NotFound)?;
+
+        Ok(entry.handle.observe_spectator().await)
+    }
+
+    /// Compute something from the live game state of a match, for queries the
+    /// observation doesn't answer. See [`MatchHandle::inspect`].
+    pub async fn inspect<R>(
+        &self,
+        match_id: MatchId,
+        session: SessionToken,
+        f: impl FnOnce(&G, PlayerId) -> R,
+    ) -> Result<R, MatchError> {
+        let handle = {
+            let matches = self.matches.read().await;
+            let entry = matches.get(&match_id).ok_or(MatchError::NotFound)?;
+            entry.handle.clone()
+        };
+
+        handle
+            .inspect(session, f)
+            .await
+            .ok_or(MatchError::InvalidSession)
+    }
+
     /// Wait for the next decision tick observation (long-poll).
     /// Returns (observation, timed_out).
     pub async fn observe_next(
@@ -219,7 +290,36 @@ where
             entry.handle.clone()
         };
 
-        handle.observe_next(session, after_tick, max_wait_ms).await
+        let result = {
+            let _waiting = self.metrics.observe_next_waiters.track();
+            handle.observe_next(session, after_tick, max_wait_ms).await
+        };
+
+        if let Ok((_, true)) = result {
+            self.metrics.observe_next_timeouts.inc();
+        }
+
+        result
+    }
+
+    /// Subscribe to observations pushed as ticks complete.
+    /// See [`MatchHandle::subscribe`].
+    pub async fn subscribe(
+        &self,
+        match_id: MatchId,
+        session: SessionToken,
+        decision_only: bool,
+    ) -> Result<MatchSubscription<G>, MatchError> {
+        let handle = {
+            let matches = self.matches.read().await;
+            let entry = matches.get(&match_id).ok_or(MatchError::NotFound)?;
+            entry.handle.clone()
+        };
+
+        handle
+            .subscribe(session, decision_only)
+            .await
+            .ok_or(MatchError::InvalidSession)
     }
 
     /// Poll events from the given cursor.
@@ -246,6 +346,151 @@ where
 
         let entry = matches.get(&match_id).ok_or(MatchError::NotFound)?;
 
-        Ok(entry.handle.current_tick().await)
+        Ok(entry.handle.current_tick())
+    }
+
+    /// Render all server and per-match metrics in the Prometheus text exposition format.
+    pub async fn render_metrics(&self) -> String {
+        let mut out = String::new();
+
+        let handles: Vec<(MatchId, MatchHandle<G>)> = {
+            let matches = self.matches.read().await;
+            matches
+                .iter()
+                .map(|(&match_id, entry)| (match_id, entry.handle.clone()))
+                .collect()
+        };
+
+        let mut by_status = [0u64; 4];
+        for (_, handle) in &handles {
+            let index = match handle.status() {
+                MatchStatus::WaitingForPlayers { .. } => 0,
+                MatchStatus::Running => 1,
+                MatchStatus::Finished(_) => 2,
+                MatchStatus::Terminated => 3,
+            };
+            by_status[index] += 1;
+        }
+
+        write_header(&mut out, "sim_matches", "gauge", "Active matches by status.");
+        for (status, count) in ["waiting", "running", "finished", "terminated"]
+            .iter()
+            .zip(by_status)
+        {
+            let _ = writeln!(out, "sim_matches{{status=\"{status}\"}} {count}");
+        }
+
+        write_header(
+            &mut out,
+            "sim_matches_created_total",
+            "counter",
+            "Matches created since server start.",
+        );
+        let _ = writeln!(
+            out,
+            "sim_matches_created_total {}",
+            self.metrics.matches_created.get()
+        );
+
+        write_header(
+            &mut out,
+            "sim_tick_lag_seconds",
+            "gauge",
+            "How far game time lags behind wall-clock time at the target tick rate.",
+        );
+        for (match_id, handle) in &handles {
+            let lag = handle.metrics().lag_ticks.get() as f64 / handle.tick_hz() as f64;
+            let _ = writeln!(out, "sim_tick_lag_seconds{{match_id=\"{match_id}\"}} {lag}");
+        }
+
+        write_header(
+            &mut out,
+            "sim_ticks_skipped_total",
+            "counter",
+            "Ticks dropped because the tick loop fell behind its interval.",
+        );
+        for (match_id, handle) in &handles {
+            let _ = writeln!(
+                out,
+                "sim_ticks_skipped_total{{match_id=\"{match_id}\"}} {}",
+                handle.metrics().ticks_skipped.get()
+            );
+        }
+
+        write_header(
+            &mut out,
+            "sim_step_overruns_total",
+            "counter",
+            "Steps that took longer than the per-tick budget.",
+        );
+        for (match_id, handle) in &handles {
+            let _ = writeln!(
+                out,
+                "sim_step_overruns_total{{match_id=\"{match_id}\"}} {}",
+                handle.metrics().step_overruns.get()
+            );
+        }
+
+        write_header(
+            &mut out,
+            "sim_step_duration_seconds",
+            "histogram",
+            "Wall-clock time spent stepping one tick.",
+        );
+        for (match_id, handle) in &handles {
+            handle.metrics().step_duration.render(
+                &mut out,
+                "sim_step_duration_seconds",
+                &format!("match_id=\"{match_id}\""),
+            );
+        }
+
+        write_header(
+            &mut out,
+            "sim_actions_submitted_total",
+            "counter",
+            "Actions accepted and scheduled for execution.",
+        );
+        let _ = writeln!(
+            out,
+            "sim_actions_submitted_total {}",
+            self.metrics.actions_submitted.get()
+        );
+
+        write_header(
+            &mut out,
+            "sim_actions_rejected_total",
+            "counter",
+            "Actions rejected before reaching the simulation, by reason.",
+        );
+        for (reason, count) in self.metrics.actions_rejected.snapshot() {
+            let _ = writeln!(out, "sim_actions_rejected_total{{reason=\"{reason}\"}} {count}");
+        }
+
+        write_header(
+            &mut out,
+            "sim_observe_next_waiters",
+            "gauge",
+            "observe_next calls currently waiting for a decision tick.",
+        );
+        let _ = writeln!(
+            out,
+            "sim_observe_next_waiters {}",
+            self.metrics.observe_next_waiters.get()
+        );
+
+        write_header(
+            &mut out,
+            "sim_observe_next_timeouts_total",
+            "counter",
+            "observe_next calls that timed out before a new decision tick.",
+        );
+        let _ = writeln!(
+            out,
+            "sim_observe_next_timeouts_total {}",
+            self.metrics.observe_next_timeouts.get()
+        );
+
+        out
     }
 }
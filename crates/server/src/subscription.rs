@@ -0,0 +1,94 @@
+use crate::errors::SubscriptionError;
+use crate::match_handle::{MatchSnapshot, MatchUpdate};
+use crate::types::{MatchStatus, ServerEvent, SessionToken, TickUpdate};
+use sim_core::Game;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// A push-based stream of observations for one session, created by
+/// [`MatchHandle::subscribe`](crate::MatchHandle::subscribe).
+///
+/// The first update is the current state at subscription time; after that one update is
+/// delivered per completed tick (or decision tick). The subscription closes after the
+/// update that finishes or terminates the match.
+pub struct MatchSubscription<G: Game> {
+    rx: broadcast::Receiver<MatchUpdate<G>>,
+    session: SessionToken,
+    decision_only: bool,
+    initial: Option<Arc<MatchSnapshot<G>>>,
+    pending_events: Vec<ServerEvent<G::Event>>,
+    closed: bool,
+}
+
+impl<G: Game> MatchSubscription<G> {
+    pub(crate) fn new(
+        rx: broadcast::Receiver<MatchUpdate<G>>,
+        snapshot: Arc<MatchSnapshot<G>>,
+        session: SessionToken,
+        decision_only: bool,
+    ) -> Self {
+        Self {
+            rx,
+            session,
+            decision_only,
+            initial: Some(snapshot),
+            pending_events: Vec::new(),
+            closed: false,
+        }
+    }
+
+    /// Wait for the next update.
+    pub async fn recv(&mut self) -> Result<TickUpdate<G::Observation, G::Event>, SubscriptionError> {
+        if let Some(snapshot) = self.initial.take() {
+            return self.deliver(&snapshot, true);
+        }
+
+        loop {
+            if self.closed {
+                return Err(SubscriptionError::Closed);
+            }
+
+            let update = match self.rx.recv().await {
+                Ok(update) => update,
+                Err(RecvError::Lagged(n)) => return Err(SubscriptionError::Lagged(n)),
+                Err(RecvError::Closed) => {
+                    self.closed = true;
+                    return Err(SubscriptionError::Closed);
+                }
+            };
+
+            self.pending_events.extend(update.events.iter().cloned());
+            if self.decision_only && !update.decision {
+                continue;
+            }
+            return self.deliver(&update.snapshot, update.decision);
+        }
+    }
+
+    fn deliver(
+        &mut self,
+        snapshot: &MatchSnapshot<G>,
+        decision: bool,
+    ) -> Result<TickUpdate<G::Observation, G::Event>, SubscriptionError> {
+        let Some(observation) = snapshot.observation(self.session) else {
+            // The session left the match
+            self.closed = true;
+            return Err(SubscriptionError::Closed);
+        };
+
+        if matches!(
+            snapshot.status,
+            MatchStatus::Finished(_) | MatchStatus::Terminated
+        ) {
+            self.closed = true;
+        }
+
+        Ok(TickUpdate {
+            tick: snapshot.observed_tick,
+            status: snapshot.status,
+            decision,
+            observation: observation.clone(),
+            events: std::mem::take(&mut self.pending_events),
+        })
+    }
+}
@@ -1,48 +1,222 @@
 use crate::match_handle::MatchHandle;
 use sim_core::Game;
-use std::time::Duration;
-use tokio::time::{interval, MissedTickBehavior};
+use std::time::{Duration, Instant};
 
-/// Run the tick loop for a match.
-/// This function runs until the match finishes or shutdown is requested.
-pub async fn run_tick_loop<G: Game + Send + 'static>(handle: MatchHandle<G>)
-where
-    G::Action: Send,
-    G::Observation: Send,
-    G::Event: Send,
-    G::Config: Send,
-{
-    let tick_hz = handle.tick_hz();
-    let tick_duration = Duration::from_secs_f64(1.0 / tick_hz as f64);
+/// Minimum time between two overrun warnings for the same match.
+const WARN_INTERVAL: Duration = Duration::from_secs(1);
 
-    let mut interval = interval(tick_duration);
-    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+/// Pacing parameters for stepping a match.
+#[derive(Clone, Copy, Debug)]
+pub struct TickLoopConfig {
+    /// Steps taking longer than this are counted as overruns.
+    pub step_budget: Duration,
+    /// Maximum extra ticks per wake-up when behind. `0` skips missed ticks instead.
+    pub max_catch_up_ticks: u32,
+}
 
-    loop {
-        interval.tick().await;
+/// Keeps game time aligned with wall-clock time.
+///
+/// Tracks how many ticks are owed since the loop started and decides how many to run on
+/// each wake-up. Without catch-up, owed ticks beyond the first are dropped (and counted
+/// as skipped); with catch-up, up to `1 + max_catch_up_ticks` are run and the remainder
+/// carries over to the next wake-up.
+#[derive(Debug)]
+pub struct TickPacer {
+    tick_duration: Duration,
+    max_catch_up_ticks: u32,
+    start: Instant,
+    stepped: u64,
+    skipped: u64,
+}
 
-        if handle.should_shutdown() {
-            break;
+impl TickPacer {
+    pub fn new(tick_duration: Duration, max_catch_up_ticks: u32, start: Instant) -> Self {
+        Self {
+            tick_duration,
+            max_catch_up_ticks,
+            start,
+            stepped: 0,
+            skipped: 0,
         }
+    }
 
-        let finished = handle.step_one_tick().await;
+    /// Ticks that should have run by `now` (the first tick is due immediately).
+    fn expected(&self, now: Instant) -> u64 {
+        let elapsed = now.saturating_duration_since(self.start);
+        (elapsed.as_nanos() / self.tick_duration.as_nanos().max(1)) as u64 + 1
+    }
 
-        if finished {
-            break;
+    /// Number of ticks to step at `now`. Returns `(steps, newly_skipped)`.
+    pub fn due(&mut self, now: Instant) -> (u32, u64) {
+        let owed = self
+            .expected(now)
+            .saturating_sub(self.stepped + self.skipped);
+        if owed == 0 {
+            return (0, 0);
         }
+
+        let max_steps = 1 + self.max_catch_up_ticks as u64;
+        let steps = owed.min(max_steps);
+
+        let skipped = if self.max_catch_up_ticks == 0 {
+            owed - steps
+        } else {
+            0
+        };
+        self.skipped += skipped;
+
+        (steps as u32, skipped)
+    }
+
+    /// Record that one tick was stepped.
+    pub fn record_step(&mut self) {
+        self.stepped += 1;
+    }
+
+    /// When the next owed tick is due. In the past if the match is behind.
+    pub fn next_due(&self) -> Instant {
+        let accounted = (self.stepped + self.skipped) as u32;
+        self.start + self.tick_duration * accounted
+    }
+
+    /// How many ticks game time is behind wall-clock time at `now`.
+    pub fn lag_ticks(&self, now: Instant) -> u64 {
+        self.expected(now).saturating_sub(self.stepped)
     }
 }
 
-/// Spawn a tick loop as a tokio task.
-/// Returns a JoinHandle that can be used to wait for the loop to finish.
-pub fn spawn_tick_loop<G: Game + Send + 'static>(
+/// Per-match pacing state driven by the scheduler.
+///
+/// Each time the scheduler dispatches a match, the runner steps the ticks that are due
+/// (one, or up to `1 + max_catch_up_ticks` when behind) and records step metrics.
+pub struct TickRunner<G: Game> {
     handle: MatchHandle<G>,
-) -> tokio::task::JoinHandle<()>
-where
-    G::Action: Send,
-    G::Observation: Send,
-    G::Event: Send,
-    G::Config: Send,
-{
-    tokio::spawn(run_tick_loop(handle))
+    pacer: TickPacer,
+    config: TickLoopConfig,
+    last_warning: Option<Instant>,
+}
+
+impl<G: Game> TickRunner<G> {
+    pub fn new(handle: MatchHandle<G>, config: TickLoopConfig, start: Instant) -> Self {
+        let tick_duration = Duration::from_secs_f64(1.0 / handle.tick_hz() as f64);
+        Self {
+            handle,
+            pacer: TickPacer::new(tick_duration, config.max_catch_up_ticks, start),
+            config,
+            last_warning: None,
+        }
+    }
+
+    /// When the next owed tick is due.
+    pub fn next_due(&self) -> Instant {
+        self.pacer.next_due()
+    }
+
+    /// Step all ticks due at `now`, blocking on the match lock.
+    /// Returns true if the match finished or shutdown was requested.
+    pub fn run_due(&mut self, now: Instant) -> bool {
+        if self.handle.should_shutdown() {
+            return true;
+        }
+
+        let (steps, skipped) = self.pacer.due(now);
+        let metrics = self.handle.metrics();
+        if skipped > 0 {
+            metrics.ticks_skipped.add(skipped);
+        }
+
+        let mut finished = false;
+        for _ in 0..steps {
+            let step_start = Instant::now();
+            finished = self.handle.step_one_tick_blocking();
+            let step_time = step_start.elapsed();
+            self.pacer.record_step();
+
+            metrics.step_duration.observe(step_time);
+            metrics.last_step_micros.set(step_time.as_micros() as i64);
+
+            if step_time > self.config.step_budget {
+                metrics.step_overruns.inc();
+                let now = Instant::now();
+                if self.last_warning.is_none_or(|t| now - t >= WARN_INTERVAL) {
+                    self.last_warning = Some(now);
+                    tracing::warn!(
+                        step_ms = step_time.as_secs_f64() * 1000.0,
+                        budget_ms = self.config.step_budget.as_secs_f64() * 1000.0,
+                        lag_ticks = self.pacer.lag_ticks(now),
+                        overruns = metrics.step_overruns.get(),
+                        "tick step exceeded budget"
+                    );
+                }
+            }
+
+            if finished || self.handle.should_shutdown() {
+                return true;
+            }
+        }
+
+        metrics
+            .lag_ticks
+            .set(self.pacer.lag_ticks(Instant::now()) as i64);
+
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_on_time_steps_once() {
+        let start = Instant::now();
+        let mut pacer = TickPacer::new(TICK, 0, start);
+
+        assert_eq!(pacer.due(start), (1, 0));
+        pacer.record_step();
+        assert_eq!(pacer.due(start + Duration::from_millis(5)), (0, 0));
+        assert_eq!(pacer.due(start + TICK), (1, 0));
+        pacer.record_step();
+        assert_eq!(pacer.lag_ticks(start + TICK), 0);
+    }
+
+    #[test]
+    fn test_skip_mode_drops_missed_ticks() {
+        let start = Instant::now();
+        let mut pacer = TickPacer::new(TICK, 0, start);
+        assert_eq!(pacer.due(start), (1, 0));
+        pacer.record_step();
+
+        // Woke up 5 periods late: ticks 1..=5 owed, run one and skip four
+        assert_eq!(pacer.due(start + TICK * 5), (1, 4));
+        pacer.record_step();
+        assert_eq!(pacer.lag_ticks(start + TICK * 5), 4);
+
+        // Skipped ticks are not owed again
+        assert_eq!(pacer.due(start + TICK * 6), (1, 0));
+    }
+
+    #[test]
+    fn test_catch_up_mode_repays_owed_ticks() {
+        let start = Instant::now();
+        let mut pacer = TickPacer::new(TICK, 2, start);
+        assert_eq!(pacer.due(start), (1, 0));
+        pacer.record_step();
+
+        // Five ticks owed, at most three per wake-up
+        assert_eq!(pacer.due(start + TICK * 5), (3, 0));
+        for _ in 0..3 {
+            pacer.record_step();
+        }
+        assert_eq!(pacer.lag_ticks(start + TICK * 5), 2);
+
+        // Next wake-up repays the remainder plus the new tick
+        assert_eq!(pacer.due(start + TICK * 6), (3, 0));
+        for _ in 0..3 {
+            pacer.record_step();
+        }
+        assert_eq!(pacer.lag_ticks(start + TICK * 6), 0);
+    }
 }
@@ -1,4 +1,5 @@
 use sim_core::{TerminalOutcome, Tick};
+use std::time::Duration;
 
 /// Identifies a player session within a match.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -24,6 +25,12 @@ pub struct MatchInfo {
     pub status: MatchStatus,
     pub current_tick: Tick,
     pub player_count: u8,
+    /// How many ticks game time is behind wall-clock time at the target tick rate.
+    pub lag_ticks: u64,
+    /// Wall-clock duration of the most recent step, in microseconds.
+    pub last_step_micros: u64,
+    /// Number of steps that exceeded the per-tick budget.
+    pub step_overruns: u64,
 }
 
 /// An event from the server with sequence number for cursor tracking.
@@ -34,6 +41,19 @@ pub struct ServerEvent<E> {
     pub event: E,
 }
 
+/// An observation pushed to a subscriber when a tick completes.
+#[derive(Clone, Debug)]
+pub struct TickUpdate<O, E> {
+    /// Tick of `observation`.
+    pub tick: Tick,
+    pub status: MatchStatus,
+    /// Whether `tick` is a decision tick.
+    pub decision: bool,
+    pub observation: O,
+    /// Events produced since the previous update delivered to this subscriber.
+    pub events: Vec<ServerEvent<E>>,
+}
+
 /// Configuration for the game server.
 #[derive(Clone, Debug)]
 pub struct ServerConfig {
@@ -46,6 +66,15 @@ pub struct ServerConfig {
     pub max_matches: usize,
     /// Capacity of the event buffer per match.
     pub event_buffer_capacity: usize,
+    /// Wall-clock budget for a single step. Steps taking longer are counted as overruns
+    /// and logged. `None` uses the tick period (`1 / simulation_rate`).
+    pub step_budget: Option<Duration>,
+    /// Catch-up mode: maximum number of extra ticks a match may run per wake-up when it
+    /// has fallen behind wall-clock time. `0` disables catch-up and missed ticks are
+    /// skipped, so the match runs slower than real time.
+    pub max_catch_up_ticks: u32,
+    /// Number of worker threads stepping matches. `0` uses the available parallelism.
+    pub worker_threads: usize,
 }
 
 impl Default for ServerConfig {
@@ -55,6 +84,9 @@ impl Default for ServerConfig {
             interaction_rate: 1,
             max_matches: 100,
             event_buffer_capacity: 1024,
+            step_budget: None,
+            max_catch_up_ticks: 0,
+            worker_threads: 0,
         }
     }
 }
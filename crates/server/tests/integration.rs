@@ -1,5 +1,9 @@
 use sim_core::{ActionEnvelope, Game, PlayerId, TerminalOutcome, Tick};
-use sim_server::{EventCursor, GameServer, MatchStatus, ServerConfig};
+use sim_host::MatchHost;
+use sim_server::{
+    EventCursor, GameServer, MatchError, MatchHandle, MatchStatus, ServerConfig, SessionToken,
+    SubscriptionError,
+};
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
@@ -9,6 +13,7 @@ use tokio::time::sleep;
 struct CounterGame {
     counter: u64,
     target: u64,
+    #[allow(dead_code)]
     events: Vec<CounterEvent>,
 }
 
@@ -24,11 +29,13 @@ enum CounterAction {
 
 #[derive(Clone, Debug)]
 struct CounterObservation {
+    tick: Tick,
     counter: u64,
     target: u64,
 }
 
 #[derive(Clone, Debug)]
+#[allow(dead_code)]
 enum CounterEvent {
     Incremented { amount: u64, new_value: u64 },
     TickAdvanced { tick: Tick },
@@ -68,8 +75,9 @@ impl Game for CounterGame {
         out_events.push(CounterEvent::TickAdvanced { tick });
     }
 
-    fn observe(&self, _tick: Tick, _player: PlayerId) -> Self::Observation {
+    fn observe(&self, tick: Tick, _player: PlayerId) -> Self::Observation {
         CounterObservation {
+            tick,
             counter: self.counter,
             target: self.target,
         }
@@ -91,6 +99,7 @@ async fn test_create_and_list_matches() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -116,6 +125,7 @@ async fn test_join_and_observe() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -146,6 +156,7 @@ async fn test_submit_action_and_poll_events() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -203,6 +214,7 @@ async fn test_game_terminates() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -244,6 +256,7 @@ async fn test_terminate_match() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -270,6 +283,7 @@ async fn test_multiple_players() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -320,6 +334,7 @@ async fn test_determinism() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     // Run the same scenario twice with same seed
@@ -378,6 +393,7 @@ async fn test_leave_match() {
         interaction_rate: 4,
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -406,6 +422,7 @@ async fn test_max_matches() {
         interaction_rate: 4,
         max_matches: 2,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -436,6 +453,7 @@ async fn test_observe_next_returns_immediately_for_cached_data() {
         interaction_rate: 10, // Decision every 10 ticks (100ms)
         max_matches: 10,
         event_buffer_capacity: 100,
+        ..ServerConfig::default()
     };
 
     let server: GameServer<CounterGame> = GameServer::new(config);
@@ -483,3 +501,329 @@ async fn test_observe_next_returns_immediately_for_cached_data() {
 
     server.shutdown().await;
 }
+
+#[tokio::test]
+async fn test_metrics_render() {
+    let config = ServerConfig {
+        simulation_rate: 100,
+        interaction_rate: 10,
+        max_matches: 10,
+        event_buffer_capacity: 100,
+        ..ServerConfig::default()
+    };
+
+    let server: GameServer<CounterGame> = GameServer::new(config);
+    let match_id = server
+        .create_match(CounterConfig { target: 1000 }, 42)
+        .await
+        .unwrap();
+    let (session, _) = server.join_match(match_id).await.unwrap();
+
+    server
+        .submit_action(match_id, session, CounterAction::Increment(1), 0)
+        .await
+        .unwrap();
+    let bogus = sim_server::SessionToken(9999);
+    assert!(server
+        .submit_action(match_id, bogus, CounterAction::Increment(1), 0)
+        .await
+        .is_err());
+
+    // Short wait guarantees a timeout once the bootstrap call has consumed tick 0
+    server.observe_next(match_id, session, 0, 10).await.unwrap();
+    let tick = server.current_tick(match_id).await.unwrap();
+    let (_, timed_out) = server
+        .observe_next(match_id, session, tick + 1000, 1)
+        .await
+        .unwrap();
+    assert!(timed_out);
+
+    sleep(Duration::from_millis(50)).await;
+
+    let text = server.render_metrics().await;
+    assert!(text.contains("sim_matches{status=\"running\"} 1\n"));
+    assert!(text.contains("sim_matches_created_total 1\n"));
+    assert!(text.contains("sim_actions_submitted_total 1\n"));
+    assert!(text.contains("sim_actions_rejected_total{reason=\"invalid_session\"} 1\n"));
+    assert!(text.contains("sim_observe_next_timeouts_total 1\n"));
+    assert!(text.contains("sim_observe_next_waiters 0\n"));
+    assert!(text.contains(&format!(
+        "sim_step_duration_seconds_bucket{{match_id=\"{match_id}\",le=\"+Inf\"}}"
+    )));
+
+    let metrics = server.metrics();
+    assert_eq!(metrics.actions_submitted.get(), 1);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_step_overruns_reported_in_match_info() {
+    let config = ServerConfig {
+        simulation_rate: 100,
+        interaction_rate: 10,
+        max_matches: 10,
+        event_buffer_capacity: 100,
+        // Every step exceeds a zero budget
+        step_budget: Some(Duration::ZERO),
+        max_catch_up_ticks: 4,
+        ..ServerConfig::default()
+    };
+
+    let server: GameServer<CounterGame> = GameServer::new(config);
+    let match_id = server
+        .create_match(CounterConfig { target: 1000 }, 42)
+        .await
+        .unwrap();
+    server.join_match(match_id).await.unwrap();
+
+    sleep(Duration::from_millis(100)).await;
+
+    let info = server
+        .list_matches()
+        .await
+        .into_iter()
+        .find(|m| m.match_id == match_id)
+        .unwrap();
+    assert!(info.step_overruns > 0);
+    assert!(info.current_tick > 0);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_many_matches_share_worker_pool() {
+    let config = ServerConfig {
+        simulation_rate: 100,
+        interaction_rate: 10,
+        max_matches: 100,
+        event_buffer_capacity: 100,
+        worker_threads: 2,
+        ..ServerConfig::default()
+    };
+
+    let server: GameServer<CounterGame> = GameServer::new(config);
+    let mut match_ids = Vec::new();
+    for seed in 0..50 {
+        let match_id = server
+            .create_match(CounterConfig { target: 1000 }, seed)
+            .await
+            .unwrap();
+        server.join_match(match_id).await.unwrap();
+        match_ids.push(match_id);
+    }
+
+    sleep(Duration::from_millis(200)).await;
+
+    // Every match advances, none is starved by the others
+    let matches = server.list_matches().await;
+    assert_eq!(matches.len(), 50);
+    for info in &matches {
+        assert!(matches!(info.status, MatchStatus::Running));
+        assert!(info.current_tick > 0, "match {} never stepped", info.match_id);
+    }
+
+    // Terminated matches stop stepping
+    let stopped = match_ids[0];
+    server.terminate_match(stopped).await.unwrap();
+    assert!(server.current_tick(stopped).await.is_err());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_snapshot_reflects_membership_without_stepping() {
+    let config = ServerConfig {
+        simulation_rate: 100,
+        interaction_rate: 10,
+        max_matches: 10,
+        event_buffer_capacity: 100,
+        ..ServerConfig::default()
+    };
+
+    let server: GameServer<CounterGame> = GameServer::new(config);
+    let match_id = server
+        .create_match_with_players(CounterConfig { target: 1000 }, 42, 2)
+        .await
+        .unwrap();
+
+    // Joins and spectators are visible immediately, before any tick is stepped
+    let (player, _) = server.join_match(match_id).await.unwrap();
+    let spectator = server.spectate_match(match_id).await.unwrap();
+    let info = server.list_matches().await.remove(0);
+    assert_eq!(info.player_count, 1);
+    assert_eq!(info.current_tick, 0);
+    assert!(matches!(
+        info.status,
+        MatchStatus::WaitingForPlayers {
+            current: 1,
+            required: 2
+        }
+    ));
+    assert_eq!(server.observe(match_id, player).await.unwrap().target, 1000);
+    assert_eq!(server.observe(match_id, spectator).await.unwrap().target, 1000);
+
+    server.join_match(match_id).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+    let info = server.list_matches().await.remove(0);
+    assert!(matches!(info.status, MatchStatus::Running));
+    assert!(info.current_tick > 0);
+
+    // Sessions that left can no longer observe
+    server.leave_match(match_id, spectator).await.unwrap();
+    assert!(server.observe(match_id, spectator).await.is_err());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_observe_spectator_without_session() {
+    let config = ServerConfig {
+        simulation_rate: 100,
+        interaction_rate: 10,
+        max_matches: 10,
+        event_buffer_capacity: 100,
+        ..ServerConfig::default()
+    };
+
+    let server: GameServer<CounterGame> = GameServer::new(config);
+    let match_id = server
+        .create_match_with_players(CounterConfig { target: 1000 }, 42, 1)
+        .await
+        .unwrap();
+
+    // Available before anyone joins
+    let obs = server.observe_spectator(match_id).await.unwrap();
+    assert_eq!(obs.target, 1000);
+
+    // Sees players' actions once applied
+    let (session, _player_id) = server.join_match(match_id).await.unwrap();
+    let tick = server.current_tick(match_id).await.unwrap();
+    server
+        .submit_action(match_id, session, CounterAction::Increment(5), tick + 2)
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(100)).await;
+    let obs = server.observe_spectator(match_id).await.unwrap();
+    assert_eq!(obs.counter, 5);
+
+    assert!(server.observe_spectator(match_id + 1).await.is_err());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_inspect_game_state() {
+    let config = ServerConfig {
+        simulation_rate: 100,
+        interaction_rate: 10,
+        max_matches: 10,
+        event_buffer_capacity: 100,
+        ..ServerConfig::default()
+    };
+
+    let server: GameServer<CounterGame> = GameServer::new(config);
+    let match_id = server
+        .create_match_with_players(CounterConfig { target: 1000 }, 42, 1)
+        .await
+        .unwrap();
+    let (session, player_id) = server.join_match(match_id).await.unwrap();
+
+    let (target, player) = server
+        .inspect(match_id, session, |game, player_id| (game.target, player_id))
+        .await
+        .unwrap();
+    assert_eq!(target, 1000);
+    assert_eq!(player, player_id);
+
+    assert!(matches!(
+        server.inspect(match_id, SessionToken(999), |_, _| ()).await,
+        Err(MatchError::InvalidSession)
+    ));
+    assert!(matches!(
+        server.inspect(match_id + 1, session, |_, _| ()).await,
+        Err(MatchError::NotFound)
+    ));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_snapshot_observes_only_when_seen() {
+    // 100 Hz with decisions at 10 Hz: every 10th tick
+    let host = MatchHost::new(CounterConfig { target: 1000 }, 42, 100);
+    let handle: MatchHandle<CounterGame> = MatchHandle::new(host, 100, 1, 10);
+    let (session, _) = handle.join_player().await.unwrap();
+
+    for _ in 0..15 {
+        handle.step_one_tick().await;
+    }
+    let snapshot = handle.snapshot();
+    assert_eq!((snapshot.tick, snapshot.observed_tick), (15, 10));
+
+    // Reads between decision ticks observe the live game
+    assert_eq!(handle.observe(session).await.unwrap().tick, 15);
+    assert_eq!(handle.observe_spectator().await.tick, 15);
+
+    // With a subscriber, every tick is observed
+    let mut subscription = handle.subscribe(session, false).await.unwrap();
+    assert_eq!(subscription.recv().await.unwrap().tick, 10);
+    handle.step_one_tick().await;
+    let update = subscription.recv().await.unwrap();
+    assert_eq!((update.tick, update.observation.tick), (16, 16));
+    assert_eq!(handle.snapshot().observed_tick, 16);
+}
+
+#[tokio::test]
+async fn test_subscription_pushes_every_tick() {
+    let config = ServerConfig {
+        simulation_rate: 100,
+        interaction_rate: 10,
+        max_matches: 10,
+        event_buffer_capacity: 100,
+        ..ServerConfig::default()
+    };
+
+    let server: GameServer<CounterGame> = GameServer::new(config);
+    let match_id = server
+        .create_match(CounterConfig { target: 1000 }, 42)
+        .await
+        .unwrap();
+    let (session, _) = server.join_match(match_id).await.unwrap();
+
+    let mut all_ticks = server.subscribe(match_id, session, false).await.unwrap();
+    let mut decisions = server.subscribe(match_id, session, true).await.unwrap();
+
+    // Initial state, then one update per tick with no gaps or duplicates
+    let first = all_ticks.recv().await.unwrap();
+    let mut last_tick = first.tick;
+    for _ in 0..5 {
+        let update = all_ticks.recv().await.unwrap();
+        assert_eq!(update.tick, last_tick + 1);
+        assert_eq!(update.events.len(), 1);
+        last_tick = update.tick;
+    }
+
+    // Decision-only subscribers accumulate events between decision ticks
+    decisions.recv().await.unwrap();
+    let update = decisions.recv().await.unwrap();
+    assert!(update.decision);
+    assert_eq!(update.tick % 10, 0);
+    assert!(!update.events.is_empty());
+
+    server.terminate_match(match_id).await.unwrap();
+    let last = loop {
+        match decisions.recv().await {
+            Ok(update) => {
+                if update.status == MatchStatus::Terminated {
+                    break update;
+                }
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    };
+    assert_eq!(last.status, MatchStatus::Terminated);
+    assert_eq!(decisions.recv().await.unwrap_err(), SubscriptionError::Closed);
+
+    server.shutdown().await;
+}